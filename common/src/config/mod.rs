@@ -1,9 +1,11 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use crate::diagnostic::Severity;
+
 pub mod schema;
 pub use schema::{build_rc_schema, write_rc_schema, RC_SCHEMA_RELATIVE_PATH};
 
@@ -21,6 +23,9 @@ pub struct Config {
     #[serde(default)]
     pub debug: DebugConfig,
 
+    #[serde(default)]
+    pub format: FormatConfig,
+
     #[serde(default = "default_workdir")]
     pub workdir: PathBuf,
 }
@@ -29,6 +34,10 @@ pub struct Config {
 pub struct ParserConfig {
     #[serde(default = "default_parser_path")]
     pub entry: String,
+
+    /// Severity import cycles are reported with.
+    #[serde(default = "default_import_cycles")]
+    pub import_cycles: Severity,
 }
 /// Re-export for plugins to use without adding toml as a direct dependency.
 pub use toml::Value as ConfigValue;
@@ -61,15 +70,37 @@ pub struct DebugConfig {
     pub ast: bool,
 }
 
+/// Options of `xeno fmt` and editor formatting.
+#[repr(Rust)]
+#[derive(Deserialize, Debug, Clone)]
+pub struct FormatConfig {
+    /// Lines longer than this are wrapped where possible.
+    #[serde(default = "default_format_width")]
+    pub width: usize,
+
+    /// Spaces per indentation level.
+    #[serde(default = "default_format_indent")]
+    pub indent: usize,
+}
+
 fn default_parser_path() -> String {
     "index".to_string()
 }
+fn default_import_cycles() -> Severity {
+    Severity::Warning
+}
 fn default_plugins_path() -> String {
     "".to_string()
 }
 fn default_plugins_list() -> Vec<String> {
     vec![]
 }
+fn default_format_width() -> usize {
+    100
+}
+fn default_format_indent() -> usize {
+    4
+}
 fn default_workdir() -> PathBuf {
     std::env::current_dir().unwrap_or_default()
 }
@@ -85,6 +116,76 @@ impl Config {
     pub fn get() -> &'static Config {
         CONFIG.get_or_init(init_config)
     }
+
+    /// Loads the global config with command line overrides applied on top of `xenomorph.toml`.
+    /// Has to run before the first [`Config::get`], afterwards the loaded config is returned as is.
+    pub fn init(overrides: ConfigOverrides) -> &'static Config {
+        CONFIG.get_or_init(|| {
+            let start_dir = match &overrides.workdir {
+                Some(workdir) => workdir.clone(),
+                None => match std::env::current_dir() {
+                    Ok(path) => path,
+                    Err(_) => {
+                        eprintln!("Error: Unable to get current directory.");
+                        PathBuf::default()
+                    }
+                },
+            };
+            let mut config = match &overrides.config {
+                Some(file) => {
+                    let file = file.canonicalize().unwrap_or_else(|_| file.clone());
+                    let workdir = file
+                        .parent()
+                        .map(|dir| dir.to_path_buf())
+                        .unwrap_or_default();
+                    read_config(workdir, &file)
+                }
+                None => load_config(start_dir),
+            };
+            overrides.apply(&mut config);
+            config
+        })
+    }
+}
+
+/// Settings given on the command line that take precedence over `xenomorph.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    /// Config file to use instead of searching for `xenomorph.toml`, its directory is the workspace root.
+    pub config: Option<PathBuf>,
+    /// Workspace root, also where `xenomorph.toml` is searched from.
+    pub workdir: Option<PathBuf>,
+    /// Entry module relative to the workspace root, with or without the `.xen` extension.
+    pub entry: Option<String>,
+    /// Plugins to load instead of `[plugins] plugins`, either by library or by plugin name.
+    pub plugins: Option<Vec<String>>,
+}
+
+impl ConfigOverrides {
+    fn apply(&self, config: &mut Config) {
+        if let Some(workdir) = &self.workdir {
+            config.workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.clone());
+        }
+        if let Some(entry) = &self.entry {
+            config.parser.entry = entry.strip_suffix(".xen").unwrap_or(entry).to_string();
+        }
+        if let Some(plugins) = &self.plugins {
+            config.plugins.plugins = plugins
+                .iter()
+                .map(|name| Self::plugin_library(&config.plugins.plugins, name))
+                .collect();
+        }
+    }
+
+    /// Library name of a selected plugin: `typescript` picks the configured `xenomorph_typescript`,
+    /// names that aren't configured are taken as library names.
+    fn plugin_library(configured: &[String], name: &str) -> String {
+        configured
+            .iter()
+            .find(|lib| *lib == name || lib.strip_prefix("xenomorph_") == Some(name))
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
 }
 
 impl Default for Config {
@@ -93,6 +194,7 @@ impl Default for Config {
             parser: ParserConfig::default(),
             plugins: PluginsConfig::default(),
             debug: DebugConfig::default(),
+            format: FormatConfig::default(),
             workdir: PathBuf::default(),
         }
     }
@@ -101,6 +203,7 @@ impl Default for ParserConfig {
     fn default() -> Self {
         Self {
             entry: default_parser_path(),
+            import_cycles: default_import_cycles(),
         }
     }
 }
@@ -113,6 +216,14 @@ impl Default for DebugConfig {
         }
     }
 }
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            width: default_format_width(),
+            indent: default_format_indent(),
+        }
+    }
+}
 impl Default for PluginsConfig {
     fn default() -> Self {
         Self {
@@ -139,35 +250,69 @@ fn find_workspace_root(wd: &PathBuf) -> Option<PathBuf> {
 }
 
 fn init_config() -> Config {
-    let current_dir = match std::env::current_dir() {
-        Ok(path) => path,
+    match std::env::current_dir() {
+        Ok(path) => load_config(path),
         Err(_) => {
             eprintln!("Error: Unable to get current directory.");
-            return Config::default();
+            Config::default()
         }
-    };
+    }
+}
 
-    match find_workspace_root(&current_dir) {
-        None => Config::default_with_workdir(current_dir),
+/// Loads `xenomorph.toml` from the closest directory at or above `start_dir`.
+fn load_config(start_dir: PathBuf) -> Config {
+    match find_workspace_root(&start_dir) {
+        None => Config::default_with_workdir(start_dir),
         Some(workdir) => {
-            let content = match fs::read_to_string(workdir.join("xenomorph.toml")) {
-                Ok(content) => content,
-                Err(_) => {
-                    eprintln!("Error: Unable to read config file.");
-                    return Config::default_with_workdir(workdir);
-                }
-            };
+            let file = workdir.join("xenomorph.toml");
+            read_config(workdir, &file)
+        }
+    }
+}
 
-            match toml::de::from_str::<Config>(&content) {
-                Ok(mut config) => {
-                    config.workdir = workdir;
-                    config
-                }
-                Err(_) => {
-                    eprintln!("Error: Unable to parse config file.");
-                    return Config::default_with_workdir(workdir);
-                }
-            }
+fn read_config(workdir: PathBuf, file: &Path) -> Config {
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(_) => {
+            eprintln!("Error: Unable to read config file.");
+            return Config::default_with_workdir(workdir);
+        }
+    };
+
+    match toml::de::from_str::<Config>(&content) {
+        Ok(mut config) => {
+            config.workdir = workdir;
+            config
+        }
+        Err(_) => {
+            eprintln!("Error: Unable to parse config file.");
+            Config::default_with_workdir(workdir)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_entry_and_select_plugins() {
+        let mut config = Config::default();
+        config.plugins.plugins = vec![
+            "xenomorph_typescript".to_string(),
+            "xenomorph_json_schema".to_string(),
+        ];
+        let overrides = ConfigOverrides {
+            entry: Some("api/index.xen".to_string()),
+            plugins: Some(vec!["typescript".to_string(), "my_plugin".to_string()]),
+            ..Default::default()
+        };
+        overrides.apply(&mut config);
+
+        assert_eq!(config.parser.entry, "api/index");
+        assert_eq!(
+            config.plugins.plugins,
+            vec!["xenomorph_typescript".to_string(), "my_plugin".to_string()]
+        );
+    }
+}
@@ -79,6 +79,12 @@ pub fn build_rc_schema(plugins: &[&'static XenoPlugin<'static>]) -> Value {
                         "type": "string",
                         "description": "Entry module path relative to the workspace root, without the `.xen` extension.",
                         "default": "index.xen"
+                    },
+                    "import_cycles": {
+                        "type": "string",
+                        "enum": ["error", "warning", "info", "hint"],
+                        "description": "Severity import cycles are reported with.",
+                        "default": "warning"
                     }
                 },
                 "additionalProperties": false
@@ -106,6 +112,25 @@ pub fn build_rc_schema(plugins: &[&'static XenoPlugin<'static>]) -> Value {
                 },
                 "additionalProperties": false
             },
+            "format": {
+                "type": "object",
+                "description": "Options of `xeno fmt` and editor formatting.",
+                "properties": {
+                    "width": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Lines longer than this are wrapped where possible.",
+                        "default": 100
+                    },
+                    "indent": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Spaces per indentation level.",
+                        "default": 4
+                    }
+                },
+                "additionalProperties": false
+            },
             "workdir": {
                 "type": "string",
                 "description": "Workspace root override. Normally detected automatically from the location of `xenomorph.toml`."
@@ -0,0 +1,550 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::lexer::{Lexer, Token, TokenVariant, XenoTokens};
+use crate::parser::{DeclarationSpan, Parser};
+
+/// Kinds of the nodes and tokens of a [`SyntaxTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// The whole file
+    Root,
+    /// `import a/b { X };` or `export import a/b;`
+    Import,
+    /// `type X = ...;`, with its documentation comment
+    TypeDecl,
+    /// Tokens the parser couldn't turn into a declaration
+    Invalid,
+    /// `<T, U>` after the name of a declared type
+    Generics,
+    /// `{ X, Y as Z }` of a selective import
+    ImportItems,
+    /// `{ ... }` of a struct
+    Struct,
+    /// `{ ... }` after `enum`
+    Enum,
+    /// `name: type,` inside a struct or enum, up to and including its comma
+    Field,
+    /// `[ ... ]` of a list, set or array
+    List,
+    /// `( ... )` of annotation arguments
+    Args,
+    /// `< ... >` after the name of a generic type
+    TypeArgs,
+
+    /// A token the parser sees
+    Token(TokenVariant),
+    /// Spaces, tabs and line breaks
+    Whitespace,
+    /// `// ...`, without the line break
+    LineComment,
+    /// `/* ... */`, documentation comments are tokens instead
+    BlockComment,
+}
+
+impl SyntaxKind {
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Whitespace | SyntaxKind::LineComment | SyntaxKind::BlockComment
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken<'src> {
+    pub kind: SyntaxKind,
+    pub text: &'src str,
+    /// Byte offset in the source
+    pub offset: usize,
+}
+
+impl SyntaxToken<'_> {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.text.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement<'src> {
+    Node(SyntaxNode<'src>),
+    Token(SyntaxToken<'src>),
+}
+
+impl SyntaxElement<'_> {
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.range.clone(),
+            SyntaxElement::Token(token) => token.range(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode<'src> {
+    pub kind: SyntaxKind,
+    /// Byte range in the source, from the start of the first child to the end of the last one
+    pub range: Range<usize>,
+    pub children: Vec<SyntaxElement<'src>>,
+}
+
+impl<'src> SyntaxNode<'src> {
+    fn new(kind: SyntaxKind, offset: usize) -> Self {
+        Self {
+            kind,
+            range: offset..offset,
+            children: Vec::new(),
+        }
+    }
+
+    /// Every token under this node in source order, trivia included.
+    pub fn tokens(&self) -> Vec<&SyntaxToken<'src>> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken<'src>>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// The child nodes, without tokens.
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode<'src>> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+}
+
+impl fmt::Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens()
+            .into_iter()
+            .try_for_each(|token| f.write_str(token.text))
+    }
+}
+
+/**
+Lossless syntax tree of a module: every token the lexer makes of it, whitespace and comments included,
+grouped into declarations and bracketed parts of them.
+Printing it gives back the source byte for byte.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree<'src> {
+    pub root: SyntaxNode<'src>,
+}
+
+impl<'src> SyntaxTree<'src> {
+    /// Lexes and parses `source` into its syntax tree.
+    pub fn parse(source: &'src str) -> Self {
+        let (tokens, _) = Lexer::tokenize_with_trivia(source);
+        let significant: XenoTokens = tokens
+            .iter()
+            .filter(|t| !t.0.is_trivia())
+            .cloned()
+            .collect();
+        let (_, spans) = Parser::parse_with_spans(&significant);
+        Self::new(source, &tokens, &spans)
+    }
+
+    /**
+    Builds the tree of `source` from all of its tokens, as `Lexer::tokenize_with_trivia` returns them,
+    and the spans of the declarations `Parser::parse_with_spans` made of the tokens without trivia,
+    so the tree agrees with the AST.
+    */
+    pub fn new(source: &'src str, tokens: &[Token<'src>], spans: &[DeclarationSpan]) -> Self {
+        let significant: XenoTokens = tokens
+            .iter()
+            .filter(|t| !t.0.is_trivia())
+            .cloned()
+            .collect();
+        let mut builder = Builder {
+            source,
+            tokens,
+            position: 0,
+            stack: vec![SyntaxNode::new(SyntaxKind::Root, 0)],
+        };
+
+        let mut spans = spans.iter().peekable();
+        let mut i = 0;
+        while i < significant.len() {
+            let (end, kind) = match spans.peek() {
+                Some(&&(start, end)) if start == i => {
+                    spans.next();
+                    (end, declaration_kind(&significant[start..end]))
+                }
+                // Everything up to the next declaration failed to parse
+                next => (
+                    next.map_or(significant.len(), |(start, _)| *start),
+                    SyntaxKind::Invalid,
+                ),
+            };
+            builder.declaration(kind, &significant[i..end]);
+            i = end;
+        }
+
+        builder.trivia();
+        let mut root = builder.stack.pop().unwrap();
+        root.range = 0..source.len();
+        Self { root }
+    }
+
+    /// Nodes of the declarations in the order of the AST, `Invalid` nodes are skipped.
+    pub fn declarations(&self) -> impl Iterator<Item = &SyntaxNode<'src>> {
+        self.root
+            .nodes()
+            .filter(|node| node.kind != SyntaxKind::Invalid)
+    }
+
+    /// The innermost node containing the byte range, at least the root.
+    pub fn covering_node(&self, range: Range<usize>) -> &SyntaxNode<'src> {
+        let mut node = &self.root;
+        while let Some(child) = node
+            .nodes()
+            .find(|n| n.range.start <= range.start && range.end <= n.range.end)
+        {
+            node = child;
+        }
+        node
+    }
+}
+
+impl fmt::Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt(f)
+    }
+}
+
+/// `Import` if the declaration starts with `import` or `export`, `TypeDecl` otherwise.
+fn declaration_kind(tokens: &[Token<'_>]) -> SyntaxKind {
+    let first = tokens
+        .iter()
+        .find(|t| !matches!(t.0, TokenVariant::Error | TokenVariant::Documentation));
+    match first {
+        Some((TokenVariant::Import, _)) => SyntaxKind::Import,
+        Some((TokenVariant::Identifier, d)) if d.v == "export" => SyntaxKind::Import,
+        _ => SyntaxKind::TypeDecl,
+    }
+}
+
+struct Builder<'a, 'src> {
+    source: &'src str,
+    /// Every token of the source, trivia included
+    tokens: &'a [Token<'src>],
+    /// Index of the first token that isn't in the tree yet
+    position: usize,
+    /// Open nodes, the root first
+    stack: Vec<SyntaxNode<'src>>,
+}
+
+impl<'src> Builder<'_, 'src> {
+    fn top(&self) -> SyntaxKind {
+        self.stack.last().unwrap().kind
+    }
+
+    fn push(&mut self, element: SyntaxElement<'src>) {
+        let range = element.range();
+        let node = self.stack.last_mut().unwrap();
+        if node.children.is_empty() {
+            node.range.start = range.start;
+        }
+        node.range.end = range.end;
+        node.children.push(element);
+    }
+
+    fn start(&mut self, kind: SyntaxKind) {
+        let offset = self
+            .tokens
+            .get(self.position)
+            .map_or(self.source.len(), |t| self.offset_of(t.1.v));
+        self.stack.push(SyntaxNode::new(kind, offset));
+    }
+
+    fn finish(&mut self) {
+        let node = self.stack.pop().unwrap();
+        self.push(SyntaxElement::Node(node));
+    }
+
+    fn offset_of(&self, text: &str) -> usize {
+        text.as_ptr() as usize - self.source.as_ptr() as usize
+    }
+
+    /// Adds the next token, trivia or not.
+    fn bump(&mut self) {
+        let (variant, data) = &self.tokens[self.position];
+        let kind = match variant {
+            TokenVariant::Whitespace => SyntaxKind::Whitespace,
+            TokenVariant::LineComment => SyntaxKind::LineComment,
+            TokenVariant::BlockComment => SyntaxKind::BlockComment,
+            variant => SyntaxKind::Token(*variant),
+        };
+        let token = SyntaxToken {
+            kind,
+            text: data.v,
+            offset: self.offset_of(data.v),
+        };
+        self.push(SyntaxElement::Token(token));
+        self.position += 1;
+    }
+
+    /// Adds the whitespace and comments up to the next token the parser sees.
+    fn trivia(&mut self) {
+        while self
+            .tokens
+            .get(self.position)
+            .is_some_and(|t| t.0.is_trivia())
+        {
+            self.bump();
+        }
+    }
+
+    /// Adds a comment that follows on the same line, with the spaces in front of it.
+    fn trailing_trivia(&mut self) {
+        let mut comment = self.position;
+        if let Some((TokenVariant::Whitespace, data)) = self.tokens.get(comment) {
+            if data.v.contains(['\n', '\r']) {
+                return;
+            }
+            comment += 1;
+        }
+        let same_line = match self.tokens.get(comment) {
+            Some((TokenVariant::LineComment, _)) => true,
+            Some((TokenVariant::BlockComment, data)) => !data.v.contains('\n'),
+            _ => false,
+        };
+        if same_line {
+            while self.position <= comment {
+                self.bump();
+            }
+        }
+    }
+
+    /// Adds the trivia in front of the next token and the token itself, which is `text`.
+    fn token(&mut self, text: &'src str) {
+        self.trivia();
+        debug_assert_eq!(self.tokens[self.position].1.v.as_ptr(), text.as_ptr());
+        self.bump();
+    }
+
+    /**
+    Adds the node of a declaration made of the non-trivia `tokens`.
+    Comments in front of it stay outside of it, its documentation and a comment after it on the same line don't.
+    */
+    fn declaration(&mut self, kind: SyntaxKind, tokens: &[Token<'src>]) {
+        self.trivia();
+        self.start(kind);
+        let depth = self.stack.len();
+
+        let mut previous = None;
+        // A field ended with a comma, its comment on the same line is still part of it
+        let mut field_ended = false;
+        for (i, (variant, data)) in tokens.iter().enumerate() {
+            let variant = *variant;
+            let opened = match variant {
+                TokenVariant::LCurly if kind == SyntaxKind::Import => Some(SyntaxKind::ImportItems),
+                TokenVariant::LCurly if previous == Some(TokenVariant::Enum) => {
+                    Some(SyntaxKind::Enum)
+                }
+                TokenVariant::LCurly => Some(SyntaxKind::Struct),
+                TokenVariant::LBracket => Some(SyntaxKind::List),
+                TokenVariant::LParen => Some(SyntaxKind::Args),
+                // Only the declared name is in front of the parameters of a generic type
+                TokenVariant::Lt if kind == SyntaxKind::TypeDecl && is_declared_name(tokens, i) => {
+                    Some(SyntaxKind::Generics)
+                }
+                TokenVariant::Lt => Some(SyntaxKind::TypeArgs),
+                _ => None,
+            };
+            let closes = match variant {
+                TokenVariant::RCurly => &[
+                    SyntaxKind::Struct,
+                    SyntaxKind::Enum,
+                    SyntaxKind::ImportItems,
+                ][..],
+                TokenVariant::RBracket => &[SyntaxKind::List],
+                TokenVariant::RParen => &[SyntaxKind::Args],
+                TokenVariant::Gt => &[SyntaxKind::Generics, SyntaxKind::TypeArgs],
+                _ => &[],
+            };
+
+            if field_ended {
+                self.trailing_trivia();
+                self.finish();
+                field_ended = false;
+            }
+            self.trivia();
+            if self.top() == SyntaxKind::Field && variant == TokenVariant::RCurly {
+                self.finish();
+            }
+            if matches!(self.top(), SyntaxKind::Struct | SyntaxKind::Enum) && closes.is_empty() {
+                self.start(SyntaxKind::Field);
+            }
+
+            if let Some(node) = opened {
+                self.start(node);
+                self.token(data.v);
+            } else if self.stack.len() > depth && closes.contains(&self.top()) {
+                self.token(data.v);
+                self.finish();
+            } else {
+                self.token(data.v);
+                field_ended = variant == TokenVariant::Comma && self.top() == SyntaxKind::Field;
+            }
+
+            if !matches!(variant, TokenVariant::Error | TokenVariant::Documentation) {
+                previous = Some(variant);
+            }
+        }
+
+        // Brackets left open by broken declarations end with them
+        while self.stack.len() > depth {
+            self.finish();
+        }
+        self.trailing_trivia();
+        self.finish();
+    }
+}
+
+/// Whether the token in front of `tokens[i]` is the name after `type`.
+fn is_declared_name(tokens: &[Token<'_>], i: usize) -> bool {
+    let significant: Vec<TokenVariant> = tokens[..i]
+        .iter()
+        .map(|t| t.0)
+        .filter(|v| !matches!(v, TokenVariant::Error | TokenVariant::Documentation))
+        .collect();
+    significant == [TokenVariant::Type, TokenVariant::Identifier]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Declaration;
+
+    const SCHEMA: &str = r#"// Users and their pages
+import common/types { Id, /* the role */ Role as UserRole };
+
+/** A user of the system */
+type User = {
+    _id: Id, // primary key
+    name: string @min(1) @max(64),
+    /* contact */ email: string @regex(/^[^@]+@[^@]+$/),
+    role: UserRole | "guest"
+};
+
+type Page<T> = { items: T[], next: Page<T> };
+type Status = enum { Active: 1, Banned: 2 };
+type Broken = { a: ;
+	type Ünicode = "unterminated
+/* dangling"#;
+
+    fn kinds(node: &SyntaxNode) -> Vec<SyntaxKind> {
+        node.nodes().map(|n| n.kind).collect()
+    }
+
+    #[test]
+    fn printing_the_tree_gives_back_the_source() {
+        for source in [
+            SCHEMA,
+            "",
+            "  \n",
+            "// only a comment",
+            "/**/type A = u8;/**/",
+        ] {
+            assert_eq!(SyntaxTree::parse(source).to_string(), source);
+        }
+
+        let bench = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/bench");
+        for entry in std::fs::read_dir(bench.join("core")).unwrap() {
+            let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert_eq!(SyntaxTree::parse(&source).to_string(), source);
+        }
+    }
+
+    #[test]
+    fn broken_sources_round_trip() {
+        let boundaries = (0..=SCHEMA.len()).filter(|&i| SCHEMA.is_char_boundary(i));
+        for i in boundaries {
+            for fragment in [
+                "{", "}", "<", "]", ",", "\"", "//", "/*", "/**", "*/", "type ",
+            ] {
+                let source = format!("{}{}{}", &SCHEMA[..i], fragment, &SCHEMA[i..]);
+                let tree = SyntaxTree::parse(&source);
+                assert_eq!(tree.to_string(), source);
+
+                let mut nodes = vec![&tree.root];
+                while let Some(node) = nodes.pop() {
+                    assert_eq!(source[node.range.clone()], node.to_string());
+                    nodes.extend(node.nodes());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nodes_span_their_source() {
+        let tree = SyntaxTree::parse(SCHEMA);
+        assert_eq!(
+            kinds(&tree.root),
+            [
+                SyntaxKind::Import,
+                SyntaxKind::TypeDecl,
+                SyntaxKind::TypeDecl,
+                SyntaxKind::TypeDecl,
+                SyntaxKind::Invalid,
+            ]
+        );
+
+        let mut nodes = vec![&tree.root];
+        while let Some(node) = nodes.pop() {
+            assert_eq!(SCHEMA[node.range.clone()], node.to_string());
+            nodes.extend(node.nodes());
+        }
+
+        // The documentation belongs to its declaration, the comment before the import doesn't
+        let user = tree.declarations().nth(1).unwrap();
+        assert!(user
+            .to_string()
+            .starts_with("/** A user of the system */\ntype User"));
+        assert_eq!(
+            tree.root.children[0].range(),
+            0.."// Users and their pages".len()
+        );
+
+        let fields = user.nodes().next().unwrap();
+        assert_eq!(fields.kind, SyntaxKind::Struct);
+        assert_eq!(fields.nodes().count(), 4);
+        assert_eq!(
+            fields.nodes().next().unwrap().to_string(),
+            "_id: Id, // primary key"
+        );
+
+        let page = tree.declarations().nth(2).unwrap();
+        assert_eq!(kinds(page), [SyntaxKind::Generics, SyntaxKind::Struct]);
+    }
+
+    #[test]
+    fn declaration_nodes_line_up_with_the_ast() {
+        let (tokens, _) = Lexer::tokenize(SCHEMA);
+        let ((ast, _), spans) = Parser::parse_with_spans(&tokens);
+        let (all_tokens, _) = Lexer::tokenize_with_trivia(SCHEMA);
+        let tree = SyntaxTree::new(SCHEMA, &all_tokens, &spans);
+
+        assert_eq!(tree.declarations().count(), ast.len());
+        for (node, declaration) in tree.declarations().zip(&ast) {
+            if let Declaration::TypeDecl { name, .. } = declaration {
+                let offset = name.v.as_ptr() as usize - SCHEMA.as_ptr() as usize;
+                assert!(node.range.contains(&offset));
+                assert_eq!(node.kind, SyntaxKind::TypeDecl);
+            }
+        }
+
+        let name = SCHEMA.find("Active").unwrap();
+        assert_eq!(tree.covering_node(name..name + 6).kind, SyntaxKind::Field);
+    }
+}
@@ -0,0 +1,130 @@
+use serde::Deserialize;
+use std::fmt;
+
+/// How serious a reported problem is. Only errors make a workspace invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+            Severity::Hint => write!(f, "hint"),
+        }
+    }
+}
+
+/// Stable identifier of a kind of problem, displayed as `XEN0012`.
+/// Codes below 1000 are reserved for xenomorph itself, plugins should use 1000 and up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ErrorCode(pub u16);
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "XEN{:04}", self.0)
+    }
+}
+
+/// Every diagnostic code reported by xenomorph. Codes are never reused once published.
+pub mod codes {
+    use super::ErrorCode;
+
+    // ── Lexer ───────────────────────────────────────────────────────
+
+    /// A character that doesn't start any token.
+    pub const UNRECOGNIZED_CHARACTER: ErrorCode = ErrorCode(1);
+    /// A string literal without a closing quote on the same line.
+    pub const UNTERMINATED_STRING: ErrorCode = ErrorCode(2);
+    /// A block comment without `*/`.
+    pub const UNTERMINATED_COMMENT: ErrorCode = ErrorCode(3);
+    /// A regex literal without a closing slash on the same line.
+    pub const MALFORMED_REGEX: ErrorCode = ErrorCode(4);
+
+    // ── Parser ──────────────────────────────────────────────────────
+
+    /// A token that doesn't fit the grammar at its position.
+    pub const UNEXPECTED_TOKEN: ErrorCode = ErrorCode(10);
+    /// The file ends in the middle of a declaration.
+    pub const UNEXPECTED_EOF: ErrorCode = ErrorCode(11);
+    /// A number literal that doesn't fit its type.
+    pub const INVALID_NUMBER: ErrorCode = ErrorCode(12);
+    /// A documentation comment in front of an import.
+    pub const DOCUMENTED_IMPORT: ErrorCode = ErrorCode(13);
+
+    // ── Names ───────────────────────────────────────────────────────
+
+    /// A type name that is neither declared, imported nor builtin.
+    pub const UNKNOWN_TYPE: ErrorCode = ErrorCode(20);
+    /// An annotation no builtin or plugin provides.
+    pub const UNKNOWN_ANNOTATION: ErrorCode = ErrorCode(21);
+    /// A member path through a type that has no such field.
+    pub const UNKNOWN_FIELD: ErrorCode = ErrorCode(22);
+    /// Type arguments on a type that doesn't take them, or the wrong number of them.
+    pub const TYPE_ARGUMENTS: ErrorCode = ErrorCode(23);
+    /// The same type parameter listed twice in one declaration.
+    pub const DUPLICATE_TYPE_PARAMETER: ErrorCode = ErrorCode(24);
+    /// A selective import of a name the imported module doesn't declare.
+    pub const UNKNOWN_IMPORTED_NAME: ErrorCode = ErrorCode(25);
+
+    // ── Annotations ─────────────────────────────────────────────────
+
+    /// An annotation on a type it doesn't apply to.
+    pub const ANNOTATION_NOT_APPLICABLE: ErrorCode = ErrorCode(30);
+    /// An annotation with the wrong number of arguments.
+    pub const ANNOTATION_ARGUMENT_COUNT: ErrorCode = ErrorCode(31);
+    /// An annotation argument of the wrong type.
+    pub const ANNOTATION_ARGUMENT_TYPE: ErrorCode = ErrorCode(32);
+    /// `@elseif` or `@else` without a preceding `@if`.
+    pub const MISPLACED_ELSE: ErrorCode = ErrorCode(33);
+
+    // ── Declarations and imports ────────────────────────────────────
+
+    /// A name declared more than once in one module.
+    pub const DUPLICATE_DECLARATION: ErrorCode = ErrorCode(40);
+    /// A name imported from several modules that declare it differently.
+    pub const AMBIGUOUS_IMPORT: ErrorCode = ErrorCode(41);
+    /// A name imported explicitly and also declared in the importing module.
+    pub const CONFLICTING_IMPORT: ErrorCode = ErrorCode(42);
+    /// Modules that import each other, directly or through other modules.
+    pub const IMPORT_CYCLE: ErrorCode = ErrorCode(43);
+    /// A module importing itself.
+    pub const SELF_IMPORT: ErrorCode = ErrorCode(44);
+    /// An import of a module that doesn't exist.
+    pub const UNRESOLVED_IMPORT: ErrorCode = ErrorCode(45);
+
+    // ── Workspace ───────────────────────────────────────────────────
+
+    /// A file or path that can't be read or isn't inside the workspace.
+    pub const FILE_ACCESS: ErrorCode = ErrorCode(50);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_zero_padded() {
+        assert_eq!(codes::UNRECOGNIZED_CHARACTER.to_string(), "XEN0001");
+        assert_eq!(codes::UNKNOWN_TYPE.to_string(), "XEN0020");
+        assert_eq!(ErrorCode(1042).to_string(), "XEN1042");
+    }
+
+    #[test]
+    fn severities_deserialize_from_lowercase_names() {
+        #[derive(Deserialize)]
+        struct Level {
+            level: Severity,
+        }
+        let level: Level = toml::from_str("level = \"hint\"").unwrap();
+        assert_eq!(level.level, Severity::Hint);
+        assert!(Severity::Error < Severity::Warning);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::parser::{Declaration, Expr, KeyValExpr};
+use crate::TokenData;
+
+/// A struct field where it's declared, in a struct of a type declaration or nested in one.
+#[derive(Debug, Clone, Copy)]
+pub struct Field<'src> {
+    pub name: &'src TokenData<'src>,
+    /// Its type, annotations included
+    pub t: &'src [Expr<'src>],
+    /// The name of the type declaration the struct is part of
+    pub owner: &'src TokenData<'src>,
+    /// Every field of the struct, this one too, `$name` references inside them name one of these
+    pub siblings: &'src [KeyValExpr<'src>],
+    /// Fields of the structs the declaration is made of, the ones member paths like `User.name` can name
+    pub top_level: bool,
+}
+
+impl Field<'_> {
+    /// Its type as written, without annotations, like `FieldInfo::type_name`.
+    pub fn type_name(&self) -> String {
+        self.t
+            .iter()
+            .filter(|e| !matches!(e, Expr::Annotation(..)))
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl PartialEq for Field<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+/// What a name says about a struct field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldName<'src> {
+    /// The name of the field where it's declared
+    Declaration(Field<'src>),
+    /// `$name` in an annotation, with the field of the same struct it names, `None` if there's no such field
+    Sibling(&'src TokenData<'src>, Option<Field<'src>>),
+    /// A member of a path like `User.address.city`, with the base type and the members up to this one
+    Member(&'src TokenData<'src>, &'src [&'src TokenData<'src>]),
+}
+
+/// The struct fields of a module and the names that refer to them, in source order.
+#[derive(Debug, Default)]
+pub struct FieldNames<'src> {
+    pub fields: Vec<Field<'src>>,
+    /// `$name` references with the sibling field they name
+    pub siblings: Vec<(&'src TokenData<'src>, Option<Field<'src>>)>,
+    /// Member paths as their base type and members, `User` and `[address, city]`
+    pub members: Vec<(&'src TokenData<'src>, &'src [&'src TokenData<'src>])>,
+}
+
+impl<'src> FieldNames<'src> {
+    pub fn of(ast: &'src [Declaration<'src>]) -> Self {
+        let mut names = Self::default();
+        for declaration in ast {
+            if let Declaration::TypeDecl { name, t, .. } = declaration {
+                names.collect(name, t, None, true);
+            }
+        }
+        names
+    }
+
+    /// The field name at a token of the module, compared by position.
+    pub fn at(&self, token: &TokenData<'_>) -> Option<FieldName<'src>> {
+        let same = |other: &TokenData<'_>| other.l == token.l && other.c == token.c;
+
+        if let Some(field) = self.fields.iter().find(|f| same(f.name)) {
+            return Some(FieldName::Declaration(*field));
+        }
+        if let Some((reference, field)) = self.siblings.iter().find(|(r, _)| same(r)) {
+            return Some(FieldName::Sibling(reference, *field));
+        }
+        self.members.iter().find_map(|(base, members)| {
+            let i = members.iter().position(|m| same(m))?;
+            Some(FieldName::Member(base, &members[..=i]))
+        })
+    }
+
+    /// `$name` references to a field, from the struct it's declared in.
+    pub fn references_to(
+        &self,
+        field: &Field<'src>,
+    ) -> impl Iterator<Item = &'src TokenData<'src>> + '_ {
+        let name = field.name;
+        self.siblings
+            .iter()
+            .filter(move |(_, f)| f.is_some_and(|f| f.name == name))
+            .map(|(reference, _)| *reference)
+    }
+
+    /// Walks a type with the fields of the struct it's directly in, `top_level` for the type of a declaration.
+    fn collect(
+        &mut self,
+        owner: &'src TokenData<'src>,
+        exprs: &'src [Expr<'src>],
+        siblings: Option<(&'src [KeyValExpr<'src>], bool)>,
+        top_level: bool,
+    ) {
+        for expr in exprs {
+            match expr {
+                Expr::Struct(fields) => {
+                    // All of them first, `$name` can refer to a field declared after it
+                    for (name, t) in fields {
+                        self.fields.push(Field {
+                            name,
+                            t,
+                            owner,
+                            siblings: fields,
+                            top_level,
+                        });
+                    }
+                    for (_, t) in fields {
+                        self.collect(owner, t, Some((fields, top_level)), false);
+                    }
+                }
+                Expr::FieldAccess(reference) => {
+                    let field = siblings.and_then(|(fields, top_level)| {
+                        let (name, t) = fields.iter().find(|(name, _)| name.v == reference.v)?;
+                        Some(Field {
+                            name,
+                            t,
+                            owner,
+                            siblings: fields,
+                            top_level,
+                        })
+                    });
+                    self.siblings.push((reference, field));
+                }
+                Expr::MemberAccess(base, members) => self.members.push((base, members)),
+                Expr::Enum(variants) => {
+                    for (_, t) in variants {
+                        self.collect(owner, t, siblings, false);
+                    }
+                }
+                Expr::Annotation(_, args)
+                | Expr::List(args)
+                | Expr::Set(args)
+                | Expr::TypeApplication(_, args) => {
+                    for arg in args {
+                        self.collect(owner, arg, siblings, false);
+                    }
+                }
+                Expr::BinaryExpr(_, pair) => {
+                    self.collect(owner, std::slice::from_ref(&pair.0), siblings, false);
+                    self.collect(owner, std::slice::from_ref(&pair.1), siblings, false);
+                }
+                Expr::Not(inner) => {
+                    self.collect(owner, std::slice::from_ref(inner.as_ref()), siblings, false)
+                }
+                Expr::Identifier(_) | Expr::Literal(_) | Expr::Regex(_) | Expr::Array(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{Lexer, Token};
+    use crate::parser::Parser;
+
+    const SOURCE: &str = "type User = {\n\
+        \x20   adult: bool,\n\
+        \x20   age: u8 @if($adult, @min(18)),\n\
+        \x20   address: { city: string @if($zip, @minlen(1)), zip: string },\n\
+        \x20   boss: User.address.city @if($missing, @minlen(1)),\n\
+        };";
+
+    fn find<'a>(tokens: &'a [Token<'a>], v: &str, nth: usize) -> &'a TokenData<'a> {
+        &tokens.iter().filter(|t| t.1.v == v).nth(nth).unwrap().1
+    }
+
+    #[test]
+    fn sibling_references_name_fields_of_their_own_struct() {
+        let (tokens, _) = Lexer::tokenize(SOURCE);
+        let (ast, errors) = Parser::parse(&tokens);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let names = FieldNames::of(&ast);
+
+        let Some(FieldName::Declaration(adult)) = names.at(find(&tokens, "adult", 0)) else {
+            panic!("`adult` is a field");
+        };
+        assert!(adult.top_level);
+        assert_eq!(adult.owner.v, "User");
+        assert_eq!(
+            names
+                .references_to(&adult)
+                .map(|r| (r.l, r.c))
+                .collect::<Vec<_>>(),
+            [(2, 17)]
+        );
+
+        let Some(FieldName::Sibling(_, Some(zip))) = names.at(find(&tokens, "zip", 0)) else {
+            panic!("`$zip` names a field");
+        };
+        assert!(!zip.top_level);
+        assert_eq!((zip.name.l, zip.name.c), (3, 51));
+
+        assert!(matches!(
+            names.at(find(&tokens, "missing", 0)),
+            Some(FieldName::Sibling(_, None))
+        ));
+        let Some(FieldName::Member(base, members)) = names.at(find(&tokens, "address", 1)) else {
+            panic!("`User.address` is a member path");
+        };
+        assert_eq!(base.v, "User");
+        assert_eq!(members.iter().map(|m| m.v).collect::<Vec<_>>(), ["address"]);
+        assert!(names.at(find(&tokens, "User", 0)).is_none());
+    }
+}
@@ -0,0 +1,868 @@
+use std::ops::Range;
+
+use crate::config::FormatConfig;
+use crate::cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxTree};
+use crate::lexer::TokenVariant;
+
+/**
+Formats a module. Declarations are pretty-printed from the syntax tree, so every comment is kept,
+while declarations that don't parse are left as they are. Formatting formatted text changes nothing.
+*/
+pub fn format_xenomorph(text: &str, options: &FormatConfig) -> String {
+    let tree = SyntaxTree::parse(text);
+    let mut formatted = print(&root(&tree.root.children), options);
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+    formatted
+}
+
+/**
+Formats the declarations and comments overlapping the byte `range` of a module.
+Returns the part of the text to replace and what to replace it with, `None` if nothing overlaps.
+*/
+pub fn format_xenomorph_range(
+    text: &str,
+    options: &FormatConfig,
+    range: Range<usize>,
+) -> Option<(Range<usize>, String)> {
+    let tree = SyntaxTree::parse(text);
+    let overlapping: Vec<usize> = tree
+        .root
+        .children
+        .iter()
+        .enumerate()
+        .filter(|(_, child)| {
+            let child_range = child.range();
+            !is_whitespace(child)
+                && child_range.start <= range.end
+                && range.start <= child_range.end
+        })
+        .map(|(i, _)| i)
+        .collect();
+    let (first, last) = (*overlapping.first()?, *overlapping.last()?);
+
+    let children = &tree.root.children[first..=last];
+    let replaced = children[0].range().start..children[children.len() - 1].range().end;
+    Some((replaced, print(&root(children), options)))
+}
+
+// ── Documents ───────────────────────────────────────────────────────
+
+/// Layout of formatted text, `Line`s of a group all break or all stay on one line.
+#[derive(Debug, Clone)]
+enum Doc {
+    Text(String),
+    /// A space, or a line break if the group breaks
+    Line,
+    /// Nothing, or a line break if the group breaks
+    SoftLine,
+    HardLine,
+    /// Makes the enclosing groups break, e.g. after a line comment
+    BreakParent,
+    /// Only printed if the group breaks, e.g. trailing commas
+    IfBreak(&'static str),
+    Indent(Vec<Doc>),
+    /// Its parts, on one line if they fit unless forced to break
+    Group(Vec<Doc>, bool),
+}
+
+fn text(s: &str) -> Doc {
+    Doc::Text(s.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+fn print(doc: &Doc, options: &FormatConfig) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut stack: Vec<(usize, Mode, &Doc)> = vec![(0, Mode::Break, doc)];
+
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                out.push_str(s);
+                column = match s.rfind('\n') {
+                    Some(i) => s[i + 1..].chars().count(),
+                    None => column + s.chars().count(),
+                };
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push('\n');
+                out.extend(std::iter::repeat_n(' ', indent));
+                column = indent;
+            }
+            Doc::BreakParent => {}
+            Doc::IfBreak(s) => {
+                if mode == Mode::Break {
+                    out.push_str(s);
+                    column += s.len();
+                }
+            }
+            Doc::Indent(docs) => {
+                stack.extend(
+                    docs.iter()
+                        .rev()
+                        .map(|d| (indent + options.indent, mode, d)),
+                );
+            }
+            Doc::Group(docs, force) => {
+                let flat = mode == Mode::Flat
+                    || (!force && fits(docs, &stack, options.width as isize - column as isize));
+                let mode = if flat { Mode::Flat } else { Mode::Break };
+                stack.extend(docs.iter().rev().map(|d| (indent, mode, d)));
+            }
+        }
+    }
+
+    out.truncate(out.trim_end().len());
+    out
+}
+
+/// Whether `docs` fit in `remaining` columns on one line, with what follows them up to the next line break.
+fn fits(docs: &[Doc], rest: &[(usize, Mode, &Doc)], mut remaining: isize) -> bool {
+    let mut stack: Vec<(Mode, &Doc)> = docs.iter().rev().map(|d| (Mode::Flat, d)).collect();
+    let mut rest = rest.iter().rev();
+
+    loop {
+        if remaining < 0 {
+            return false;
+        }
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => match s.find('\n') {
+                Some(i) => {
+                    return mode == Mode::Break && remaining >= s[..i].chars().count() as isize
+                }
+                None => remaining -= s.chars().count() as isize,
+            },
+            Doc::Line | Doc::SoftLine if mode == Mode::Break => return true,
+            Doc::Line => remaining -= 1,
+            Doc::SoftLine => {}
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::BreakParent => {
+                if mode == Mode::Flat {
+                    return false;
+                }
+            }
+            Doc::IfBreak(s) => {
+                if mode == Mode::Break {
+                    remaining -= s.len() as isize;
+                }
+            }
+            Doc::Indent(docs) => stack.extend(docs.iter().rev().map(|d| (mode, d))),
+            Doc::Group(docs, force) => {
+                if *force && mode == Mode::Flat {
+                    return false;
+                }
+                let mode = if *force { Mode::Break } else { mode };
+                stack.extend(docs.iter().rev().map(|d| (mode, d)));
+            }
+        }
+    }
+}
+
+// ── Syntax tree to documents ────────────────────────────────────────
+
+fn variant(element: &SyntaxElement) -> Option<TokenVariant> {
+    match element {
+        SyntaxElement::Token(t) => match t.kind {
+            SyntaxKind::Token(variant) => Some(variant),
+            _ => None,
+        },
+        SyntaxElement::Node(_) => None,
+    }
+}
+
+fn is_whitespace(element: &SyntaxElement) -> bool {
+    matches!(element, SyntaxElement::Token(t) if t.kind == SyntaxKind::Whitespace)
+}
+
+fn comment<'src>(element: &SyntaxElement<'src>) -> Option<&'src str> {
+    match element {
+        SyntaxElement::Token(t)
+            if matches!(t.kind, SyntaxKind::LineComment | SyntaxKind::BlockComment) =>
+        {
+            Some(t.text)
+        }
+        _ => None,
+    }
+}
+
+fn is_significant(element: &SyntaxElement) -> bool {
+    !is_whitespace(element) && comment(element).is_none()
+}
+
+fn line_breaks(element: &SyntaxElement) -> usize {
+    match element {
+        SyntaxElement::Token(t) if t.kind == SyntaxKind::Whitespace => t.text.matches('\n').count(),
+        _ => 0,
+    }
+}
+
+/// Prints comments after something, a line comment ends the line.
+fn trailing_comments(docs: &mut Vec<Doc>, comments: &[&str]) {
+    for comment in comments {
+        docs.push(text(" "));
+        docs.push(text(comment));
+        if comment.starts_with("//") {
+            docs.push(Doc::BreakParent);
+        }
+    }
+}
+
+/// Splits the comments off the end of `elements`.
+fn split_trailing<'a, 'src>(
+    elements: &[&'a SyntaxElement<'src>],
+) -> (Vec<&'a SyntaxElement<'src>>, Vec<&'a str>) {
+    let end = elements
+        .iter()
+        .rposition(|e| is_significant(e))
+        .map_or(0, |i| i + 1);
+    let comments = elements[end..].iter().filter_map(|e| comment(e)).collect();
+    (elements[..end].to_vec(), comments)
+}
+
+/// A declaration that doesn't parse, or that can't be formatted without losing something, stays as it is.
+fn contains_error(node: &SyntaxNode) -> bool {
+    node.tokens()
+        .iter()
+        .any(|t| t.kind == SyntaxKind::Token(TokenVariant::Error))
+}
+
+enum RootItem {
+    Import(String, Doc),
+    Other(Doc),
+}
+
+/// Top level declarations and comments, each on its own line with single blank lines kept.
+fn root(children: &[SyntaxElement]) -> Doc {
+    let mut items: Vec<(RootItem, bool)> = Vec::new();
+    let mut blank = false;
+    for child in children {
+        if is_whitespace(child) {
+            blank |= line_breaks(child) >= 2;
+            continue;
+        }
+        let item = match child {
+            SyntaxElement::Node(node) if node.kind == SyntaxKind::Import => {
+                let key = node.tokens().iter().filter(|t| !t.kind.is_trivia()).fold(
+                    String::new(),
+                    |key, t| match t.kind {
+                        SyntaxKind::Token(TokenVariant::Import) => String::new(),
+                        _ => key + t.text,
+                    },
+                );
+                match import(node) {
+                    Some(doc) => RootItem::Import(key, doc),
+                    None => RootItem::Other(text(&node.to_string())),
+                }
+            }
+            SyntaxElement::Node(node) if node.kind == SyntaxKind::TypeDecl => {
+                RootItem::Other(type_decl(node).unwrap_or_else(|| text(&node.to_string())))
+            }
+            SyntaxElement::Node(node) => RootItem::Other(text(&node.to_string())),
+            SyntaxElement::Token(token) => RootItem::Other(text(token.text)),
+        };
+        items.push((item, blank && !items.is_empty()));
+        blank = false;
+    }
+
+    // Imports are sorted within each run that no blank line or comment interrupts
+    let mut start = 0;
+    while start < items.len() {
+        let mut end = start + 1;
+        if matches!(items[start].0, RootItem::Import(..)) {
+            while end < items.len() && matches!(items[end], (RootItem::Import(..), false)) {
+                end += 1;
+            }
+            let blank_before = items[start].1;
+            items[start..end].sort_by(|(a, _), (b, _)| match (a, b) {
+                (RootItem::Import(a, _), RootItem::Import(b, _)) => a.cmp(b),
+                _ => std::cmp::Ordering::Equal,
+            });
+            items[start].1 = blank_before;
+            for item in &mut items[start + 1..end] {
+                item.1 = false;
+            }
+        }
+        start = end;
+    }
+
+    let mut docs = Vec::new();
+    for (i, (item, blank)) in items.into_iter().enumerate() {
+        if i > 0 {
+            docs.push(Doc::HardLine);
+        }
+        if blank {
+            docs.push(Doc::HardLine);
+        }
+        docs.push(match item {
+            RootItem::Import(_, doc) | RootItem::Other(doc) => doc,
+        });
+    }
+    Doc::Group(docs, true)
+}
+
+/// `export import a/b { X, Y as Z };`
+fn import(node: &SyntaxNode) -> Option<Doc> {
+    if contains_error(node) {
+        return None;
+    }
+    let end = node.children.iter().rposition(|e| is_significant(e))?;
+    if variant(&node.children[end]) != Some(TokenVariant::Semicolon) {
+        return None;
+    }
+
+    let mut docs = Vec::new();
+    for child in &node.children[..end] {
+        match child {
+            SyntaxElement::Token(t) => match t.kind {
+                SyntaxKind::Whitespace => {}
+                SyntaxKind::Token(TokenVariant::Import) => docs.push(text("import ")),
+                SyntaxKind::Token(TokenVariant::Identifier) if docs.is_empty() => {
+                    docs.push(text("export "))
+                }
+                SyntaxKind::Token(TokenVariant::Identifier | TokenVariant::Slash) => {
+                    docs.push(text(t.text))
+                }
+                _ => return None,
+            },
+            SyntaxElement::Node(items) if items.kind == SyntaxKind::ImportItems => {
+                docs.push(text(" "));
+                docs.push(bracketed(items, Doc::Line, true)?);
+            }
+            SyntaxElement::Node(_) => return None,
+        }
+    }
+    docs.push(text(";"));
+
+    let rest: Vec<&SyntaxElement> = node.children[end + 1..].iter().collect();
+    let (_, comments) = split_trailing(&rest);
+    trailing_comments(&mut docs, &comments);
+    Some(Doc::Group(docs, false))
+}
+
+/// `/** docs */ type Name<T> = body;`
+fn type_decl(node: &SyntaxNode) -> Option<Doc> {
+    if contains_error(node) {
+        return None;
+    }
+    let eq = node
+        .children
+        .iter()
+        .position(|e| variant(e) == Some(TokenVariant::Eq))?;
+    let end = node.children.iter().rposition(|e| is_significant(e))?;
+    if variant(&node.children[end]) != Some(TokenVariant::Semicolon) {
+        return None;
+    }
+
+    let mut docs = Vec::new();
+    for child in &node.children[..eq] {
+        match child {
+            SyntaxElement::Token(t) => match t.kind {
+                SyntaxKind::Whitespace => {}
+                SyntaxKind::Token(TokenVariant::Documentation) => {
+                    docs.push(text(t.text));
+                    docs.push(Doc::HardLine);
+                }
+                SyntaxKind::Token(TokenVariant::Type) => docs.push(text("type ")),
+                SyntaxKind::Token(TokenVariant::Identifier) => docs.push(text(t.text)),
+                _ => return None,
+            },
+            SyntaxElement::Node(generics) if generics.kind == SyntaxKind::Generics => {
+                docs.push(bracketed(generics, Doc::SoftLine, false)?);
+            }
+            SyntaxElement::Node(_) => return None,
+        }
+    }
+
+    let body: Vec<&SyntaxElement> = node.children[eq + 1..end].iter().collect();
+    let (body, mut comments) = split_trailing(&body);
+    docs.push(text(" ="));
+    docs.push(expression(&body, true)?);
+    docs.push(text(";"));
+
+    let rest: Vec<&SyntaxElement> = node.children[end + 1..].iter().collect();
+    comments.extend(split_trailing(&rest).1);
+    trailing_comments(&mut docs, &comments);
+    Some(Doc::Group(docs, false))
+}
+
+/**
+A type made of tokens and bracketed parts, with normalised spacing.
+A long union breaks in front of every `|`, `spaced` puts a space in front of the type,
+or a line break in front of a broken union.
+*/
+fn expression(elements: &[&SyntaxElement], spaced: bool) -> Option<Doc> {
+    // The alternatives of a union, each without its `|`
+    let mut alternatives: Vec<Vec<Doc>> = vec![vec![]];
+    let mut previous: Option<&SyntaxElement> = None;
+    let mut after_line_comment = false;
+
+    for element in elements {
+        if is_whitespace(element) {
+            continue;
+        }
+        if variant(element) == Some(TokenVariant::Or) {
+            alternatives.push(vec![]);
+            previous = None;
+            continue;
+        }
+        let docs = alternatives.last_mut()?;
+        if after_line_comment {
+            docs.push(Doc::HardLine);
+        } else if let Some(previous) = previous {
+            if spaced_between(previous, element) {
+                docs.push(text(" "));
+            }
+        }
+        after_line_comment = false;
+        previous = Some(element);
+
+        if let Some(comment) = comment(element) {
+            docs.push(text(comment));
+            if comment.starts_with("//") {
+                docs.push(Doc::BreakParent);
+                after_line_comment = true;
+            }
+            continue;
+        }
+        docs.push(match element {
+            SyntaxElement::Token(t) => text(t.text),
+            SyntaxElement::Node(node) => match node.kind {
+                SyntaxKind::Struct | SyntaxKind::Enum => fields(node)?,
+                SyntaxKind::List | SyntaxKind::Args | SyntaxKind::TypeArgs => {
+                    bracketed(node, Doc::SoftLine, true)?
+                }
+                _ => return None,
+            },
+        });
+    }
+
+    // `type X = | A | B;` is written without the leading `|`
+    if alternatives.len() > 1 && alternatives[0].is_empty() {
+        alternatives.remove(0);
+    }
+    let mut alternatives = alternatives.into_iter();
+    let first = alternatives.next()?;
+    let mut tail = Vec::new();
+    for alternative in alternatives {
+        tail.push(Doc::Line);
+        tail.push(text("| "));
+        tail.extend(alternative);
+    }
+
+    if !spaced {
+        return Some(Doc::Group(
+            vec![Doc::Group(first, false), Doc::Indent(tail)],
+            false,
+        ));
+    }
+    if tail.is_empty() {
+        return Some(Doc::Group(vec![text(" "), Doc::Group(first, false)], false));
+    }
+    let mut union = vec![Doc::Line, Doc::IfBreak("| "), Doc::Group(first, false)];
+    union.extend(tail);
+    Some(Doc::Group(vec![Doc::Indent(union)], false))
+}
+
+/// Whether a space goes between two parts of a type.
+fn spaced_between(previous: &SyntaxElement, next: &SyntaxElement) -> bool {
+    use TokenVariant::*;
+
+    if comment(previous).is_some() || comment(next).is_some() {
+        return true;
+    }
+    if matches!(
+        variant(previous),
+        Some(At | Dollar | Not | Dot | Range | Plus | Minus)
+    ) {
+        return false;
+    }
+    if matches!(variant(next), Some(Dot | Range | Comma | Colon | Semicolon)) {
+        return false;
+    }
+    match next {
+        SyntaxElement::Node(node) => match node.kind {
+            SyntaxKind::Args | SyntaxKind::TypeArgs => false,
+            // `set[...]`, `T[]` and `[A, B][]`, other lists stay apart from what is in front of them
+            SyntaxKind::List => {
+                let empty = !node
+                    .children
+                    .iter()
+                    .skip(1)
+                    .any(|child| is_significant(child) && variant(child) != Some(RBracket));
+                let previous_list =
+                    matches!(previous, SyntaxElement::Node(n) if n.kind == SyntaxKind::List);
+                !(variant(previous) == Some(Set)
+                    || empty && (variant(previous) == Some(Identifier) || previous_list))
+            }
+            _ => true,
+        },
+        SyntaxElement::Token(_) => true,
+    }
+}
+
+/// Comma separated parts between brackets. `padding` goes inside the brackets, `Line` for `{ A, B }`.
+fn bracketed(node: &SyntaxNode, padding: Doc, trailing_comma: bool) -> Option<Doc> {
+    let (open, inner, close) = match node.children.as_slice() {
+        [open, inner @ .., close] if is_significant(close) && variant(close).is_some() => {
+            (open, inner, close)
+        }
+        _ => return None,
+    };
+
+    // Parts with the comments that follow them
+    let mut parts: Vec<(Vec<&SyntaxElement>, Vec<&str>)> = Vec::new();
+    let mut current = Vec::new();
+    for element in inner {
+        if variant(element) == Some(TokenVariant::Comma) {
+            let (part, comments) = split_trailing(&current);
+            parts.push((part, comments));
+            current.clear();
+        } else {
+            current.push(element);
+        }
+    }
+    let (part, comments) = split_trailing(&current);
+    if !part.is_empty() {
+        parts.push((part, comments));
+    } else if let Some(last) = parts.last_mut() {
+        last.1.extend(comments);
+    } else if !comments.is_empty() {
+        return None;
+    }
+
+    // Leading comments of the next part that follow a comma on the same line belong to the previous one
+    for i in 1..parts.len() {
+        let leading = parts[i].0.iter().take_while(|e| !is_significant(e)).count();
+        let on_same_line = parts[i].0[..leading]
+            .iter()
+            .take_while(|e| line_breaks(e) == 0)
+            .filter_map(|e| comment(e))
+            .count();
+        let moved: Vec<&str> = parts[i]
+            .0
+            .iter()
+            .filter_map(|e| comment(e))
+            .take(on_same_line)
+            .collect();
+        if moved.is_empty() {
+            continue;
+        }
+        let mut taken = 0;
+        parts[i].0.retain(|e| {
+            if taken < moved.len() && comment(e).is_some() {
+                taken += 1;
+                return false;
+            }
+            true
+        });
+        parts[i - 1].1.extend(moved);
+    }
+
+    if node.kind == SyntaxKind::ImportItems
+        && parts.iter().all(|(part, comments)| {
+            comments.is_empty() && part.iter().all(|e| comment(e).is_none())
+        })
+    {
+        parts.sort_by_key(|(part, _)| {
+            part.iter()
+                .filter(|e| is_significant(e))
+                .map(|e| e.to_text())
+                .collect::<Vec<_>>()
+        });
+    }
+
+    let open = text(open.to_text().as_str());
+    let close = text(close.to_text().as_str());
+    if parts.is_empty() {
+        return Some(Doc::Group(vec![open, close], false));
+    }
+
+    let mut inside = Vec::new();
+    let count = parts.len();
+    for (i, (part, comments)) in parts.into_iter().enumerate() {
+        inside.push(if i == 0 { padding.clone() } else { Doc::Line });
+        inside.push(expression(&part, false)?);
+        if i + 1 < count {
+            inside.push(text(","));
+        } else if trailing_comma {
+            inside.push(Doc::IfBreak(","));
+        }
+        trailing_comments(&mut inside, &comments);
+    }
+    Some(Doc::Group(
+        vec![open, Doc::Indent(inside), padding, close],
+        false,
+    ))
+}
+
+/// `{ name: type, ... }` of a struct or enum. Fields stay on separate lines if they were.
+fn fields(node: &SyntaxNode) -> Option<Doc> {
+    let (inner, close) = match node.children.as_slice() {
+        [_, inner @ .., close] if variant(close) == Some(TokenVariant::RCurly) => (inner, close),
+        _ => return None,
+    };
+
+    enum Item<'a> {
+        Field(Doc, Vec<&'a str>),
+        Comment(&'a str),
+    }
+    let mut items: Vec<(Item, bool)> = Vec::new();
+    let mut broken = false;
+    let mut blank = false;
+    for element in inner {
+        match element {
+            SyntaxElement::Node(field) if field.kind == SyntaxKind::Field => {
+                let (doc, comments) = field_doc(field)?;
+                items.push((Item::Field(doc, comments), blank));
+            }
+            SyntaxElement::Node(_) => return None,
+            _ if is_whitespace(element) => {
+                broken |= items.is_empty() && line_breaks(element) > 0;
+                blank = !items.is_empty() && line_breaks(element) >= 2;
+                continue;
+            }
+            _ => match comment(element) {
+                Some(comment) => items.push((Item::Comment(comment), blank)),
+                None => return None,
+            },
+        }
+        blank = false;
+    }
+
+    let close = text(close.to_text().as_str());
+    if items.is_empty() {
+        return Some(Doc::Group(vec![text("{"), close], false));
+    }
+
+    let last_field = items
+        .iter()
+        .rposition(|(item, _)| matches!(item, Item::Field(..)));
+    let mut inside = Vec::new();
+    for (i, (item, blank)) in items.into_iter().enumerate() {
+        if blank {
+            inside.push(Doc::HardLine);
+        }
+        inside.push(Doc::Line);
+        match item {
+            Item::Field(doc, comments) => {
+                inside.push(doc);
+                inside.push(if Some(i) == last_field {
+                    Doc::IfBreak(",")
+                } else {
+                    text(",")
+                });
+                trailing_comments(&mut inside, &comments);
+            }
+            Item::Comment(comment) => {
+                inside.push(text(comment));
+                broken = true;
+            }
+        }
+    }
+    Some(Doc::Group(
+        vec![text("{"), Doc::Indent(inside), Doc::Line, close],
+        broken,
+    ))
+}
+
+/// `name: type` of a field and the comments after it.
+fn field_doc<'a>(node: &'a SyntaxNode) -> Option<(Doc, Vec<&'a str>)> {
+    let significant: Vec<usize> = (0..node.children.len())
+        .filter(|&i| is_significant(&node.children[i]))
+        .collect();
+    let (name, colon) = (*significant.first()?, *significant.get(1)?);
+    if variant(&node.children[name]) != Some(TokenVariant::Identifier)
+        || variant(&node.children[colon]) != Some(TokenVariant::Colon)
+        || node.children[..colon].iter().any(|e| comment(e).is_some())
+    {
+        return None;
+    }
+
+    let comma = significant
+        .last()
+        .filter(|&&i| variant(&node.children[i]) == Some(TokenVariant::Comma))
+        .copied();
+    let value_end = comma.unwrap_or(node.children.len());
+    let value: Vec<&SyntaxElement> = node.children[colon + 1..value_end].iter().collect();
+    let (value, mut comments) = split_trailing(&value);
+    if let Some(comma) = comma {
+        comments.extend(node.children[comma + 1..].iter().filter_map(comment));
+    }
+
+    let doc = Doc::Group(
+        vec![
+            text(&node.children[name].to_text()),
+            text(":"),
+            expression(&value, true)?,
+        ],
+        false,
+    );
+    Some((doc, comments))
+}
+
+trait ToText {
+    fn to_text(&self) -> String;
+}
+
+impl ToText for SyntaxElement<'_> {
+    fn to_text(&self) -> String {
+        match self {
+            SyntaxElement::Node(node) => node.to_string(),
+            SyntaxElement::Token(token) => token.text.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::{Declaration, Parser};
+
+    fn format(text: &str) -> String {
+        format_xenomorph(text, &FormatConfig::default())
+    }
+
+    /// Declarations as written by `Display`, imports sorted, plus every comment.
+    fn meaning(text: &str) -> (Vec<String>, Vec<String>) {
+        let (tokens, _) = Lexer::tokenize(text);
+        let (ast, _) = Parser::parse(&tokens);
+        let mut imports: Vec<String> = ast
+            .iter()
+            .filter(|d| matches!(d, Declaration::Import { .. }))
+            .map(|d| d.to_string())
+            .collect();
+        imports.sort();
+        let mut declarations: Vec<String> = ast
+            .iter()
+            .filter(|d| !matches!(d, Declaration::Import { .. }))
+            .map(|d| d.to_string())
+            .collect();
+        declarations.extend(imports);
+
+        let comments = SyntaxTree::parse(text)
+            .root
+            .tokens()
+            .iter()
+            .filter(|t| matches!(t.kind, SyntaxKind::LineComment | SyntaxKind::BlockComment))
+            .map(|t| t.text.to_string())
+            .collect();
+        (declarations, comments)
+    }
+
+    #[test]
+    fn spacing_is_normalised() {
+        assert_eq!(
+            format("type   A<T,U>={a:T|U,b :string@min( 1 )@max(2),c:u8[]  ,d:set [ string ],e:User . _id};"),
+            "type A<T, U> = { a: T | U, b: string @min(1) @max(2), c: u8[], d: set[string], e: User._id };\n"
+        );
+        assert_eq!(
+            format(
+                "type B=[date,Action] []@minlen(1) ;\ntype C=f64&!0.5   |  $x +@min(1) | 1..10;"
+            ),
+            "type B = [date, Action][] @minlen(1);\ntype C = f64 & !0.5 | $x +@min(1) | 1..10;\n"
+        );
+    }
+
+    #[test]
+    fn multiline_structs_get_trailing_commas_and_keep_comments() {
+        let source = "/** A user */\ntype User = {\n  // the key\n  _id: u64, // primary\n\n\n  name: string /* short */\n};\n\n\n\ntype X = u8; // trailing\n";
+        assert_eq!(
+            format(source),
+            "/** A user */\ntype User = {\n    // the key\n    _id: u64, // primary\n\n    name: string, /* short */\n};\n\ntype X = u8; // trailing\n"
+        );
+    }
+
+    #[test]
+    fn long_declarations_wrap() {
+        let options = FormatConfig {
+            width: 30,
+            indent: 2,
+        };
+        assert_eq!(
+            format_xenomorph(
+                "type User = { name: string @minlen(1), role: Admin | Moderator | Guest };",
+                &options
+            ),
+            "type User = {\n  name: string @minlen(1),\n  role:\n    | Admin\n    | Moderator\n    | Guest,\n};\n"
+        );
+        assert_eq!(
+            format_xenomorph("type L = [first_type, second_type];", &options),
+            "type L = [\n  first_type,\n  second_type,\n];\n"
+        );
+    }
+
+    #[test]
+    fn imports_are_sorted_within_blocks() {
+        assert_eq!(
+            format("import z;\nexport import b/c{Y,X as W};\nimport a;\n\nimport 0;\n// keep\nimport c;\n"),
+            "import a;\nexport import b/c { X as W, Y };\nimport z;\n\nimport 0;\n// keep\nimport c;\n"
+        );
+    }
+
+    #[test]
+    fn broken_declarations_are_left_alone() {
+        let source = "type A = {a: ;\ntype   B = u8;\n";
+        assert_eq!(format(source), source);
+        assert_eq!(format("type C = u8 ü;"), "type C = u8 ü;\n");
+    }
+
+    #[test]
+    fn formatting_keeps_meaning_and_is_idempotent() {
+        let examples = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/examples");
+        let mut sources = vec![];
+        let mut dirs = vec![examples];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|e| e == "xen") {
+                    sources.push(std::fs::read_to_string(path).unwrap());
+                }
+            }
+        }
+
+        for source in sources {
+            let formatted = format(&source);
+            assert_eq!(meaning(&formatted), meaning(&source), "{}", formatted);
+            assert_eq!(format(&formatted), formatted);
+            for width in [10, 40] {
+                let options = FormatConfig { width, indent: 2 };
+                let narrow = format_xenomorph(&source, &options);
+                assert_eq!(meaning(&narrow), meaning(&source), "{}", narrow);
+                assert_eq!(format_xenomorph(&narrow, &options), narrow);
+            }
+        }
+    }
+
+    #[test]
+    fn ranges_format_the_declarations_they_touch() {
+        let source = "type A={a:u8};\ntype   B = u8;\ntype C={c:u8};\n";
+        let start = source.find("B").unwrap();
+        let (range, text) =
+            format_xenomorph_range(source, &FormatConfig::default(), start..start).unwrap();
+        assert_eq!(&source[range], "type   B = u8;");
+        assert_eq!(text, "type B = u8;");
+    }
+}
@@ -0,0 +1,293 @@
+use std::ops::Range;
+
+use crate::cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxTree};
+use crate::lexer::TokenVariant;
+use crate::semantic::{ScopeInfo, BUILTIN_ANNOTATIONS};
+
+/// What a highlighted part of a module is, editors pick its color by this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightKind {
+    /// A declared, imported or builtin type
+    Type,
+    /// `T` of `type Page<T> = ...` and its uses in the declaration
+    TypeParameter,
+    /// A field name, where it's declared, in a `$field` reference or in a member path like `User.name`
+    Field,
+    /// A name inside `enum { ... }`
+    EnumMember,
+    /// The name of an annotation, without its `@`
+    Annotation,
+    /// A segment of an import path
+    Module,
+    Regex,
+    Documentation,
+}
+
+/// A highlighted byte range of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub range: Range<usize>,
+    pub kind: HighlightKind,
+    /// Where the name is declared rather than used
+    pub declaration: bool,
+    /// Builtin types and annotations, types provided by plugins count as builtin
+    pub builtin: bool,
+    /// Types brought into scope by an import
+    pub imported: bool,
+    /// Annotations provided by a plugin
+    pub plugin: bool,
+}
+
+/**
+Classifies the names, regexes and documentation comments of a module against its scope, in source order.
+Unknown types aren't highlighted, keywords, literals and plain comments are left to the editor's grammar.
+*/
+pub fn highlight(tree: &SyntaxTree, scope: &ScopeInfo) -> Vec<Highlight> {
+    let mut highlighter = Highlighter {
+        scope,
+        generics: Vec::new(),
+        highlights: Vec::new(),
+    };
+    for declaration in tree.root.nodes() {
+        highlighter.generics = declaration
+            .nodes()
+            .filter(|node| node.kind == SyntaxKind::Generics)
+            .flat_map(|node| node.tokens())
+            .filter(|t| t.kind == SyntaxKind::Token(TokenVariant::Identifier))
+            .map(|t| t.text.to_string())
+            .collect();
+        highlighter.node(declaration, SyntaxKind::Root);
+    }
+    highlighter.highlights
+}
+
+struct Highlighter<'a> {
+    scope: &'a ScopeInfo,
+    /// Type parameters of the declaration being highlighted
+    generics: Vec<String>,
+    highlights: Vec<Highlight>,
+}
+
+impl Highlighter<'_> {
+    fn push(&mut self, range: Range<usize>, kind: HighlightKind) -> &mut Highlight {
+        self.highlights.push(Highlight {
+            range,
+            kind,
+            declaration: false,
+            builtin: false,
+            imported: false,
+            plugin: false,
+        });
+        self.highlights.last_mut().unwrap()
+    }
+
+    fn node(&mut self, node: &SyntaxNode, parent: SyntaxKind) {
+        // The significant token before the current one, with its offset
+        let mut previous: Option<(TokenVariant, usize)> = None;
+        let mut first = true;
+
+        for child in &node.children {
+            let token = match child {
+                SyntaxElement::Node(child) => {
+                    self.node(child, node.kind);
+                    previous = None;
+                    first = false;
+                    continue;
+                }
+                SyntaxElement::Token(token) => token,
+            };
+            let SyntaxKind::Token(variant) = token.kind else {
+                continue;
+            };
+            let range = token.range();
+
+            match variant {
+                TokenVariant::Documentation => {
+                    self.push(range, HighlightKind::Documentation);
+                }
+                TokenVariant::Regex => {
+                    self.push(range, HighlightKind::Regex);
+                }
+                TokenVariant::Identifier => {
+                    self.identifier(token.text, range, previous, node.kind, parent, first)
+                }
+                _ => {}
+            }
+            previous = Some((variant, token.offset));
+            first = false;
+        }
+    }
+
+    fn identifier(
+        &mut self,
+        name: &str,
+        range: Range<usize>,
+        previous: Option<(TokenVariant, usize)>,
+        kind: SyntaxKind,
+        parent: SyntaxKind,
+        first: bool,
+    ) {
+        match (kind, previous.map(|(variant, _)| variant)) {
+            (SyntaxKind::Import, _) => {
+                if name != "export" {
+                    self.push(range, HighlightKind::Module);
+                }
+            }
+            // `X as Y`, the alias is declared by the import
+            (SyntaxKind::ImportItems, Some(TokenVariant::Identifier)) if name == "as" => {}
+            (SyntaxKind::ImportItems, previous) => {
+                let highlight = self.push(range, HighlightKind::Type);
+                highlight.imported = true;
+                highlight.declaration = previous == Some(TokenVariant::Identifier);
+            }
+            (SyntaxKind::TypeDecl, Some(TokenVariant::Type)) => {
+                self.push(range, HighlightKind::Type).declaration = true;
+            }
+            (SyntaxKind::Generics, _) => {
+                self.push(range, HighlightKind::TypeParameter).declaration = true;
+            }
+            (SyntaxKind::Field, _) if first => {
+                let kind = match parent {
+                    SyntaxKind::Enum => HighlightKind::EnumMember,
+                    _ => HighlightKind::Field,
+                };
+                self.push(range, kind).declaration = true;
+            }
+            (_, Some(TokenVariant::At)) => {
+                let builtin = BUILTIN_ANNOTATIONS.iter().any(|a| a.name == name);
+                // Known annotations are the builtin ones and those of the plugins
+                let plugin = !builtin && self.scope.has_annotation(name);
+                let highlight = self.push(range, HighlightKind::Annotation);
+                highlight.builtin = builtin;
+                highlight.plugin = plugin;
+            }
+            // `$field` is highlighted together with its `$`
+            (_, Some(TokenVariant::Dollar)) => {
+                let start = previous.map_or(range.start, |(_, offset)| offset);
+                self.push(start..range.end, HighlightKind::Field);
+            }
+            (_, Some(TokenVariant::Dot)) => {
+                self.push(range, HighlightKind::Field);
+            }
+            _ => self.type_reference(name, range),
+        }
+    }
+
+    fn type_reference(&mut self, name: &str, range: Range<usize>) {
+        if self.generics.iter().any(|g| g == name) {
+            self.push(range, HighlightKind::TypeParameter);
+        } else if self.scope.own_types.iter().any(|t| t == name) {
+            self.push(range, HighlightKind::Type);
+        } else if self.scope.imports.contains_key(name) || self.scope.provider_of(name).is_some() {
+            self.push(range, HighlightKind::Type).imported = true;
+        } else if self.scope.builtin_types.contains(name) {
+            self.push(range, HighlightKind::Type).builtin = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::semantic::BUILTIN_TYPES;
+
+    fn scope() -> ScopeInfo {
+        ScopeInfo {
+            module_path: "test".to_string(),
+            abs_path: PathBuf::new(),
+            own_types: vec!["Page".to_string(), "User".to_string()],
+            imported_types: HashMap::from([("models/role".to_string(), vec!["Role".to_string()])]),
+            builtin_types: BUILTIN_TYPES.iter().map(|t| t.name.to_string()).collect(),
+            known_annotations: HashSet::from(["custom".to_string()]),
+            declarations: HashMap::new(),
+            imports: HashMap::new(),
+            field_scopes: HashMap::new(),
+            generic_declarations: HashMap::new(),
+        }
+    }
+
+    /// Highlighted text with its kind and flags, e.g. `"User:Type+declaration"`.
+    fn highlights(source: &str) -> Vec<String> {
+        highlight(&SyntaxTree::parse(source), &scope())
+            .into_iter()
+            .map(|h| {
+                let mut s = format!("{}:{:?}", &source[h.range], h.kind);
+                for (set, flag) in [
+                    (h.declaration, "declaration"),
+                    (h.builtin, "builtin"),
+                    (h.imported, "imported"),
+                    (h.plugin, "plugin"),
+                ] {
+                    if set {
+                        s += "+";
+                        s += flag;
+                    }
+                }
+                s
+            })
+            .collect()
+    }
+
+    #[test]
+    fn names_are_classified_by_scope() {
+        assert_eq!(
+            highlights("export import models/role { Role as R };"),
+            [
+                "models:Module",
+                "role:Module",
+                "Role:Type+imported",
+                "R:Type+declaration+imported"
+            ]
+        );
+        assert_eq!(
+            highlights(
+                "/** A user */\ntype User = { role: Role, page: Page<User>, id: u64, x: Nope };"
+            ),
+            [
+                "/** A user */:Documentation",
+                "User:Type+declaration",
+                "role:Field+declaration",
+                "Role:Type+imported",
+                "page:Field+declaration",
+                "Page:Type",
+                "User:Type",
+                "id:Field+declaration",
+                "u64:Type+builtin",
+                "x:Field+declaration",
+            ]
+        );
+    }
+
+    #[test]
+    fn fields_annotations_and_type_parameters() {
+        assert_eq!(
+            highlights(
+                "type Page<T> = { items: T[] @minlen(1) @custom @nope, s: string /^a$/ @if($flag, @min(1)), u: User.name, e: enum { A: 1, B: 2 } };"
+            ),
+            [
+                "Page:Type+declaration",
+                "T:TypeParameter+declaration",
+                "items:Field+declaration",
+                "T:TypeParameter",
+                "minlen:Annotation+builtin",
+                "custom:Annotation+plugin",
+                "nope:Annotation",
+                "s:Field+declaration",
+                "string:Type+builtin",
+                "/^a$/:Regex",
+                "if:Annotation+builtin",
+                "$flag:Field",
+                "min:Annotation+builtin",
+                "u:Field+declaration",
+                "User:Type",
+                "name:Field",
+                "e:Field+declaration",
+                "A:EnumMember+declaration",
+                "B:EnumMember+declaration",
+            ]
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::lexer::{Token, TokenVariant};
+use crate::parser::{BinaryExprType, Declaration, Expr, Literal, NumberType};
+use crate::semantic::{AnalyzerListener, AnnotationValidator, ScopeInfo};
+use crate::TokenData;
+
+/// What an inlay hint shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlayHintKind {
+    /// The integer of an enum variant that doesn't set its own, e.g. `= 2`
+    Discriminant,
+    /// The builtin types behind an alias chain or a member type like `User._id`
+    Type,
+    /// The numeric range left by `@min`, `@max`, `@gt`, `@lt` and range expressions
+    Range,
+}
+
+/// A label shown right after a token of a module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlayHint<'src> {
+    pub after: &'src TokenData<'src>,
+    pub label: String,
+    pub kind: InlayHintKind,
+}
+
+/**
+Hints for the declarations of a module in source order. Aliases are resolved the way `AnnotationValidator`
+resolves them, so only aliases declared in the module itself are followed.
+*/
+pub fn inlay_hints<'src>(
+    ast: &'src [Declaration<'src>],
+    tokens: &'src [Token<'src>],
+    scope: &ScopeInfo,
+) -> Vec<InlayHint<'src>> {
+    let mut validator = AnnotationValidator::new(scope);
+    validator.on_before_ast(ast, &mut Vec::new());
+
+    let mut hinter = Hinter {
+        tokens,
+        validator,
+        aliases: HashMap::new(),
+        generics: Vec::new(),
+        hints: Vec::new(),
+    };
+    for declaration in ast {
+        if let Declaration::TypeDecl { name, t, .. } = declaration {
+            hinter.aliases.insert(name.v, t);
+        }
+    }
+    for declaration in ast {
+        if let Declaration::TypeDecl { generics, t, .. } = declaration {
+            hinter.generics = generics.iter().map(|g| g.v).collect();
+            hinter.type_expr(t);
+        }
+    }
+    hinter.hints
+}
+
+/// One end of a numeric range, with the number as it's written.
+#[derive(Debug, Clone, Copy)]
+struct Bound<'src> {
+    value: f64,
+    text: &'src str,
+    inclusive: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct NumericRange<'src> {
+    lower: Option<Bound<'src>>,
+    upper: Option<Bound<'src>>,
+}
+
+impl<'src> NumericRange<'src> {
+    fn is_unbounded(&self) -> bool {
+        self.lower.is_none() && self.upper.is_none()
+    }
+
+    fn raise_lower(&mut self, bound: Bound<'src>) {
+        let tighter = self.lower.is_none_or(|current| {
+            bound.value > current.value || (bound.value == current.value && !bound.inclusive)
+        });
+        if tighter {
+            self.lower = Some(bound);
+        }
+    }
+
+    fn drop_upper(&mut self, bound: Bound<'src>) {
+        let tighter = self.upper.is_none_or(|current| {
+            bound.value < current.value || (bound.value == current.value && !bound.inclusive)
+        });
+        if tighter {
+            self.upper = Some(bound);
+        }
+    }
+
+    fn intersect(&mut self, other: NumericRange<'src>) {
+        if let Some(lower) = other.lower {
+            self.raise_lower(lower);
+        }
+        if let Some(upper) = other.upper {
+            self.drop_upper(upper);
+        }
+    }
+}
+
+/// Written in the range syntax of the language, `1..10`, `0<.1`, `..5`
+impl fmt::Display for NumericRange<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inclusive = |bound: Option<Bound>| bound.is_none_or(|b| b.inclusive);
+        let operator = match (inclusive(self.lower), inclusive(self.upper)) {
+            (true, true) => "..",
+            (true, false) => ".<",
+            (false, true) => "<.",
+            (false, false) => "<.<",
+        };
+        write!(
+            f,
+            "{}{}{}",
+            self.lower.map_or("", |b| b.text),
+            operator,
+            self.upper.map_or("", |b| b.text)
+        )
+    }
+}
+
+fn number_bound<'src>(expr: &Expr<'src>, inclusive: bool) -> Option<Bound<'src>> {
+    let (value, token) = match expr {
+        Expr::Literal(Literal::Number(NumberType::Int(n, token))) => (*n as f64, token),
+        Expr::Literal(Literal::Number(NumberType::Float(n, token))) => (*n, token),
+        _ => return None,
+    };
+    Some(Bound {
+        value,
+        text: token.v,
+        inclusive,
+    })
+}
+
+fn number_token<'src>(expr: &Expr<'src>) -> Option<&'src TokenData<'src>> {
+    match expr {
+        Expr::Literal(Literal::Number(NumberType::Int(_, token) | NumberType::Float(_, token))) => {
+            Some(token)
+        }
+        _ => None,
+    }
+}
+
+struct Hinter<'src> {
+    tokens: &'src [Token<'src>],
+    validator: AnnotationValidator,
+    /// Type expressions of the declarations of the module by name
+    aliases: HashMap<&'src str, &'src Vec<Expr<'src>>>,
+    /// Type parameters of the declaration being hinted
+    generics: Vec<&'src str>,
+    hints: Vec<InlayHint<'src>>,
+}
+
+impl<'src> Hinter<'src> {
+    fn push(&mut self, after: &'src TokenData<'src>, label: String, kind: InlayHintKind) {
+        self.hints.push(InlayHint { after, label, kind });
+    }
+
+    /// The index of a token the AST points to.
+    fn token_index(&self, token: &TokenData<'_>) -> Option<usize> {
+        self.tokens
+            .binary_search_by_key(&(token.l, token.c), |(_, t)| (t.l, t.c))
+            .ok()
+    }
+
+    /// The `)` closing the arguments of an annotation, or its name when it has none.
+    fn annotation_end(&self, name: &'src TokenData<'src>) -> &'src TokenData<'src> {
+        let Some(start) = self.token_index(name) else {
+            return name;
+        };
+        if self.tokens.get(start + 1).map(|t| t.0) != Some(TokenVariant::LParen) {
+            return name;
+        }
+        let mut depth = 0;
+        for (variant, token) in &self.tokens[start + 1..] {
+            match variant {
+                TokenVariant::LParen => depth += 1,
+                TokenVariant::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return token;
+                    }
+                }
+                _ => {}
+            }
+        }
+        name
+    }
+
+    /// The range a `@min(1)`-like annotation or a `1..10` expression allows, with the token it ends at.
+    fn constraint(
+        &self,
+        expr: &'src Expr<'src>,
+    ) -> Option<(NumericRange<'src>, &'src TokenData<'src>)> {
+        let mut range = NumericRange::default();
+        match expr {
+            Expr::Annotation(name, args) => {
+                let [arg] = args.as_slice() else {
+                    return None;
+                };
+                let [value] = arg.as_slice() else {
+                    return None;
+                };
+                match name.v {
+                    "min" => range.raise_lower(number_bound(value, true)?),
+                    "gt" => range.raise_lower(number_bound(value, false)?),
+                    "max" => range.drop_upper(number_bound(value, true)?),
+                    "lt" => range.drop_upper(number_bound(value, false)?),
+                    _ => return None,
+                }
+                Some((range, self.annotation_end(name)))
+            }
+            Expr::BinaryExpr(BinaryExprType::Range, pair) => {
+                let start = number_token(&pair.0)?;
+                let end = number_token(&pair.1)?;
+                // The AST doesn't keep which of `..`, `.<`, `<.` and `<.<` it was
+                let operator = self
+                    .token_index(start)
+                    .and_then(|i| self.tokens.get(i + 1))
+                    .map_or("..", |(_, t)| t.v);
+                range.raise_lower(number_bound(&pair.0, !operator.starts_with('<'))?);
+                range.drop_upper(number_bound(&pair.1, !operator.ends_with('<'))?);
+                Some((range, end))
+            }
+            _ => None,
+        }
+    }
+
+    /// The range an alias allows through its own constraints and the aliases it's built on.
+    fn alias_range(&self, alias: &str, visited: &mut HashSet<&'src str>) -> NumericRange<'src> {
+        let mut range = NumericRange::default();
+        let Some((&alias, t)) = self.aliases.get_key_value(alias) else {
+            return range;
+        };
+        if !visited.insert(alias) {
+            return range;
+        }
+        for expr in t.iter() {
+            if let Some((constraint, _)) = self.constraint(expr) {
+                range.intersect(constraint);
+            } else if let Expr::Identifier(name) = expr {
+                range.intersect(self.alias_range(name.v, visited));
+            }
+        }
+        visited.remove(alias);
+        range
+    }
+
+    fn type_hint(
+        &mut self,
+        expr: &'src Expr<'src>,
+        after: &'src TokenData<'src>,
+        range: NumericRange<'src>,
+    ) {
+        let mut names = Vec::new();
+        for resolved in self.validator.resolve_types(std::slice::from_ref(expr)) {
+            if !names.contains(&resolved.name) {
+                names.push(resolved.name);
+            }
+        }
+        // Struct types resolve to `dict`, the name of the type says more than that
+        if names.is_empty() || names.contains(&"dict") {
+            return;
+        }
+
+        let mut label = format!(": {}", names.join(" | "));
+        if !range.is_unbounded() {
+            label += &format!(" {}", range);
+        }
+        self.push(after, label, InlayHintKind::Type);
+    }
+
+    /// A list of expressions one type is made of, like a field type or the type of a declaration.
+    fn type_expr(&mut self, exprs: &'src [Expr<'src>]) {
+        let mut range = NumericRange::default();
+        let mut last_constraint = None;
+
+        for expr in exprs {
+            if let Some((constraint, end)) = self.constraint(expr) {
+                range.intersect(constraint);
+                last_constraint = Some(end);
+                continue;
+            }
+            match expr {
+                Expr::Identifier(name) if self.aliases.contains_key(name.v) => {
+                    if !self.generics.contains(&name.v) {
+                        let alias_range = self.alias_range(name.v, &mut HashSet::new());
+                        range.intersect(alias_range);
+                        self.type_hint(expr, name, alias_range);
+                    }
+                }
+                Expr::MemberAccess(base, members) => {
+                    let last = members.last().copied().unwrap_or(base);
+                    self.type_hint(expr, last, NumericRange::default());
+                }
+                _ => self.expr(expr),
+            }
+        }
+
+        if let Some(end) = last_constraint {
+            if !range.is_unbounded() {
+                self.push(end, format!("in {}", range), InlayHintKind::Range);
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &'src Expr<'src>) {
+        match expr {
+            Expr::TypeApplication(_, args) | Expr::List(args) | Expr::Set(args) => {
+                args.iter().for_each(|arg| self.type_expr(arg))
+            }
+            Expr::Struct(fields) => fields.iter().for_each(|(_, t)| self.type_expr(t)),
+            Expr::Enum(variants) => {
+                // Variants without a number of their own count up from the previous one, like in Rust
+                let mut next: i64 = 0;
+                for (name, value) in variants {
+                    match value.as_slice() {
+                        [Expr::Literal(Literal::Number(NumberType::Int(n, _)))] => {
+                            next = n.saturating_add(1)
+                        }
+                        [Expr::Literal(_)] => {}
+                        _ => {
+                            self.push(name, format!("= {}", next), InlayHintKind::Discriminant);
+                            next = next.saturating_add(1);
+                            self.type_expr(value);
+                        }
+                    }
+                }
+            }
+            Expr::BinaryExpr(_, pair) => {
+                self.type_expr(std::slice::from_ref(&pair.0));
+                self.type_expr(std::slice::from_ref(&pair.1));
+            }
+            Expr::Not(inner) => self.type_expr(std::slice::from_ref(inner.as_ref())),
+            Expr::Identifier(_)
+            | Expr::Literal(_)
+            | Expr::Regex(_)
+            | Expr::Annotation(..)
+            | Expr::FieldAccess(_)
+            | Expr::Array(_)
+            | Expr::MemberAccess(..) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::semantic::BUILTIN_TYPES;
+
+    fn scope() -> ScopeInfo {
+        ScopeInfo {
+            module_path: "test".to_string(),
+            abs_path: PathBuf::new(),
+            own_types: Vec::new(),
+            imported_types: HashMap::new(),
+            builtin_types: BUILTIN_TYPES.iter().map(|t| t.name.to_string()).collect(),
+            known_annotations: HashSet::new(),
+            declarations: HashMap::new(),
+            imports: HashMap::new(),
+            field_scopes: HashMap::new(),
+            generic_declarations: HashMap::new(),
+        }
+    }
+
+    /// Hints as `"<token the hint follows> <label>"`.
+    fn hints(source: &str) -> Vec<String> {
+        let (tokens, _) = Lexer::tokenize(source);
+        let (ast, errors) = Parser::parse(&tokens);
+        assert!(errors.is_empty(), "{:?}", errors);
+        inlay_hints(&ast, &tokens, &scope())
+            .into_iter()
+            .map(|h| format!("{} {}", h.after.v, h.label))
+            .collect()
+    }
+
+    #[test]
+    fn enum_variants_count_up_from_the_previous_number() {
+        assert_eq!(
+            hints("type Action = enum { Delete: string, Edit: string, Admin: 5, Query: { from: date } };"),
+            ["Delete = 0", "Edit = 1", "Query = 6"]
+        );
+    }
+
+    #[test]
+    fn aliases_resolve_to_builtin_types_and_ranges() {
+        assert_eq!(
+            hints(
+                "type Channel = f64 @min(0);\n\
+                 type BrightChannel = Channel @min(0.5) @max(1.0);\n\
+                 type Color = { r: BrightChannel, g: BrightChannel @lt(0.8), age: u8 1<.100 };"
+            ),
+            [
+                ") in 0..",
+                "Channel : f64 0..",
+                ") in 0.5..1.0",
+                "BrightChannel : f64 0.5..1.0",
+                "BrightChannel : f64 0.5..1.0",
+                ") in 0.5.<0.8",
+                "100 in 1<.100",
+            ]
+        );
+    }
+}
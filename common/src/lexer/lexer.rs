@@ -1,7 +1,14 @@
 use std::{fmt, iter::Peekable, str::Chars};
 
 use crate::lexer::{Token, TokenVariant, XenoTokens};
-use crate::{TokenData, XenoError};
+use crate::{
+    diagnostic::{codes, ErrorCode, Severity},
+    TokenData, XenoError,
+};
+
+/// Every token the lexer could produce plus every lexical error it ran into.
+/// Erroneous input is kept in the token stream as [`TokenVariant::Error`] tokens.
+pub type XenoLexResult<'src> = (XenoTokens<'src>, Vec<XenoError<'src>>);
 
 static NOT_RECOGNIZED: &str = "Token not recognized";
 static MALFORMED_REGEX: &str = "Malformed regex";
@@ -29,6 +36,7 @@ pub struct Lexer<'src> {
     pub src: &'src str,
     it: Peekable<Chars<'src>>,
     pub location: LexerLocation,
+    errors: Vec<XenoError<'src>>,
 }
 
 impl<'src> Lexer<'src> {
@@ -41,13 +49,14 @@ impl<'src> Lexer<'src> {
                 line: 0,
                 column: 0,
             },
+            errors: Vec::new(),
         }
     }
 
     fn next(&mut self) -> Option<char> {
         let c = self.it.next();
         if let Some(c) = c {
-            self.location.src_index += 1;
+            self.location.src_index += c.len_utf8();
             self.location.column += 1;
             if c == '\n' {
                 self.location.line += 1;
@@ -77,8 +86,12 @@ impl<'src> Lexer<'src> {
 
     fn token_single_at_lexer(&self) -> TokenData<'src> {
         let start = &self.location;
+        let len = self.src[start.src_index..]
+            .chars()
+            .next()
+            .map_or(0, char::len_utf8);
         TokenData {
-            v: &self.src[start.src_index..=start.src_index],
+            v: &self.src[start.src_index..start.src_index + len],
             l: start.line,
             c: start.column,
         }
@@ -96,38 +109,70 @@ impl<'src> Lexer<'src> {
         }
     }
 
-    pub fn tokenize(src: &'src str) -> Result<XenoTokens<'src>, XenoError<'src>> {
+    /**
+    Moves the lexer back to an earlier location, e.g. to resume lexing right
+    after the line of an unterminated string.
+    */
+    fn rewind_to(&mut self, location: LexerLocation) {
+        self.it = self.src[location.src_index..].chars().peekable();
+        self.location = location;
+    }
+
+    /**
+    Records a lexical error and returns the error token covering the offending input,
+    so the caller can push it into the token stream and keep going.
+    */
+    fn error_token(
+        &mut self,
+        code: ErrorCode,
+        message: &str,
+        location: TokenData<'src>,
+    ) -> Token<'src> {
+        self.errors.push(XenoError {
+            message: message.to_string(),
+            location: location.clone(),
+            severity: Severity::Error,
+            code,
+            labels: Vec::new(),
+        });
+        (TokenVariant::Error, location)
+    }
+
+    /// Tokens the parser sees, without whitespace and comments.
+    pub fn tokenize(src: &'src str) -> XenoLexResult<'src> {
+        let (mut tokens, errors) = Self::tokenize_with_trivia(src);
+        tokens.retain(|t| !t.0.is_trivia());
+        (tokens, errors)
+    }
+
+    /**
+    Every token of the source including whitespace and comments, the trivia.
+    Concatenating the tokens gives back the source byte for byte.
+    */
+    pub fn tokenize_with_trivia(src: &'src str) -> XenoLexResult<'src> {
         Self::new(src)._tokenize()
     }
-    fn _tokenize(mut self) -> Result<XenoTokens<'src>, XenoError<'src>> {
+
+    fn _tokenize(mut self) -> XenoLexResult<'src> {
         let mut tokens: XenoTokens<'src> = vec![];
         while let Some(c) = self.peek() {
             let token = match c {
-                ' ' | '\n' | '\t' | '\r' => {
-                    self.next();
-                    continue;
-                }
+                ' ' | '\n' | '\t' | '\r' => self.consume_whitespace(),
                 '/' => {
                     // '/' is a Slash (path separator) only inside import paths.
                     // An import path looks like: Import Identifier Slash Identifier ...
                     // So Slash context = previous token is Identifier AND that Identifier
                     // was preceded by Import or by another Slash (for chained paths).
-                    let is_slash_context = match tokens.len() {
-                        0 | 1 => false,
-                        _ => {
-                            let last = tokens.last().map(|t| t.0);
-                            let second_last = tokens.get(tokens.len() - 2).map(|t| t.0);
-                            last == Some(TokenVariant::Identifier)
-                                && matches!(
-                                    second_last,
-                                    Some(TokenVariant::Import) | Some(TokenVariant::Slash)
-                                )
-                        }
-                    };
-                    match self.consume_comment_slash_or_regex(is_slash_context)? {
-                        None => continue,
-                        Some(t) => t,
-                    }
+                    let mut significant =
+                        tokens.iter().rev().map(|t| t.0).filter(|v| !v.is_trivia());
+                    let last = significant.next();
+                    let second_last = significant.next();
+                    let is_slash_context = last == Some(TokenVariant::Identifier)
+                        && matches!(
+                            second_last,
+                            Some(TokenVariant::Import) | Some(TokenVariant::Slash)
+                        );
+                    self.consume_comment_slash_or_regex(is_slash_context)
                 }
                 'a'..='z' | 'A'..='Z' | '_' => self.consume_word(),
                 '@' => (TokenVariant::At, self.single_char_token_next()),
@@ -150,22 +195,31 @@ impl<'src> Lexer<'src> {
                 '^' => (TokenVariant::Caret, self.single_char_token_next()),
                 '=' => (TokenVariant::Eq, self.single_char_token_next()),
                 '0'..='9' => self.consume_number(),
-                '"' => self.consume_string()?,
+                '"' => self.consume_string(),
                 '.' | '<' => self.consume_range_lt_dot_symmdiff(),
                 '!' => self.consume_not_or_neq(),
                 '\\' => (TokenVariant::Backslash, self.single_char_token_next()),
                 _ => {
-                    return Err(XenoError {
-                        message: NOT_RECOGNIZED.to_string(),
-                        location: self.token_single_at_lexer(),
-                    });
+                    let location = self.single_char_token_next();
+                    self.error_token(codes::UNRECOGNIZED_CHARACTER, NOT_RECOGNIZED, location)
                 }
             };
 
             tokens.push(token);
         }
 
-        Ok(tokens)
+        (tokens, self.errors)
+    }
+
+    fn consume_whitespace(&mut self) -> Token<'src> {
+        let initial_loc = self.location_snapshot();
+        while let Some(' ' | '\n' | '\t' | '\r') = self.peek() {
+            self.next();
+        }
+        (
+            TokenVariant::Whitespace,
+            self.token_from_but_not_including_lexer(&initial_loc),
+        )
     }
 
     fn consume_word(&mut self) -> Token<'src> {
@@ -200,26 +254,37 @@ impl<'src> Lexer<'src> {
         }
     }
 
-    fn consume_string(&mut self) -> Result<Token<'src>, XenoError<'src>> {
+    fn consume_string(&mut self) -> Token<'src> {
         let initial_loc = self.location_snapshot();
         self.next();
 
-        while let Some(c) = self.next() {
+        let mut line_end = None;
+        while let Some(c) = self.peek() {
             match c {
                 '"' => {
-                    return Ok((
+                    self.next();
+                    return (
                         TokenVariant::String,
                         self.token_from_but_not_including_lexer(&initial_loc),
-                    ))
+                    );
                 }
-                _ => continue,
+                '\n' if line_end.is_none() => line_end = Some(self.location_snapshot()),
+                _ => {}
             }
+            self.next();
         }
 
-        Err(XenoError {
-            message: STRING_TERMINATION_ERROR.to_string(),
-            location: self.token_from_but_not_including_lexer(&initial_loc),
-        })
+        // Strings may span lines, so an unterminated one would swallow the rest of the file.
+        // Cut the error token at the end of the opening line and resume lexing from there.
+        if let Some(line_end) = line_end {
+            self.rewind_to(line_end);
+        }
+        let location = self.token_from_but_not_including_lexer(&initial_loc);
+        self.error_token(
+            codes::UNTERMINATED_STRING,
+            STRING_TERMINATION_ERROR,
+            location,
+        )
     }
 
     fn consume_not_or_neq(&mut self) -> Token<'src> {
@@ -305,40 +370,38 @@ impl<'src> Lexer<'src> {
         )
     }
 
-    fn consume_comment_slash_or_regex(
-        &mut self,
-        slash_context: bool,
-    ) -> Result<Option<Token<'src>>, XenoError<'src>> {
+    fn consume_comment_slash_or_regex(&mut self, slash_context: bool) -> Token<'src> {
         let initial_loc = self.location_snapshot();
         self.next(); // skip first '/'
 
         match self.peek() {
-            Some(&'/') => self.skip_line_comment(),
+            Some(&'/') => self.consume_line_comment(initial_loc),
             Some(&'*') => self.consume_doc_comment(initial_loc),
-            _ if slash_context => Ok(Some((
+            _ if slash_context => (
                 TokenVariant::Slash,
                 self.token_from_but_not_including_lexer(&initial_loc),
-            ))),
+            ),
             _ => self.consume_regex(initial_loc),
         }
     }
 
-    fn skip_line_comment(&mut self) -> Result<Option<Token<'src>>, XenoError<'src>> {
+    fn consume_line_comment(&mut self, start: LexerLocation) -> Token<'src> {
         self.next(); // skip second '/'
         while let Some(&c) = self.peek() {
-            self.next(); // skip til after comment
+            // the line break is whitespace of its own
             if c == '\n' {
                 break;
             }
+            self.next();
         }
 
-        Ok(None)
+        (
+            TokenVariant::LineComment,
+            self.token_from_but_not_including_lexer(&start),
+        )
     }
 
-    fn consume_doc_comment(
-        &mut self,
-        start: LexerLocation,
-    ) -> Result<Option<Token<'src>>, XenoError<'src>> {
+    fn consume_doc_comment(&mut self, start: LexerLocation) -> Token<'src> {
         self.next(); // skip '*'
 
         if let Some('*') = self.next() {
@@ -346,7 +409,11 @@ impl<'src> Lexer<'src> {
             if let Some('/') = self.peek() {
                 // '/**/
                 self.next();
-                return Ok(None); // just an empty multiline comment, skip
+                // just an empty multiline comment
+                return (
+                    TokenVariant::BlockComment,
+                    self.token_from_but_not_including_lexer(&start),
+                );
             }
 
             // doc comment, consume until '*/'
@@ -354,10 +421,10 @@ impl<'src> Lexer<'src> {
                 if c == '*' {
                     if let Some('/') = self.peek() {
                         self.next();
-                        return Ok(Some((
+                        return (
                             TokenVariant::Documentation,
                             self.token_from_but_not_including_lexer(&start),
-                        )));
+                        );
                     }
                 }
             }
@@ -368,39 +435,42 @@ impl<'src> Lexer<'src> {
             if c == '*' {
                 if let Some('/') = self.peek() {
                     self.next();
-                    return Ok(None);
+                    return (
+                        TokenVariant::BlockComment,
+                        self.token_from_but_not_including_lexer(&start),
+                    );
                 }
             }
         }
 
-        return Err(XenoError {
-            message: COMMENT_NOT_TERMINATED.to_string(),
-            location: self.token_from_but_not_including_lexer(&start),
-        });
+        let location = self.token_from_but_not_including_lexer(&start);
+        self.error_token(
+            codes::UNTERMINATED_COMMENT,
+            COMMENT_NOT_TERMINATED,
+            location,
+        )
     }
 
-    fn consume_regex(
-        &mut self,
-        start: LexerLocation,
-    ) -> Result<Option<Token<'src>>, XenoError<'src>> {
+    fn consume_regex(&mut self, start: LexerLocation) -> Token<'src> {
         let mut has_escape = false;
-        while let Some(c) = self.next() {
+        while let Some(&c) = self.peek() {
             match c {
+                // Leave the newline alone so lexing resumes on the next line
                 '\n' => break,
                 '\\' => has_escape = !has_escape,
                 '/' if !has_escape => {
-                    return Ok(Some((
+                    self.next();
+                    return (
                         TokenVariant::Regex,
                         self.token_from_but_not_including_lexer(&start),
-                    )))
+                    );
                 }
                 _ => has_escape = false,
             }
+            self.next();
         }
 
-        return Err(XenoError {
-            message: MALFORMED_REGEX.to_string(),
-            location: self.token_from_but_not_including_lexer(&start),
-        });
+        let location = self.token_from_but_not_including_lexer(&start);
+        self.error_token(codes::MALFORMED_REGEX, MALFORMED_REGEX, location)
     }
 }
@@ -1,22 +1,24 @@
 #[cfg(test)]
 mod tests {
-    use crate::lexer::{Lexer, TokenVariant};
+    use crate::lexer::{Lexer, TokenVariant, XenoTokens};
 
     // ── Helpers ──────────────────────────────────────────────────────
 
+    /// Tokenize error-free input, panicking on any lexer error.
+    fn tok_ok(src: &str) -> XenoTokens<'_> {
+        let (tokens, errors) = Lexer::tokenize(src);
+        assert!(errors.is_empty(), "Unexpected lexer errors: {:?}", errors);
+        tokens
+    }
+
     /// Tokenize and return vec of (variant, value_str) for easy assertion.
     fn tok(src: &str) -> Vec<(TokenVariant, &str)> {
-        Lexer::tokenize(src)
-            .unwrap()
-            .into_iter()
-            .map(|(v, td)| (v, td.v))
-            .collect()
+        tok_ok(src).into_iter().map(|(v, td)| (v, td.v)).collect()
     }
 
     /// Tokenize and return vec of (variant, value, line, column).
     fn tok_pos(src: &str) -> Vec<(TokenVariant, &str, u32, u32)> {
-        Lexer::tokenize(src)
-            .unwrap()
+        tok_ok(src)
             .into_iter()
             .map(|(v, td)| (v, td.v, td.l, td.c))
             .collect()
@@ -24,7 +26,8 @@ mod tests {
 
     /// Expect a lexer error containing `msg_fragment`.
     fn tok_err(src: &str, msg_fragment: &str) {
-        let err = Lexer::tokenize(src).unwrap_err();
+        let (_, errors) = Lexer::tokenize(src);
+        let err = errors.first().expect("Expected a lexer error");
         assert!(
             err.message.contains(msg_fragment),
             "Expected error containing '{}', got '{}'",
@@ -897,7 +900,8 @@ mod tests {
 
     #[test]
     fn error_location_unrecognized() {
-        let err = Lexer::tokenize("foo #").unwrap_err();
+        let (_, errors) = Lexer::tokenize("foo #");
+        let err = &errors[0];
         assert_eq!(err.message, "Token not recognized");
         assert_eq!(err.location.l, 0);
         assert_eq!(err.location.c, 4);
@@ -907,7 +911,8 @@ mod tests {
     #[test]
     fn error_location_unterminated_string_multiline() {
         // Unterminated string starting on line 1
-        let err = Lexer::tokenize("foo\n\"hello").unwrap_err();
+        let (_, errors) = Lexer::tokenize("foo\n\"hello");
+        let err = &errors[0];
         assert_eq!(err.message, "String not terminated");
         assert_eq!(err.location.l, 1);
     }
@@ -1104,4 +1109,114 @@ type User = {
         assert_eq!(tokens.len(), 1);
         assert_eq!(tokens[0].0, TokenVariant::Regex);
     }
+
+    // ── Trivia ──────────────────────────────────────────────────────
+
+    #[test]
+    fn trivia_tokens_cover_the_source() {
+        let src = "// head\ntype A /* a */ = /** doc */ u8;/**/\n  /* open";
+        let (tokens, errors) = Lexer::tokenize_with_trivia(src);
+        assert_eq!(errors.len(), 1);
+        let tokens: Vec<(TokenVariant, &str)> = tokens.iter().map(|(v, td)| (*v, td.v)).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenVariant::LineComment, "// head"),
+                (TokenVariant::Whitespace, "\n"),
+                (TokenVariant::Type, "type"),
+                (TokenVariant::Whitespace, " "),
+                (TokenVariant::Identifier, "A"),
+                (TokenVariant::Whitespace, " "),
+                (TokenVariant::BlockComment, "/* a */"),
+                (TokenVariant::Whitespace, " "),
+                (TokenVariant::Eq, "="),
+                (TokenVariant::Whitespace, " "),
+                (TokenVariant::Documentation, "/** doc */"),
+                (TokenVariant::Whitespace, " "),
+                (TokenVariant::Identifier, "u8"),
+                (TokenVariant::Semicolon, ";"),
+                (TokenVariant::BlockComment, "/**/"),
+                (TokenVariant::Whitespace, "\n  "),
+                (TokenVariant::Error, "/* open"),
+            ]
+        );
+        assert_eq!(tokens.iter().map(|(_, v)| *v).collect::<String>(), src);
+    }
+
+    #[test]
+    fn trivia_does_not_end_import_paths() {
+        let tokens = tok("import a /* sub */ / b;");
+        assert_eq!(tokens[2], (TokenVariant::Slash, "/"));
+        assert!(tokens.iter().all(|(v, _)| !v.is_trivia()));
+    }
+
+    // ── Error recovery ──────────────────────────────────────────────
+
+    #[test]
+    fn recovery_reports_every_error_in_one_pass() {
+        let (_, errors) = Lexer::tokenize("type A = #;\ntype B = /abc\ntype C = %;");
+        let found: Vec<(&str, u32, u32)> = errors
+            .iter()
+            .map(|e| (e.message.as_str(), e.location.l, e.location.c))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Token not recognized", 0, 9),
+                ("Malformed regex", 1, 9),
+                ("Token not recognized", 2, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery_keeps_tokens_after_unrecognized() {
+        let (tokens, errors) = Lexer::tokenize("a # b");
+        assert_eq!(errors.len(), 1);
+        let tokens: Vec<(TokenVariant, &str)> = tokens.iter().map(|(v, td)| (*v, td.v)).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenVariant::Identifier, "a"),
+                (TokenVariant::Error, "#"),
+                (TokenVariant::Identifier, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery_unterminated_string_stops_at_line_end() {
+        let (tokens, errors) = Lexer::tokenize("type A = \"oops;\ntype B = string;");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location.v, "\"oops;");
+        assert_eq!(errors[0].location.l, 0);
+        let rest: Vec<&str> = tokens
+            .iter()
+            .skip_while(|(v, _)| *v != TokenVariant::Error)
+            .skip(1)
+            .map(|(_, td)| td.v)
+            .collect();
+        assert_eq!(rest, vec!["type", "B", "=", "string", ";"]);
+    }
+
+    #[test]
+    fn recovery_malformed_regex_resumes_next_line() {
+        let (tokens, errors) = Lexer::tokenize("type A = /abc\ntype B = int;");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location.v, "/abc");
+        assert_eq!(tokens.last().map(|t| t.0), Some(TokenVariant::Semicolon));
+        assert_eq!(
+            tokens.iter().filter(|t| t.0 == TokenVariant::Type).count(),
+            2
+        );
+    }
+
+    #[test]
+    fn non_ascii_input_does_not_panic() {
+        let (tokens, errors) = Lexer::tokenize("a é b");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location.v, "é");
+        assert_eq!(tokens[2].0, TokenVariant::Identifier);
+        assert_eq!(tokens[2].1.v, "b");
+    }
 }
@@ -48,6 +48,28 @@ pub enum TokenVariant {
     RBracket,
 
     Documentation,
+
+    /// Spaces, tabs and line breaks
+    Whitespace,
+    /// `// ...`, without the line break
+    LineComment,
+    /// `/* ... */`, documentation comments are [`TokenVariant::Documentation`] instead
+    BlockComment,
+
+    /// Input the lexer could not make sense of, the matching error is reported separately
+    Error,
+}
+
+impl TokenVariant {
+    /// Whether the parser skips tokens of this kind, only [`Lexer::tokenize_with_trivia`] returns them.
+    ///
+    /// [`Lexer::tokenize_with_trivia`]: crate::lexer::Lexer::tokenize_with_trivia
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            TokenVariant::Whitespace | TokenVariant::LineComment | TokenVariant::BlockComment
+        )
+    }
 }
 
 pub type Token<'src> = (TokenVariant, TokenData<'src>);
@@ -95,6 +117,10 @@ impl fmt::Display for TokenVariant {
             TokenVariant::RBracket => write!(f, "RBracket"),
             TokenVariant::Range => write!(f, "Range"),
             TokenVariant::Documentation => write!(f, "Documentation"),
+            TokenVariant::Whitespace => write!(f, "Whitespace"),
+            TokenVariant::LineComment => write!(f, "LineComment"),
+            TokenVariant::BlockComment => write!(f, "BlockComment"),
+            TokenVariant::Error => write!(f, "Error"),
         }
     }
 }
@@ -1,9 +1,17 @@
 pub mod config;
+pub mod cst;
+pub mod diagnostic;
+pub mod fields;
+pub mod formatter;
+pub mod highlight;
+pub mod inlay_hints;
 pub mod lexer;
 pub mod module;
 pub mod parser;
 pub mod plugins;
+pub mod quick_fixes;
 pub mod semantic;
+pub mod semantic_tokens;
 pub mod utils;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -16,8 +24,14 @@ pub struct TokenData<'src> {
     pub c: u32,
 }
 
+use diagnostic::{ErrorCode, Severity};
+
 #[derive(Clone, Debug)]
 pub struct XenoError<'src> {
     pub location: TokenData<'src>,
     pub message: String,
+    pub severity: Severity,
+    pub code: ErrorCode,
+    /// Secondary spans in the same module, each with a short explanation.
+    pub labels: Vec<(TokenData<'src>, String)>,
 }
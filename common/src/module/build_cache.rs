@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::PluginConfigs;
+use crate::module::types::ModulePath;
+use crate::module::ModuleData;
+use crate::plugins::XenoPlugin;
+use crate::utils::calculate_hash;
+
+/// Where the build cache is stored, relative to the workspace root.
+pub const BUILD_CACHE_RELATIVE_PATH: &str = ".xenomorph/cache/build.json";
+
+/// Bumped whenever the stored format or the meaning of its hashes changes.
+const BUILD_CACHE_VERSION: u32 = 2;
+
+/// What the generators last ran on and wrote. A module whose source, imported sources,
+/// plugins and plugin configuration are all unchanged doesn't have to be generated again,
+/// as long as the files generated for it are still as they were written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildCache {
+    version: u32,
+    /// Loaded plugins as `name@version`, in load order.
+    plugins: Vec<String>,
+    /// Hash of every `[plugins.<name>]` section.
+    config_hash: u64,
+    /// Modules generated without errors.
+    modules: BTreeMap<ModulePath, CachedModule>,
+}
+
+/// Inputs a module was generated from and the files generated for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedModule {
+    pub hash: u64,
+    /// Hashes of every module imported directly or through other modules,
+    /// re-exports make declarations of indirect imports visible too.
+    pub imports: BTreeMap<ModulePath, u64>,
+    /// Hashes of the contents of the generated files, 0 for files that couldn't be read.
+    pub outputs: BTreeMap<PathBuf, u64>,
+}
+
+impl BuildCache {
+    /// An empty cache for the given plugins and their configuration.
+    pub fn new(plugins: &[&XenoPlugin], configs: &PluginConfigs) -> Self {
+        let configs: BTreeMap<&String, _> = configs.iter().collect();
+        Self {
+            version: BUILD_CACHE_VERSION,
+            plugins: plugins
+                .iter()
+                .map(|p| format!("{}@{}", p.name, p.version))
+                .collect(),
+            config_hash: calculate_hash(serde_json::to_string(&configs).unwrap_or_default()),
+            modules: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the cache at `path`. Missing or unreadable caches and caches written
+    /// for other plugins or another plugin configuration are ignored.
+    pub fn load(path: &Path, plugins: &[&XenoPlugin], configs: &PluginConfigs) -> Option<Self> {
+        let stored: BuildCache = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        let current = Self::new(plugins, configs);
+        (stored.version == current.version
+            && stored.plugins == current.plugins
+            && stored.config_hash == current.config_hash)
+            .then_some(stored)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn insert(&mut self, module_path: ModulePath, module: CachedModule) {
+        self.modules.insert(module_path, module);
+    }
+
+    /// Whether `module_path` was generated from exactly what is in `cache` now,
+    /// and every file generated for it is unchanged.
+    pub fn is_fresh(&self, module_path: &str, cache: &HashMap<ModulePath, ModuleData>) -> bool {
+        self.modules.get(module_path).is_some_and(|cached| {
+            CachedModule::inputs(module_path, cache)
+                .is_some_and(|(hash, imports)| cached.hash == hash && cached.imports == imports)
+                && cached
+                    .outputs
+                    .iter()
+                    .all(|(path, hash)| fs::read(path).is_ok_and(|c| calculate_hash(c) == *hash))
+        })
+    }
+
+    /// The files generated for `module_path` last time.
+    pub fn outputs(&self, module_path: &str) -> Vec<PathBuf> {
+        self.modules
+            .get(module_path)
+            .map(|cached| cached.outputs.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl CachedModule {
+    /// The current inputs and outputs of a loaded module, `None` if it isn't loaded.
+    pub fn of(module_path: &str, cache: &HashMap<ModulePath, ModuleData>) -> Option<Self> {
+        let (hash, imports) = Self::inputs(module_path, cache)?;
+        let outputs = cache[module_path]
+            .borrow_outputs()
+            .iter()
+            .map(|path| (path.clone(), fs::read(path).map_or(0, calculate_hash)))
+            .collect();
+        Some(Self {
+            hash,
+            imports,
+            outputs,
+        })
+    }
+
+    /// Hash of a loaded module and of everything it imports.
+    fn inputs(
+        module_path: &str,
+        cache: &HashMap<ModulePath, ModuleData>,
+    ) -> Option<(u64, BTreeMap<ModulePath, u64>)> {
+        let module = cache.get(module_path)?;
+        let mut imports = BTreeMap::new();
+        let mut pending: Vec<&str> = module
+            .borrow_imports()
+            .iter()
+            .map(|i| i.module_path.as_str())
+            .collect();
+        while let Some(path) = pending.pop() {
+            if path == module_path || imports.contains_key(path) {
+                continue;
+            }
+            // Imports that failed to load still count, loading them later changes the inputs
+            let imported = cache.get(path);
+            imports.insert(path.to_string(), imported.map_or(0, |m| *m.borrow_hash()));
+            if let Some(imported) = imported {
+                pending.extend(
+                    imported
+                        .borrow_imports()
+                        .iter()
+                        .map(|i| i.module_path.as_str()),
+                );
+            }
+        }
+
+        Some((*module.borrow_hash(), imports))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::XenoRegistry;
+    use crate::utils::calculate_hash;
+    use std::path::PathBuf;
+
+    fn workspace(modules: &[(&str, &str)]) -> HashMap<ModulePath, ModuleData> {
+        modules
+            .iter()
+            .map(|(path, src)| {
+                let md = XenoRegistry::_create_module_data(
+                    &path.to_string(),
+                    PathBuf::from(format!("/ws/{}.xen", path)),
+                    src.to_string(),
+                    calculate_hash(src),
+                );
+                (path.to_string(), md)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn modules_are_stale_when_an_indirect_import_changes() {
+        let before = workspace(&[
+            ("main", "import lib;\ntype X = A;"),
+            ("lib", "export import base;"),
+            ("base", "type A = string;"),
+        ]);
+        let mut build_cache = BuildCache::new(&[], &PluginConfigs::new());
+        for path in ["main", "lib", "base"] {
+            build_cache.insert(path.to_string(), CachedModule::of(path, &before).unwrap());
+        }
+        assert!(build_cache.is_fresh("main", &before));
+        assert_eq!(build_cache.modules["main"].imports.len(), 2);
+
+        let after = workspace(&[
+            ("main", "import lib;\ntype X = A;"),
+            ("lib", "export import base;"),
+            ("base", "type A = u8;"),
+        ]);
+        assert!(!build_cache.is_fresh("main", &after));
+        assert!(build_cache.is_fresh("lib", &before));
+        assert!(!build_cache.is_fresh("unknown", &after));
+    }
+
+    #[test]
+    fn caches_of_other_plugin_configurations_are_ignored() {
+        let path =
+            std::env::temp_dir().join(format!("xeno-build-cache-{}.json", std::process::id()));
+        let configs = PluginConfigs::new();
+        BuildCache::new(&[], &configs).save(&path).unwrap();
+        assert!(BuildCache::load(&path, &[], &configs).is_some());
+
+        let mut other = PluginConfigs::new();
+        other.insert("typescript".to_string(), toml::Value::Boolean(true));
+        assert!(BuildCache::load(&path, &[], &other).is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
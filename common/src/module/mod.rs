@@ -1,18 +1,32 @@
 use ouroboros::self_referencing;
-use std::collections::{HashMap, HashSet};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tokio::sync::RwLock;
 
+pub mod build_cache;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod types;
 
 use crate::config::Config;
+use crate::cst::SyntaxTree;
+use crate::diagnostic::{codes, Severity};
+use crate::fields::FieldNames;
 use crate::lexer::{Lexer, Token, XenoTokens};
-use crate::module::types::{DeclarationInfo, ErrorPhase, ModuleError, ModulePath};
-use crate::parser::{Declaration, Expr, Parser, XenoAst};
+use crate::module::build_cache::{BuildCache, CachedModule};
+use crate::module::types::{
+    DeclarationInfo, ErrorPhase, FieldInfo, FieldReferences, ImportedName, MemberPaths,
+    ModuleError, ModuleImport, ModulePath, RefreshSummary, RelatedLocation, TypeRelation, TypeUse,
+};
+use crate::parser::{
+    BinaryExprType, Declaration, DeclarationSpan, Expr, Parser, PreviousParse, XenoAst,
+};
 use crate::plugins::XenoPlugin;
-use crate::semantic::Analyzer;
+use crate::semantic::{Analyzer, ScopeInfo};
 use crate::utils::calculate_hash;
+use crate::TokenData;
 
 /// Information about a single module (one .xen file).
 /// Owns the source text so that all borrows from tokens/ast remain valid.
@@ -34,23 +48,580 @@ pub struct ModuleData {
     pub analyzer_errors: Vec<ModuleError>,
     /// Module-level errors (file not found, import resolution, etc.)
     pub module_errors: Vec<ModuleError>,
-    /// Modules that this module imports
-    pub imports: Vec<ModulePath>,
+    /// Files the generators wrote for the module, as of its last analysis
+    pub outputs: Vec<PathBuf>,
+    /// Modules that this module imports, with the names it takes from them
+    pub imports: Vec<ModuleImport>,
     /// Changed flag
     pub changed: bool,
-    /// Tokens of the module
+    /// Token range of every declaration in `ast`, lets the next version reuse unchanged ones
+    pub declaration_spans: Vec<DeclarationSpan>,
+    /// Tokens of the module, whitespace and comments included
     #[borrows(source)]
     #[covariant]
+    pub all_tokens: XenoTokens<'this>,
+    /// Tokens of the module the parser sees, without whitespace and comments
+    #[borrows(all_tokens)]
+    #[covariant]
     pub tokens: XenoTokens<'this>,
     /// AST of the module
     #[borrows(tokens)]
     #[covariant]
     pub ast: XenoAst<'this>,
+    /// Lossless syntax tree of the module, built from `all_tokens` and the declarations of `ast`
+    #[borrows(source, all_tokens)]
+    #[covariant]
+    pub syntax_tree: SyntaxTree<'this>,
     #[borrows(ast, abs_path, module_path)]
     #[covariant]
     pub declarations: HashMap<&'this str, DeclarationInfo>,
 }
 
+impl ModuleData {
+    /// Declarations brought into scope by this module's imports.
+    /// Re-exports of imported modules are followed, self-imports and modules that aren't loaded are skipped.
+    pub fn imported_declarations<'c>(
+        &'c self,
+        cache: &'c HashMap<ModulePath, ModuleData>,
+    ) -> Vec<ImportedDeclaration<'c>> {
+        let mut visiting = vec![self.borrow_module_path().as_str()];
+        self.imports_through(cache, |_| true, &mut visiting)
+    }
+
+    /// Declarations importers of this module can see, as `(exported name, declaration)`:
+    /// its own declarations followed by everything its `export import`s bring in.
+    pub fn exported_declarations<'c>(
+        &'c self,
+        cache: &'c HashMap<ModulePath, ModuleData>,
+    ) -> Vec<(&'c str, &'c DeclarationInfo)> {
+        let mut visiting = vec![self.borrow_module_path().as_str()];
+        self.exports_through(cache, &mut visiting)
+    }
+
+    fn exports_through<'c>(
+        &'c self,
+        cache: &'c HashMap<ModulePath, ModuleData>,
+        visiting: &mut Vec<&'c str>,
+    ) -> Vec<(&'c str, &'c DeclarationInfo)> {
+        let mut exported: Vec<(&str, &DeclarationInfo)> = self
+            .borrow_declarations()
+            .iter()
+            .map(|(name, d)| (*name, d))
+            .collect();
+        exported.sort_unstable_by_key(|(name, _)| *name);
+        exported.extend(
+            self.imports_through(cache, |import| import.exported, visiting)
+                .into_iter()
+                .map(|i| (i.local, i.declaration)),
+        );
+        exported
+    }
+
+    /// Resolves the imports accepted by `filter`. `visiting` holds the modules whose exports are
+    /// being collected, so re-export cycles end instead of recursing forever.
+    fn imports_through<'c>(
+        &'c self,
+        cache: &'c HashMap<ModulePath, ModuleData>,
+        filter: impl Fn(&ModuleImport) -> bool,
+        visiting: &mut Vec<&'c str>,
+    ) -> Vec<ImportedDeclaration<'c>> {
+        let mut visible = Vec::new();
+        for import in self.borrow_imports().iter().filter(|i| filter(i)) {
+            if visiting.contains(&import.module_path.as_str()) {
+                continue;
+            }
+            let Some(module) = cache.get(&import.module_path) else {
+                continue;
+            };
+
+            visiting.push(&import.module_path);
+            let exported = module.exports_through(cache, visiting);
+            visiting.pop();
+
+            let names: Vec<&str> = exported.iter().map(|(name, _)| *name).collect();
+            for (local, name) in import.bindings(&names) {
+                if let Some((name, declaration)) = exported.iter().find(|(n, _)| *n == name) {
+                    visible.push(ImportedDeclaration {
+                        import,
+                        local,
+                        name,
+                        declaration,
+                    });
+                }
+            }
+        }
+        visible
+    }
+
+    /// Reports names declared more than once in this module, local declarations clashing
+    /// with explicitly imported names, and names that imports bring in from different declarations.
+    /// Every error points at the other declarations involved through `related`.
+    pub fn declaration_conflicts(
+        &self,
+        cache: &HashMap<ModulePath, ModuleData>,
+    ) -> Vec<ModuleError> {
+        let module_path = self.borrow_module_path();
+        let abs_path = self.borrow_abs_path();
+        let mut errors = Vec::new();
+
+        // Own declarations in source order, grouped by name
+        type NameLocations<'a> = Vec<(&'a str, Vec<(u32, u32, u32)>)>;
+        let mut own: NameLocations = Vec::new();
+        for decl in self.borrow_ast() {
+            if let Declaration::TypeDecl { name, .. } = decl {
+                let loc = (name.l, name.c, name.v.len() as u32);
+                match own.iter_mut().find(|(n, _)| *n == name.v) {
+                    Some((_, locs)) => locs.push(loc),
+                    None => own.push((name.v, vec![loc])),
+                }
+            }
+        }
+        for (name, locs) in &own {
+            for (i, loc) in locs.iter().enumerate().skip(1) {
+                errors.push(ModuleError {
+                    module_path: module_path.clone(),
+                    message: format!("Duplicate declaration of '{}'", name),
+                    location: Some(*loc),
+                    phase: ErrorPhase::Analyzer,
+                    severity: Severity::Error,
+                    code: codes::DUPLICATE_DECLARATION,
+                    related: locs
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(_, other)| RelatedLocation {
+                            module_path: module_path.clone(),
+                            abs_path: abs_path.clone(),
+                            location: *other,
+                            message: format!("'{}' is also declared here", name),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        // Imported names grouped by the name they are visible as, one entry per distinct declaration
+        let mut imported: Vec<(&str, Vec<(&ModuleImport, &DeclarationInfo)>)> = Vec::new();
+        for ImportedDeclaration {
+            import,
+            local,
+            declaration: info,
+            ..
+        } in self.imported_declarations(cache)
+        {
+            let entry = match imported.iter_mut().find(|(n, _)| *n == local) {
+                Some((_, entry)) => entry,
+                None => {
+                    imported.push((local, Vec::new()));
+                    &mut imported.last_mut().unwrap().1
+                }
+            };
+            let same_declaration = |(_, d): &(&ModuleImport, &DeclarationInfo)| {
+                d.module_path == info.module_path && d.name == info.name
+            };
+            if !entry.iter().any(same_declaration) {
+                entry.push((import, info));
+            }
+        }
+
+        let declared_here = |info: &DeclarationInfo| RelatedLocation {
+            module_path: info.module_path.clone(),
+            abs_path: info.abs_path.clone(),
+            location: (info.line, info.column, info.name_len),
+            message: format!("'{}' is declared here", info.name),
+        };
+
+        for (local, sources) in &imported {
+            if let Some((_, locs)) = own.iter().find(|(n, _)| n == local) {
+                // Own declarations shadow whole-module imports, but not names listed explicitly
+                for (import, info) in sources.iter().filter(|(i, _)| i.names.is_some()) {
+                    errors.push(ModuleError {
+                        module_path: module_path.clone(),
+                        message: format!(
+                            "'{}' is imported from '{}' and also declared in this module",
+                            local, import.module_path
+                        ),
+                        location: Some(locs[0]),
+                        phase: ErrorPhase::Analyzer,
+                        severity: Severity::Error,
+                        code: codes::CONFLICTING_IMPORT,
+                        related: vec![
+                            RelatedLocation {
+                                module_path: module_path.clone(),
+                                abs_path: abs_path.clone(),
+                                location: import.location_of(local),
+                                message: format!("'{}' is imported here", local),
+                            },
+                            declared_here(info),
+                        ],
+                    });
+                }
+                continue;
+            }
+
+            for (i, (import, _)) in sources.iter().enumerate().skip(1) {
+                errors.push(ModuleError {
+                    module_path: module_path.clone(),
+                    message: format!(
+                        "'{}' is ambiguous, it is imported from {}",
+                        local,
+                        sources
+                            .iter()
+                            .map(|(import, _)| format!("'{}'", import.module_path))
+                            .collect::<Vec<_>>()
+                            .join(" and ")
+                    ),
+                    location: Some(import.location_of(local)),
+                    phase: ErrorPhase::Analyzer,
+                    severity: Severity::Error,
+                    code: codes::AMBIGUOUS_IMPORT,
+                    related: sources
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(_, (other, _))| RelatedLocation {
+                            module_path: module_path.clone(),
+                            abs_path: abs_path.clone(),
+                            location: other.location_of(local),
+                            message: format!("'{}' is also imported here", local),
+                        })
+                        .chain(sources.iter().map(|(_, info)| declared_here(info)))
+                        .collect(),
+                });
+            }
+        }
+
+        errors
+    }
+}
+
+/// A declaration made visible by an import.
+#[derive(Debug, Clone, Copy)]
+pub struct ImportedDeclaration<'c> {
+    /// The import that brings the declaration into scope.
+    pub import: &'c ModuleImport,
+    /// The name it is visible as in the importing module.
+    pub local: &'c str,
+    /// The name the imported module exports it as, differs from `declaration.name` for aliased re-exports.
+    pub name: &'c str,
+    /// The declaration itself, in the module that declares it.
+    pub declaration: &'c DeclarationInfo,
+}
+
+/// Collects the fields of every struct in a type declaration, `None` if there are none.
+fn collect_fields(t: &[Expr]) -> Option<Vec<FieldInfo>> {
+    let fields: Vec<FieldInfo> = t
+        .iter()
+        .filter_map(|item| match item {
+            Expr::Struct(fields) => Some(fields),
+            _ => None,
+        })
+        .flatten()
+        .map(|(key, value)| FieldInfo {
+            name: key.v.to_string(),
+            type_name: value
+                .iter()
+                .filter(|e| !matches!(e, Expr::Annotation(..)))
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            line: key.l,
+            column: key.c,
+        })
+        .collect();
+    (!fields.is_empty()).then_some(fields)
+}
+
+/// Types a declaration body refers to with how it refers to them, type parameters included.
+/// Arguments of annotations aren't types and are skipped.
+fn type_references<'src>(t: &[Expr<'src>]) -> Vec<(&'src TokenData<'src>, TypeRelation)> {
+    let mut references = Vec::new();
+    for expr in t {
+        collect_type_references(expr, TypeRelation::Extends, &mut references);
+    }
+    references
+}
+
+fn collect_type_references<'src>(
+    expr: &Expr<'src>,
+    relation: TypeRelation,
+    references: &mut Vec<(&'src TokenData<'src>, TypeRelation)>,
+) {
+    let embedded = |exprs: &[Expr<'src>], references: &mut Vec<_>| {
+        for expr in exprs {
+            collect_type_references(expr, TypeRelation::Embeds, references);
+        }
+    };
+    match expr {
+        Expr::Identifier(name) => references.push((*name, relation)),
+        Expr::TypeApplication(name, args) => {
+            references.push((*name, relation));
+            args.iter().for_each(|arg| embedded(arg, references));
+        }
+        Expr::Array(name) | Expr::MemberAccess(name, _) => {
+            references.push((*name, TypeRelation::Embeds))
+        }
+        // Operands of `&` in a declaration's own type are intersected, deeper down they stay embedded
+        Expr::BinaryExpr(BinaryExprType::Union, operands) => {
+            let relation = relation.min(TypeRelation::Intersects);
+            collect_type_references(&operands.0, relation, references);
+            collect_type_references(&operands.1, relation, references);
+        }
+        Expr::BinaryExpr(_, operands) => {
+            collect_type_references(&operands.0, TypeRelation::Embeds, references);
+            collect_type_references(&operands.1, TypeRelation::Embeds, references);
+        }
+        Expr::Not(inner) => collect_type_references(inner, TypeRelation::Embeds, references),
+        Expr::List(items) | Expr::Set(items) => {
+            items.iter().for_each(|item| embedded(item, references))
+        }
+        Expr::Struct(fields) | Expr::Enum(fields) => fields
+            .iter()
+            .for_each(|(_, value)| embedded(value, references)),
+        Expr::Annotation(..) | Expr::Literal(_) | Expr::Regex(_) | Expr::FieldAccess(_) => {}
+    }
+}
+
+/// (referring declaration, referenced declaration, relation, (line, column, length))
+type ResolvedTypeReference<'c> = (&'c str, &'c DeclarationInfo, TypeRelation, (u32, u32, u32));
+
+/// Type references of every declaration in a module resolved in its scope.
+fn resolved_type_references<'c>(
+    module: &'c ModuleData,
+    cache: &'c HashMap<ModulePath, ModuleData>,
+) -> Vec<ResolvedTypeReference<'c>> {
+    let imported = module.imported_declarations(cache);
+    let declarations = module.borrow_declarations();
+
+    let mut resolved = Vec::new();
+    for decl in module.borrow_ast() {
+        let Declaration::TypeDecl {
+            name, generics, t, ..
+        } = decl
+        else {
+            continue;
+        };
+        for (reference, relation) in type_references(t) {
+            if generics.iter().any(|g| g.v == reference.v) {
+                continue;
+            }
+            let target = declarations.get(reference.v).or_else(|| {
+                imported
+                    .iter()
+                    .find(|i| i.local == reference.v)
+                    .map(|i| i.declaration)
+            });
+            if let Some(target) = target {
+                let location = (reference.l, reference.c, reference.v.len() as u32);
+                resolved.push((name.v, target, relation, location));
+            }
+        }
+    }
+    resolved
+}
+
+/**
+Finds the import cycles among the cached modules in one pass over the import graph.
+Every import that is part of a cycle gets an error showing the whole cycle from the importing module on,
+the other imports of the cycle are attached as related locations.
+Each cycle is searched for once and reported on all of its imports. Self-imports are left to the analyzer.
+*/
+pub fn import_cycle_errors(
+    cache: &HashMap<ModulePath, ModuleData>,
+    importers: &HashMap<ModulePath, BTreeSet<ModulePath>>,
+    severity: Severity,
+) -> HashMap<ModulePath, Vec<ModuleError>> {
+    let mut nodes: Vec<&str> = cache.keys().map(|path| path.as_str()).collect();
+    nodes.sort_unstable();
+    // The importers graph is the import graph reversed, both have the same components
+    let importers_of = |path: &str| -> Vec<&str> {
+        importers
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(|importer| importer.as_str())
+            .filter(|importer| *importer != path && cache.contains_key(*importer))
+            .collect()
+    };
+
+    let mut errors: HashMap<ModulePath, Vec<ModuleError>> = HashMap::new();
+    for component in strongly_connected(&nodes, importers_of) {
+        if component.len() < 2 {
+            continue;
+        }
+        let imports_in = |path: &str| -> Vec<&ModuleImport> {
+            cache[path]
+                .borrow_imports()
+                .iter()
+                .filter(|i| i.module_path != path && component.contains(&i.module_path.as_str()))
+                .collect()
+        };
+
+        let mut reported: HashSet<(&str, &str)> = HashSet::new();
+        for &module in &component {
+            for import in imports_in(module) {
+                if reported.contains(&(module, import.module_path.as_str())) {
+                    continue;
+                }
+                let cycle = shortest_cycle(module, &import.module_path, &imports_in);
+                for i in 0..cycle.len() - 1 {
+                    if !reported.insert((cycle[i], cycle[i + 1])) {
+                        continue;
+                    }
+                    // The cycle as seen from the module importing `cycle[i + 1]`
+                    let rotated: Vec<&str> = cycle[i..cycle.len() - 1]
+                        .iter()
+                        .chain(&cycle[..=i])
+                        .copied()
+                        .collect();
+                    let md = &cache[cycle[i]];
+                    let related: Vec<RelatedLocation> = rotated
+                        .windows(2)
+                        .skip(1)
+                        .filter_map(|pair| {
+                            let module = cache.get(pair[0])?;
+                            let next = module
+                                .borrow_imports()
+                                .iter()
+                                .find(|i| i.module_path == pair[1])?;
+                            Some(RelatedLocation {
+                                module_path: pair[0].to_string(),
+                                abs_path: module.borrow_abs_path().clone(),
+                                location: next.location,
+                                message: format!("'{}' imports '{}' here", pair[0], pair[1]),
+                            })
+                        })
+                        .collect();
+                    let offending = md
+                        .borrow_imports()
+                        .iter()
+                        .filter(|import| import.module_path == cycle[i + 1]);
+                    for import in offending {
+                        errors
+                            .entry(cycle[i].to_string())
+                            .or_default()
+                            .push(ModuleError {
+                                module_path: cycle[i].to_string(),
+                                message: format!("Import cycle: {}", rotated.join(" -> ")),
+                                location: Some(import.location),
+                                phase: ErrorPhase::Module,
+                                severity,
+                                code: codes::IMPORT_CYCLE,
+                                related: related.clone(),
+                            });
+                    }
+                }
+            }
+        }
+    }
+    for module_errors in errors.values_mut() {
+        module_errors.sort_by_key(|e| e.location);
+    }
+    errors
+}
+
+/// Shortest chain of modules from `module` through `import` back to `module`,
+/// e.g. `["a", "b", "c", "a"]` for the import of `b` in `a`. `imports_in` only leads to modules of one cycle.
+fn shortest_cycle<'c>(
+    module: &'c str,
+    import: &'c str,
+    imports_in: &impl Fn(&str) -> Vec<&'c ModuleImport>,
+) -> Vec<&'c str> {
+    // Breadth-first search back to the module, remembering where each module was reached from
+    let mut parents: HashMap<&str, &str> = HashMap::from([(import, module)]);
+    let mut queue = VecDeque::from([import]);
+    while let Some(current) = queue.pop_front() {
+        if current == module {
+            break;
+        }
+        for next in imports_in(current) {
+            let next = next.module_path.as_str();
+            if !parents.contains_key(next) {
+                parents.insert(next, current);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let mut cycle = vec![module];
+    let mut step = module;
+    loop {
+        step = parents[step];
+        cycle.push(step);
+        if step == module {
+            break;
+        }
+    }
+    cycle.reverse();
+    cycle
+}
+
+/// Strongly connected components of the graph of `nodes` and their `successors`, found with Tarjan's algorithm.
+/// Each component is sorted like `nodes`.
+fn strongly_connected<'a>(
+    nodes: &[&'a str],
+    successors: impl Fn(&'a str) -> Vec<&'a str>,
+) -> Vec<Vec<&'a str>> {
+    struct Search<'a> {
+        /// Visit order and lowest reachable visit order of every visited node
+        order: HashMap<&'a str, (usize, usize)>,
+        stack: Vec<&'a str>,
+        on_stack: HashSet<&'a str>,
+        components: Vec<Vec<&'a str>>,
+    }
+
+    fn visit<'a>(
+        node: &'a str,
+        search: &mut Search<'a>,
+        successors: &impl Fn(&'a str) -> Vec<&'a str>,
+    ) {
+        let index = search.order.len();
+        search.order.insert(node, (index, index));
+        search.stack.push(node);
+        search.on_stack.insert(node);
+
+        for next in successors(node) {
+            let low = match search.order.get(next) {
+                None => {
+                    visit(next, search, successors);
+                    search.order[next].1
+                }
+                Some(&(index, _)) if search.on_stack.contains(next) => index,
+                Some(_) => continue,
+            };
+            let entry = search.order.get_mut(node).unwrap();
+            entry.1 = entry.1.min(low);
+        }
+
+        let (index, low) = search.order[node];
+        if index == low {
+            let mut component = Vec::new();
+            while let Some(member) = search.stack.pop() {
+                search.on_stack.remove(member);
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            search.components.push(component);
+        }
+    }
+
+    let mut search = Search {
+        order: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+    for node in nodes {
+        if !search.order.contains_key(node) {
+            visit(node, &mut search, &successors);
+        }
+    }
+    search.components
+}
+
 /// Determines the workspace root and entry module path from the config.
 fn get_root() -> Result<(PathBuf, String), ModuleError> {
     let config = Config::get();
@@ -61,6 +632,9 @@ fn get_root() -> Result<(PathBuf, String), ModuleError> {
         message: format!("Cannot resolve entry file '{:?}': {}", joined, e),
         location: None,
         phase: ErrorPhase::Module,
+        severity: Severity::Error,
+        code: codes::FILE_ACCESS,
+        related: Vec::new(),
     })?;
 
     let root_err = || ModuleError {
@@ -71,6 +645,9 @@ fn get_root() -> Result<(PathBuf, String), ModuleError> {
         ),
         location: None,
         phase: ErrorPhase::Module,
+        severity: Severity::Error,
+        code: codes::FILE_ACCESS,
+        related: Vec::new(),
     };
 
     let root = entry_file
@@ -93,13 +670,23 @@ fn get_root() -> Result<(PathBuf, String), ModuleError> {
                 ),
                 location: None,
                 phase: ErrorPhase::Module,
+                severity: Severity::Error,
+                code: codes::FILE_ACCESS,
+                related: Vec::new(),
             })?,
     ))
 }
 
+/// Analyzer and module errors of one module and the files generated for it, waiting to be stored in the cache.
+type Analysis = (Vec<ModuleError>, Vec<ModuleError>, Vec<PathBuf>);
+
 /// Thread-safe module registry. Single source of truth for all module data.
 pub struct XenoRegistry {
     pub module_cache: RwLock<HashMap<ModulePath, ModuleData>>,
+    /// Reverse of `ModuleData.imports`: every module path to the cached modules importing it.
+    pub importers: RwLock<HashMap<ModulePath, BTreeSet<ModulePath>>>,
+    /// What the generators ran on last time, plugins are skipped for modules that are still fresh.
+    pub build_cache: Option<BuildCache>,
     pub root: PathBuf,
     pub entry: String,
     pub plugins: &'static Vec<&'static XenoPlugin<'static>>,
@@ -112,6 +699,8 @@ impl XenoRegistry {
         let plugins = XenoPlugin::get_plugins();
         Ok(XenoRegistry {
             module_cache: RwLock::new(HashMap::default()),
+            importers: RwLock::new(HashMap::default()),
+            build_cache: None,
             root,
             entry,
             analyzer: Analyzer::new(generation_mode, plugins),
@@ -120,10 +709,11 @@ impl XenoRegistry {
     }
 
     /// Initializes a new `XenoRegistry` and loads the entire workspace starting from the entry module.
+    /// Fails with every diagnostic if any of them is an error, warnings stay available in the cache.
     pub fn load_workspace(generation_mode: bool) -> Result<XenoRegistry, Vec<ModuleError>> {
         let reg = XenoRegistry::new(generation_mode).map_err(|e| vec![e])?;
-        let errs = reg.load_module(&[&reg.entry], true, None);
-        if !errs.is_empty() {
+        let errs = reg.load_module_tree(&[&reg.entry]);
+        if errs.iter().any(|e| e.severity == Severity::Error) {
             return Err(errs);
         }
         Ok(reg)
@@ -133,8 +723,12 @@ impl XenoRegistry {
 
     /// Converts an absolute file path to a ModulePath relative to the workspace root.
     /// e.g. "C:/workspace/api/user.xen" → "api/user"
+    /// Files that no longer exist are resolved through their directory.
     pub fn abs_path_to_module_path(&self, abs_path: &Path) -> Option<ModulePath> {
-        let canonical = abs_path.canonicalize().ok()?;
+        let canonical = abs_path.canonicalize().ok().or_else(|| {
+            let dir = abs_path.parent()?.canonicalize().ok()?;
+            Some(dir.join(abs_path.file_name()?))
+        })?;
         let relative = canonical.strip_prefix(&self.root).ok()?;
         Some(relative.with_extension("").to_str()?.replace('\\', "/"))
     }
@@ -148,6 +742,9 @@ impl XenoRegistry {
             message: format!("Cannot resolve URI '{}': {}", uri, e),
             location: None,
             phase: ErrorPhase::Module,
+            severity: Severity::Error,
+            code: codes::FILE_ACCESS,
+            related: Vec::new(),
         });
         let path = match path_res {
             Ok(p) => p,
@@ -167,6 +764,9 @@ impl XenoRegistry {
                     ),
                     location: None,
                     phase: ErrorPhase::Module,
+                    severity: Severity::Error,
+                    code: codes::FILE_ACCESS,
+                    related: Vec::new(),
                 }]
             }
         };
@@ -195,6 +795,9 @@ impl XenoRegistry {
                     ),
                     location: None,
                     phase: ErrorPhase::Module,
+                    severity: Severity::Error,
+                    code: codes::FILE_ACCESS,
+                    related: Vec::new(),
                 }]
             }
         };
@@ -207,6 +810,9 @@ impl XenoRegistry {
                     message: format!("Cannot canonicalize '{}': {}", abs_path.display(), e),
                     location: None,
                     phase: ErrorPhase::Module,
+                    severity: Severity::Error,
+                    code: codes::FILE_ACCESS,
+                    related: Vec::new(),
                 }]
             }
         };
@@ -222,7 +828,16 @@ impl XenoRegistry {
             }
         }
 
-        self._load_module_inner(module_path, canonical, source, hash)
+        let mut loaded = Vec::new();
+        let mut errors =
+            self._load_source_inner(module_path.clone(), canonical, source, hash, &mut loaded);
+        errors.extend(self._analyze_loaded(&loaded));
+
+        // Importers may refer to names that changed, their errors are stored for `get_all_errors_for`
+        for dependent in self.dependents_of(&[module_path]) {
+            self._analyze_cached(&dependent);
+        }
+        errors
     }
 
     /// Recursively loads a .xen file from disk and all its imports.
@@ -231,6 +846,21 @@ impl XenoRegistry {
         import_segments: &[&str],
         force: bool,
         import_str: Option<&str>,
+    ) -> Vec<ModuleError> {
+        let mut loaded = Vec::new();
+        let mut errors = self._load_sources(import_segments, force, import_str, &mut loaded);
+        errors.extend(self._analyze_loaded(&loaded));
+        errors
+    }
+
+    /// Reads, lexes and parses a module and everything it imports that isn't loaded yet.
+    /// Every module put into the cache is added to `loaded` after the modules it imports.
+    fn _load_sources(
+        &self,
+        import_segments: &[&str],
+        force: bool,
+        import_str: Option<&str>,
+        loaded: &mut Vec<ModulePath>,
     ) -> Vec<ModuleError> {
         let (module_path, abs_path) = match self.resolve_import(import_segments, import_str) {
             Err(e) => return vec![e],
@@ -242,16 +872,9 @@ impl XenoRegistry {
             return vec![];
         }
 
-        let source = match fs::read_to_string(&abs_path) {
+        let source = match Self::_read_source(&module_path, &abs_path) {
             Ok(s) => s,
-            Err(e) => {
-                return vec![ModuleError {
-                    module_path,
-                    message: format!("Failed to read file '{}': {}", abs_path.display(), e),
-                    location: None,
-                    phase: ErrorPhase::Module,
-                }];
-            }
+            Err(e) => return vec![e],
         };
 
         // Hash-based skip when forced
@@ -265,168 +888,530 @@ impl XenoRegistry {
             }
         }
 
-        self._load_module_inner(module_path, abs_path, source, hash)
+        self._load_source_inner(module_path, abs_path, source, hash, loaded)
     }
 
-    fn _load_module_inner(
+    fn _load_source_inner(
         &self,
         module_path: ModulePath,
         abs_path: PathBuf,
         source: String,
         hash: u64,
+        loaded: &mut Vec<ModulePath>,
     ) -> Vec<ModuleError> {
         let mut errors: Vec<ModuleError> = Vec::new();
 
-        let md = match Self::_create_module_data(&module_path, abs_path, source, hash) {
-            Ok(r) => r,
-            Err(e) => {
-                errors.extend(e);
-                return errors;
-            }
-        };
-
         // ── Step 1: Insert into cache immediately to break import cycles ──
         // Any recursive load_module call for this module will now find it and return early.
-        let imports = md.borrow_imports().to_vec();
-        {
-            self.module_cache
-                .blocking_write()
-                .insert(module_path.clone(), md);
-        }
+        let imports = self._insert_module(&module_path, abs_path, source, hash);
 
         // ── Step 2: Load imports (cycle-safe now) ──
         for import in &imports {
-            let segments: Vec<&str> = import.split('/').collect();
-            errors.extend(self.load_module(&segments, false, Some(import)));
+            let segments: Vec<&str> = import.module_path.split('/').collect();
+            errors.extend(self._load_sources(&segments, false, Some(&import.module_path), loaded));
         }
+        loaded.push(module_path);
 
-        // ── Step 3: Analyze with full scope (read lock only) ──
-        let (analyzer_errors, import_errors, lexer_errs, parser_errs) = {
-            let cache = self.module_cache.blocking_read();
-            let md = cache.get(&module_path).unwrap();
-
-            let xeno_errors = self.analyzer.run(
-                md.borrow_ast(),
-                md,
-                &imports,
-                &cache,
-                self.plugins,
-                &Config::get().plugins.config,
-            );
-
-            let analyzer_errors: Vec<ModuleError> = xeno_errors
-                .iter()
-                .map(|e| ModuleError {
-                    module_path: module_path.clone(),
-                    message: e.message.clone(),
-                    location: Some((e.location.l, e.location.c, e.location.v.len() as u32)),
-                    phase: ErrorPhase::Analyzer,
-                })
-                .collect();
+        errors
+    }
 
-            let import_errors = self.validate_imports(md, &module_path);
-            let lexer_errs = md.borrow_lexer_errors().clone();
-            let parser_errs = md.borrow_parser_errors().clone();
+    /// Looks for import cycles in the whole cache once, then analyzes the `loaded` modules in order.
+    /// Returns all errors of those modules.
+    fn _analyze_loaded(&self, loaded: &[ModulePath]) -> Vec<ModuleError> {
+        self._store_import_cycles();
+        loaded
+            .iter()
+            .flat_map(|module_path| self._analyze_cached(module_path))
+            .collect()
+    }
 
-            (analyzer_errors, import_errors, lexer_errs, parser_errs)
+    /// Replaces the import cycle errors of every cached module with the ones of the current import graph.
+    fn _store_import_cycles(&self) {
+        let mut found = {
+            let cache = self.module_cache.blocking_read();
+            let importers = self.importers.blocking_read();
+            import_cycle_errors(&cache, &importers, Config::get().parser.import_cycles)
         };
-
-        // ── Step 4: Write error fields back into the cached module ──
-        {
-            let mut cache = self.module_cache.blocking_write();
-            let md = cache.get_mut(&module_path).unwrap();
-            md.with_analyzer_errors_mut(|errs: &mut Vec<ModuleError>| {
-                *errs = analyzer_errors.clone()
+        let mut cache = self.module_cache.blocking_write();
+        for (module_path, md) in cache.iter_mut() {
+            let cycles = found.remove(module_path).unwrap_or_default();
+            md.with_module_errors_mut(|errs: &mut Vec<ModuleError>| {
+                errs.retain(|e| e.code != codes::IMPORT_CYCLE);
+                errs.extend(cycles);
             });
-            md.with_module_errors_mut(|errs: &mut Vec<ModuleError>| *errs = import_errors.clone());
         }
-
-        errors.extend(lexer_errs);
-        errors.extend(parser_errs);
-        errors.extend(analyzer_errors);
-        errors.extend(import_errors);
-
-        errors
     }
 
-    // ── Import resolution & validation ──────────────────────────────
-
-    /// Resolves an import path (e.g. `["a", "b"]`) relative to the workspace root.
-    pub fn resolve_import(
+    /// Lexes and parses a module into the cache without analyzing it, returns its imports.
+    fn _insert_module(
         &self,
-        import_array: &[&str],
-        import_str: Option<&str>,
-    ) -> Result<(ModulePath, PathBuf), ModuleError> {
-        let import_str = import_str
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| import_array.join("/"));
-        let mut pathbuf = self.root.join(&import_str);
-        pathbuf.add_extension("xen");
+        module_path: &ModulePath,
+        abs_path: PathBuf,
+        source: String,
+        hash: u64,
+    ) -> Vec<ModuleImport> {
+        // An edited module only parses the declarations that changed again
+        let md = {
+            let cache = self.module_cache.blocking_read();
+            Self::_reparse_module_data(module_path, abs_path, source, hash, cache.get(module_path))
+        };
+        self._store_module(md)
+    }
 
-        match pathbuf.canonicalize() {
-            Ok(p) => Ok((import_str, p)),
-            Err(e) => Err(ModuleError {
-                module_path: import_str.clone(),
-                message: format!("Cannot resolve import '{}': {}", import_str, e),
-                location: None,
-                phase: ErrorPhase::Module,
-            }),
-        }
+    /// Puts a parsed module into the cache and the importer index, returns its imports.
+    fn _store_module(&self, md: ModuleData) -> Vec<ModuleImport> {
+        let module_path = md.borrow_module_path().clone();
+        let imports = md.borrow_imports().to_vec();
+        let previous = self
+            .module_cache
+            .blocking_write()
+            .insert(module_path.clone(), md);
+        let previous_imports = previous.map(|md| md.borrow_imports().to_vec());
+        self._index_imports(
+            &module_path,
+            previous_imports.as_deref().unwrap_or_default(),
+            &imports,
+        );
+        imports
     }
 
-    /// Validates all import declarations in a module.
-    fn validate_imports(&self, module: &ModuleData, module_path: &str) -> Vec<ModuleError> {
-        let mut errors = Vec::new();
-        for decl in module.borrow_ast().iter() {
-            if let Declaration::Import { path, location } = decl {
-                let segments: Vec<&str> = path.iter().copied().collect();
-                match self.resolve_import(&segments, None) {
-                    Ok((_, abs_path)) => {
-                        if !abs_path.exists() {
-                            errors.push(ModuleError {
-                                module_path: module_path.to_string(),
-                                message: format!(
-                                    "Module '{}' not found (expected at '{}')",
-                                    path.join("/"),
-                                    abs_path.display()
-                                ),
-                                location: Some((location.l, location.c, location.v.len() as u32)),
-                                phase: ErrorPhase::Analyzer,
-                            });
-                        }
-                    }
-                    Err(_) => {
-                        errors.push(ModuleError {
-                            module_path: module_path.to_string(),
-                            message: format!("Cannot resolve module '{}'", path.join("/")),
-                            location: Some((location.l, location.c, location.v.len() as u32)),
-                            phase: ErrorPhase::Analyzer,
-                        });
-                    }
+    /// Replaces what `module_path` imported before with `imports` in the importer index.
+    fn _index_imports(&self, module_path: &str, before: &[ModuleImport], imports: &[ModuleImport]) {
+        let mut importers = self.importers.blocking_write();
+        for import in before {
+            if let Some(set) = importers.get_mut(&import.module_path) {
+                set.remove(module_path);
+                if set.is_empty() {
+                    importers.remove(&import.module_path);
                 }
             }
         }
-        errors
+        for import in imports {
+            importers
+                .entry(import.module_path.clone())
+                .or_default()
+                .insert(module_path.to_string());
+        }
     }
 
-    /// Suggest available module paths starting with the given partial path.
-    /// Returns `(segment_name, abs_path, is_directory)` tuples.
-    pub fn suggest_import(&self, path_so_far: &str) -> Vec<(String, PathBuf, bool)> {
-        let segments: Vec<&str> = path_so_far.split('/').collect();
-        let prefix = segments[..segments.len().saturating_sub(1)].join("/");
-        let last_segment = *segments.last().unwrap_or(&"");
-        let dir_to_search = if prefix.is_empty() {
-            self.root.clone()
-        } else {
-            self.root.join(&prefix)
+    /// Analyzes a cached module against the current cache and stores the analyzer and import errors.
+    /// Returns all errors of the module. With a generation mode registry this reruns the generators.
+    fn _analyze_cached(&self, module_path: &str) -> Vec<ModuleError> {
+        // ── Step 3: Analyze with full scope (read lock only) ──
+        let analysis = {
+            let cache = self.module_cache.blocking_read();
+            let Some(md) = cache.get(module_path) else {
+                return vec![];
+            };
+            self._analyze(module_path, md, &cache)
         };
 
-        if let Ok(entries) = fs::read_dir(dir_to_search) {
-            entries
-                .filter_map(|e| e.ok())
-                .filter_map(|e| {
-                    let path = e.path();
-                    if path.is_dir() {
+        // ── Step 4: Write error fields back into the cached module ──
+        self._store_analysis(vec![(module_path.to_string(), analysis)])
+    }
+
+    /// Runs the analyzer and the registry's own checks on a module,
+    /// returns its analyzer and module errors.
+    fn _analyze(
+        &self,
+        module_path: &str,
+        md: &ModuleData,
+        cache: &HashMap<ModulePath, ModuleData>,
+    ) -> Analysis {
+        let fresh_outputs = self
+            .build_cache
+            .as_ref()
+            .filter(|build_cache| build_cache.is_fresh(module_path, cache))
+            .map(|build_cache| build_cache.outputs(module_path));
+        let up_to_date = fresh_outputs.is_some();
+        let (xeno_errors, outputs) = self.analyzer.run(
+            md.borrow_ast(),
+            md,
+            cache,
+            self.plugins,
+            &Config::get().plugins.config,
+            !up_to_date,
+        );
+
+        let mut analyzer_errors: Vec<ModuleError> = xeno_errors
+            .iter()
+            .map(|e| {
+                ModuleError::from_xeno(module_path, md.borrow_abs_path(), ErrorPhase::Analyzer, e)
+            })
+            .collect();
+        analyzer_errors.extend(md.declaration_conflicts(cache));
+
+        let import_errors = self.validate_imports(md, module_path);
+
+        // Generators skip up to date modules, the files of their last run are still there
+        (
+            analyzer_errors,
+            import_errors,
+            fresh_outputs.unwrap_or(outputs),
+        )
+    }
+
+    /// Stores analysis results in the cached modules under a single write lock,
+    /// returns all errors of those modules in the given order.
+    fn _store_analysis(&self, results: Vec<(ModulePath, Analysis)>) -> Vec<ModuleError> {
+        let mut errors = Vec::new();
+        let mut cache = self.module_cache.blocking_write();
+        for (module_path, (analyzer_errors, import_errors, outputs)) in results {
+            let Some(md) = cache.get_mut(&module_path) else {
+                continue;
+            };
+            // Import cycles were found for the whole graph before the modules were analyzed
+            let cycles = md
+                .borrow_module_errors()
+                .iter()
+                .filter(|e| e.code == codes::IMPORT_CYCLE)
+                .cloned();
+            let module_errors: Vec<ModuleError> = import_errors.into_iter().chain(cycles).collect();
+            errors.extend(md.borrow_lexer_errors().iter().cloned());
+            errors.extend(md.borrow_parser_errors().iter().cloned());
+            errors.extend(analyzer_errors.iter().cloned());
+            errors.extend(module_errors.iter().cloned());
+            md.with_analyzer_errors_mut(|errs: &mut Vec<ModuleError>| *errs = analyzer_errors);
+            md.with_module_errors_mut(|errs: &mut Vec<ModuleError>| *errs = module_errors);
+            md.with_outputs_mut(|files: &mut Vec<PathBuf>| *files = outputs);
+        }
+        errors
+    }
+
+    fn _read_source(module_path: &str, abs_path: &Path) -> Result<String, ModuleError> {
+        fs::read_to_string(abs_path).map_err(|e| ModuleError {
+            module_path: module_path.to_string(),
+            message: format!("Failed to read file '{}': {}", abs_path.display(), e),
+            location: None,
+            phase: ErrorPhase::Module,
+            severity: Severity::Error,
+            code: codes::FILE_ACCESS,
+            related: Vec::new(),
+        })
+    }
+
+    /// Loads a module and everything it imports, like a forced [`XenoRegistry::load_module`].
+    /// Modules are read, lexed and parsed in parallel one import level at a time, then analyzed
+    /// in topological order with the modules of each level analyzed in parallel.
+    /// The errors come out in the same order on every run: load errors by level and module path,
+    /// then every module's errors in analysis order.
+    pub fn load_module_tree(&self, import_segments: &[&str]) -> Vec<ModuleError> {
+        let mut errors = Vec::new();
+        let mut loaded: Vec<ModulePath> = Vec::new();
+        let root = import_segments.join("/");
+        let mut seen: HashSet<ModulePath> = HashSet::from([root.clone()]);
+        let mut frontier = vec![root];
+        let mut is_root = true;
+
+        // ── Load level by level ──
+        while !frontier.is_empty() {
+            let results: Vec<Result<Option<ModuleData>, ModuleError>> = frontier
+                .par_iter()
+                .map(|import_str| {
+                    let (module_path, abs_path) = self.resolve_import(&[], Some(import_str))?;
+                    let cached_hash = self
+                        .module_cache
+                        .blocking_read()
+                        .get(&module_path)
+                        .map(|md| *md.borrow_hash());
+                    // Imports already loaded are kept, like `load_module` without `force`
+                    if !is_root && cached_hash.is_some() {
+                        return Ok(None);
+                    }
+                    let source = Self::_read_source(&module_path, &abs_path)?;
+                    let hash = calculate_hash(&source);
+                    if cached_hash == Some(hash) {
+                        return Ok(None);
+                    }
+                    Ok(Some(Self::_create_module_data(
+                        &module_path,
+                        abs_path,
+                        source,
+                        hash,
+                    )))
+                })
+                .collect();
+
+            let mut next: BTreeSet<ModulePath> = BTreeSet::new();
+            for result in results {
+                let md = match result {
+                    Ok(Some(md)) => md,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
+                    }
+                };
+                let module_path = md.borrow_module_path().clone();
+                for import in self._store_module(md) {
+                    if seen.insert(import.module_path.clone()) {
+                        next.insert(import.module_path);
+                    }
+                }
+                loaded.push(module_path);
+            }
+            frontier = next.into_iter().collect();
+            is_root = false;
+        }
+
+        // ── Find import cycles in the loaded graph ──
+        self._store_import_cycles();
+
+        // ── Analyze in topological order, one level at a time ──
+        for level in self._analysis_levels(&loaded) {
+            let results: Vec<_> = {
+                let cache = self.module_cache.blocking_read();
+                level
+                    .par_iter()
+                    .filter_map(|module_path| {
+                        let md = cache.get(module_path)?;
+                        Some((module_path.clone(), self._analyze(module_path, md, &cache)))
+                    })
+                    .collect()
+            };
+            errors.extend(self._store_analysis(results));
+        }
+
+        errors
+    }
+
+    /// Groups modules so that every module comes after the modules it imports, each level sorted.
+    /// Modules in import cycles can't be ordered and end up together in the last level.
+    fn _analysis_levels(&self, modules: &[ModulePath]) -> Vec<Vec<ModulePath>> {
+        let cache = self.module_cache.blocking_read();
+        let mut pending: BTreeMap<&str, BTreeSet<&str>> = modules
+            .iter()
+            .map(|path| {
+                let imports = cache
+                    .get(path)
+                    .map(|md| {
+                        md.borrow_imports()
+                            .iter()
+                            .map(|i| i.module_path.as_str())
+                            .filter(|i| i != path && modules.iter().any(|m| m == i))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (path.as_str(), imports)
+            })
+            .collect();
+
+        let mut levels = Vec::new();
+        while !pending.is_empty() {
+            let mut level: Vec<&str> = pending
+                .iter()
+                .filter(|(_, imports)| imports.is_empty())
+                .map(|(path, _)| *path)
+                .collect();
+            if level.is_empty() {
+                level = pending.keys().copied().collect();
+            }
+            for path in &level {
+                pending.remove(path);
+            }
+            for imports in pending.values_mut() {
+                for path in &level {
+                    imports.remove(path);
+                }
+            }
+            levels.push(level.into_iter().map(String::from).collect());
+        }
+        levels
+    }
+
+    /// Brings the cache up to date with files that changed on disk.
+    /// Only modules whose content changed are lexed and parsed again, they and every module
+    /// importing them (directly or not) are analyzed again. Deleted files are dropped from the cache.
+    pub fn refresh(&self, abs_paths: &[PathBuf]) -> RefreshSummary {
+        let mut summary = RefreshSummary::default();
+        let mut imports = Vec::new();
+
+        for abs_path in abs_paths {
+            let Some(module_path) = self.abs_path_to_module_path(abs_path) else {
+                continue;
+            };
+            if summary.changed.contains(&module_path) || summary.removed.contains(&module_path) {
+                continue;
+            }
+
+            let Ok(source) = fs::read_to_string(abs_path) else {
+                let removed = self.module_cache.blocking_write().remove(&module_path);
+                if let Some(md) = removed {
+                    // Modules importing it keep their entries and find it again once it's back
+                    self._index_imports(&module_path, md.borrow_imports(), &[]);
+                    summary.removed.push(module_path);
+                }
+                continue;
+            };
+
+            let hash = calculate_hash(&source);
+            let unchanged = self
+                .module_cache
+                .blocking_read()
+                .get(&module_path)
+                .is_some_and(|existing| *existing.borrow_hash() == hash);
+            if unchanged {
+                continue;
+            }
+
+            let canonical = abs_path.canonicalize().unwrap_or_else(|_| abs_path.clone());
+            imports.extend(self._insert_module(&module_path, canonical, source, hash));
+            summary.changed.push(module_path);
+        }
+
+        // Modules imported for the first time are loaded and analyzed as a whole
+        let mut loaded = Vec::new();
+        for import in &imports {
+            let segments: Vec<&str> = import.module_path.split('/').collect();
+            summary.errors.extend(self._load_sources(
+                &segments,
+                false,
+                Some(&import.module_path),
+                &mut loaded,
+            ));
+        }
+        summary.errors.extend(self._analyze_loaded(&loaded));
+
+        let mut touched = summary.changed.clone();
+        touched.extend(summary.removed.iter().cloned());
+        summary.dependents = self
+            .dependents_of(&touched)
+            .into_iter()
+            .filter(|path| !summary.changed.contains(path))
+            .collect();
+
+        for module_path in summary.changed.iter().chain(&summary.dependents) {
+            summary.errors.extend(self._analyze_cached(module_path));
+        }
+        summary
+    }
+
+    /// Modules that import any of `module_paths`, directly or through other modules, sorted.
+    pub fn dependents_of(&self, module_paths: &[ModulePath]) -> Vec<ModulePath> {
+        let importers = self.importers.blocking_read();
+        let mut dependents: BTreeSet<ModulePath> = BTreeSet::new();
+        let mut queue: VecDeque<&str> = module_paths.iter().map(|p| p.as_str()).collect();
+        while let Some(path) = queue.pop_front() {
+            for importer in importers.get(path).into_iter().flatten() {
+                if module_paths.contains(importer) || !dependents.insert(importer.clone()) {
+                    continue;
+                }
+                queue.push_back(importer);
+            }
+        }
+        dependents.into_iter().collect()
+    }
+
+    /// Build cache describing the loaded modules, modules with errors are left out
+    /// so they are generated again on the next run.
+    pub fn snapshot_build_cache(&self) -> BuildCache {
+        let mut build_cache = BuildCache::new(self.plugins, &Config::get().plugins.config);
+        let cache = self.module_cache.blocking_read();
+        for (module_path, module) in cache.iter() {
+            let has_errors = module
+                .borrow_lexer_errors()
+                .iter()
+                .chain(module.borrow_parser_errors())
+                .chain(module.borrow_analyzer_errors())
+                .chain(module.borrow_module_errors())
+                .any(|e| e.severity == Severity::Error);
+            if has_errors {
+                continue;
+            }
+            if let Some(inputs) = CachedModule::of(module_path, &cache) {
+                build_cache.insert(module_path.clone(), inputs);
+            }
+        }
+        build_cache
+    }
+
+    // ── Import resolution & validation ──────────────────────────────
+
+    /// Resolves an import path (e.g. `["a", "b"]`) relative to the workspace root.
+    pub fn resolve_import(
+        &self,
+        import_array: &[&str],
+        import_str: Option<&str>,
+    ) -> Result<(ModulePath, PathBuf), ModuleError> {
+        let import_str = import_str
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| import_array.join("/"));
+        let mut pathbuf = self.root.join(&import_str);
+        pathbuf.add_extension("xen");
+
+        match pathbuf.canonicalize() {
+            Ok(p) => Ok((import_str, p)),
+            Err(e) => Err(ModuleError {
+                module_path: import_str.clone(),
+                message: format!("Cannot resolve import '{}': {}", import_str, e),
+                location: None,
+                phase: ErrorPhase::Module,
+                severity: Severity::Error,
+                code: codes::UNRESOLVED_IMPORT,
+                related: Vec::new(),
+            }),
+        }
+    }
+
+    /// Validates all import declarations in a module.
+    fn validate_imports(&self, module: &ModuleData, module_path: &str) -> Vec<ModuleError> {
+        let mut errors = Vec::new();
+        for decl in module.borrow_ast().iter() {
+            if let Declaration::Import { path, location, .. } = decl {
+                let segments: Vec<&str> = path.iter().copied().collect();
+                match self.resolve_import(&segments, None) {
+                    Ok((_, abs_path)) => {
+                        if !abs_path.exists() {
+                            errors.push(ModuleError {
+                                module_path: module_path.to_string(),
+                                message: format!(
+                                    "Module '{}' not found (expected at '{}')",
+                                    path.join("/"),
+                                    abs_path.display()
+                                ),
+                                location: Some((location.l, location.c, location.v.len() as u32)),
+                                phase: ErrorPhase::Analyzer,
+                                severity: Severity::Error,
+                                code: codes::UNRESOLVED_IMPORT,
+                                related: Vec::new(),
+                            });
+                        }
+                    }
+                    Err(_) => {
+                        errors.push(ModuleError {
+                            module_path: module_path.to_string(),
+                            message: format!("Cannot resolve module '{}'", path.join("/")),
+                            location: Some((location.l, location.c, location.v.len() as u32)),
+                            phase: ErrorPhase::Analyzer,
+                            severity: Severity::Error,
+                            code: codes::UNRESOLVED_IMPORT,
+                            related: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Suggest available module paths starting with the given partial path.
+    /// Returns `(segment_name, abs_path, is_directory)` tuples.
+    pub fn suggest_import(&self, path_so_far: &str) -> Vec<(String, PathBuf, bool)> {
+        let segments: Vec<&str> = path_so_far.split('/').collect();
+        let prefix = segments[..segments.len().saturating_sub(1)].join("/");
+        let last_segment = *segments.last().unwrap_or(&"");
+        let dir_to_search = if prefix.is_empty() {
+            self.root.clone()
+        } else {
+            self.root.join(&prefix)
+        };
+
+        if let Ok(entries) = fs::read_dir(dir_to_search) {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let path = e.path();
+                    if path.is_dir() {
                         let name = path.file_name()?.to_str()?;
                         if name.starts_with(last_segment) {
                             return Some((name.to_string(), path, true));
@@ -499,76 +1484,198 @@ impl XenoRegistry {
 
     // ── Declaration lookup ──────────────────────────────────────────
 
+    /// Finds the declaration a name refers to in a module: its own declarations first,
+    /// then the names its imports bring into scope (respecting selective imports and aliases).
     pub fn find_declaration(&self, current_module: &str, name: &str) -> Option<DeclarationInfo> {
-        self._find_declaration(
-            current_module,
-            name,
-            &self.module_cache.blocking_read(),
-            &mut HashSet::new(),
-        )
+        let cache = self.module_cache.blocking_read();
+        let module = cache.get(current_module)?;
+        if let Some(d) = module.borrow_declarations().get(name) {
+            return Some(d.clone());
+        }
+
+        module
+            .imported_declarations(&cache)
+            .into_iter()
+            .find(|i| i.local == name)
+            .map(|i| i.declaration.clone())
+    }
+
+    /// Declarations importers of a module can see, keyed by the name they are exported as.
+    pub fn get_exported_declarations(&self, module_path: &str) -> Vec<(String, DeclarationInfo)> {
+        let cache = self.module_cache.blocking_read();
+        let Some(module) = cache.get(module_path) else {
+            return Vec::new();
+        };
+
+        module
+            .exported_declarations(&cache)
+            .into_iter()
+            .map(|(name, d)| (name.to_string(), d.clone()))
+            .collect()
     }
 
-    fn _find_declaration<'s, 'c: 's>(
+    /// All declarations visible in a module, keyed by the name they are visible as.
+    pub fn get_all_declarations_in_scope(
         &self,
-        current_module: &'c str,
-        name: &str,
-        cache: &'c HashMap<String, ModuleData>,
-        tried: &'s mut HashSet<&'c str>,
-    ) -> Option<DeclarationInfo> {
-        tried.insert(current_module);
+        current_module: &str,
+    ) -> Vec<(String, DeclarationInfo)> {
+        let cache = self.module_cache.blocking_read();
+        let Some(module) = cache.get(current_module) else {
+            return Vec::new();
+        };
 
-        let module = cache.get(current_module)?;
-        if let Some(d) = module.borrow_declarations().get(&name) {
-            return Some(d.clone());
-        }
+        let mut decls: Vec<(String, DeclarationInfo)> = module
+            .borrow_declarations()
+            .iter()
+            .map(|(name, d)| (name.to_string(), d.clone()))
+            .collect();
+        decls.extend(
+            module
+                .imported_declarations(&cache)
+                .into_iter()
+                .map(|i| (i.local.to_string(), i.declaration.clone())),
+        );
+        decls
+    }
 
-        for import in module.borrow_imports() {
-            if tried.contains(import.as_str()) {
-                continue;
-            }
-            tried.insert(import);
-            if let Some(m) = cache.get(import) {
-                if let Some(d) = m.borrow_declarations().get(name) {
-                    return Some(d.clone());
+    /// Type declarations of every loaded module that refer to `target`, one entry per declaration.
+    pub fn type_users(&self, target: &DeclarationInfo) -> Vec<TypeUse> {
+        let cache = self.module_cache.blocking_read();
+        let mut module_paths: Vec<&ModulePath> = cache.keys().collect();
+        module_paths.sort();
+
+        let mut users = Vec::new();
+        for module_path in module_paths {
+            let module = &cache[module_path];
+            for (user, used, relation, location) in resolved_type_references(module, &cache) {
+                if used.module_path != target.module_path || used.name != target.name {
+                    continue;
+                }
+                if let Some(user) = module.borrow_declarations().get(user) {
+                    Self::add_type_use(&mut users, user, relation, location);
                 }
             }
         }
-
-        None
+        users
     }
 
-    pub fn get_all_declarations_in_scope(&self, current_module: &str) -> Vec<DeclarationInfo> {
-        let mut decls = Vec::new();
-        self._get_all_declarations_in_scope(
-            current_module,
-            &mut decls,
-            &self.module_cache.blocking_read(),
-            &mut HashSet::new(),
-        );
-        decls
+    /// Declarations the type declaration refers to, one entry per referenced declaration.
+    pub fn types_used_by(&self, declaration: &DeclarationInfo) -> Vec<TypeUse> {
+        let cache = self.module_cache.blocking_read();
+        let Some(module) = cache.get(&declaration.module_path) else {
+            return Vec::new();
+        };
+
+        let mut used = Vec::new();
+        for (user, target, relation, location) in resolved_type_references(module, &cache) {
+            if user == declaration.name {
+                Self::add_type_use(&mut used, target, relation, location);
+            }
+        }
+        used
     }
 
-    fn _get_all_declarations_in_scope<'s, 'c: 's>(
+    /// A struct field, picked by the position of its name where it's declared, and every name
+    /// referring to it in the loaded modules.
+    pub fn field_references(
         &self,
-        current_module: &'c str,
-        decls: &mut Vec<DeclarationInfo>,
-        cache: &'c HashMap<String, ModuleData>,
-        tried: &'s mut HashSet<&'c str>,
-    ) {
-        tried.insert(current_module);
+        module_path: &str,
+        line: u32,
+        column: u32,
+    ) -> Option<FieldReferences> {
+        let cache = self.module_cache.blocking_read();
+        let module = cache.get(module_path)?;
+        let names = FieldNames::of(module.borrow_ast());
+        let field = names
+            .fields
+            .iter()
+            .find(|f| f.name.l == line && f.name.c == column)?;
+        let location = |t: &TokenData| (t.l, t.c, t.v.len() as u32);
 
-        if let Some(m) = cache.get(current_module) {
-            decls.extend(m.borrow_declarations().values().cloned());
+        let taken = names
+            .fields
+            .iter()
+            .filter(|f| f.owner == field.owner && f != &field)
+            .filter(|f| {
+                std::ptr::eq(f.siblings, field.siblings) || (f.top_level && field.top_level)
+            })
+            .map(|f| f.name.v.to_string())
+            .collect();
 
-            for import in m.borrow_imports() {
-                if tried.contains(import.as_str()) {
-                    continue;
+        let mut members = Vec::new();
+        if field.top_level {
+            let mut module_paths: Vec<&ModulePath> = cache.keys().collect();
+            module_paths.sort();
+            for path in module_paths {
+                let other = &cache[path];
+                let scope = ScopeInfo::new(other, &cache, self.plugins);
+                let mut locations = Vec::new();
+                for (base, path_members) in FieldNames::of(other.borrow_ast()).members {
+                    let mut prefix = Vec::new();
+                    for member in path_members {
+                        prefix.push(member.v);
+                        let Some((owner, info)) = scope.resolve_member_declaration(base.v, &prefix)
+                        else {
+                            break;
+                        };
+                        if owner.module_path == module_path
+                            && info.line == line
+                            && info.column == column
+                        {
+                            locations.push(location(member));
+                        }
+                    }
                 }
-                if let Some(im) = cache.get(import) {
-                    decls.extend(im.borrow_declarations().values().cloned());
+                if !locations.is_empty() {
+                    members.push(MemberPaths {
+                        module_path: path.clone(),
+                        abs_path: other.borrow_abs_path().clone(),
+                        locations,
+                    });
                 }
             }
         }
+
+        Some(FieldReferences {
+            module_path: module_path.to_string(),
+            abs_path: module.borrow_abs_path().clone(),
+            owner: field.owner.v.to_string(),
+            name: field.name.v.to_string(),
+            declaration: location(field.name),
+            siblings: names.references_to(field).map(location).collect(),
+            members,
+            taken,
+        })
+    }
+
+    fn add_type_use(
+        uses: &mut Vec<TypeUse>,
+        declaration: &DeclarationInfo,
+        relation: TypeRelation,
+        location: (u32, u32, u32),
+    ) {
+        let existing = uses.iter_mut().find(|u| {
+            u.declaration.module_path == declaration.module_path
+                && u.declaration.name == declaration.name
+        });
+        match existing {
+            Some(existing) => {
+                existing.relation = existing.relation.max(relation);
+                existing.locations.push(location);
+            }
+            None => uses.push(TypeUse {
+                declaration: declaration.clone(),
+                relation,
+                locations: vec![location],
+            }),
+        }
+    }
+
+    /// The scope the analyzer checks a module against, e.g. to tell own, imported and builtin types apart.
+    pub fn scope_info(&self, module_path: &str) -> Option<ScopeInfo> {
+        let cache = self.module_cache.blocking_read();
+        let module = cache.get(module_path)?;
+        Some(ScopeInfo::new(module, &cache, self.plugins))
     }
 
     // ── Internal ────────────────────────────────────────────────────
@@ -578,107 +1685,836 @@ impl XenoRegistry {
         abs_path: PathBuf,
         source: String,
         hash: u64,
-    ) -> Result<ModuleData, Vec<ModuleError>> {
-        // Collect parser errors via shared mutability since ouroboros closures
+    ) -> ModuleData {
+        Self::_reparse_module_data(module_path, abs_path, source, hash, None)
+    }
+
+    /// Like `_create_module_data`, declarations the edit didn't touch are taken from `previous`.
+    fn _reparse_module_data(
+        module_path: &ModulePath,
+        abs_path: PathBuf,
+        source: String,
+        hash: u64,
+        previous: Option<&ModuleData>,
+    ) -> ModuleData {
+        // Collect lexer and parser errors via shared mutability since ouroboros closures
         // can't write to head fields during construction.
+        let lexer_errors_cell: std::cell::RefCell<Vec<ModuleError>> =
+            std::cell::RefCell::new(Vec::new());
         let parser_errors_cell: std::cell::RefCell<Vec<ModuleError>> =
             std::cell::RefCell::new(Vec::new());
+        let spans_cell: std::cell::RefCell<Vec<DeclarationSpan>> =
+            std::cell::RefCell::new(Vec::new());
 
-        let mut md = ModuleDataTryBuilder {
+        let error_path = abs_path.clone();
+        let mut md = ModuleDataBuilder {
             abs_path,
             module_path: module_path.clone(),
             source,
             hash,
             changed: true,
+            declaration_spans: Vec::new(),
             lexer_errors: Vec::new(),
             parser_errors: Vec::new(),
             analyzer_errors: Vec::new(),
             module_errors: Vec::new(),
+            outputs: Vec::new(),
             imports: Vec::new(),
-            tokens_builder: |source| {
-                Lexer::tokenize(source).map_err(|e| {
-                    vec![ModuleError {
-                        module_path: module_path.clone(),
-                        message: format!("{}", e.message),
-                        location: Some((e.location.l, e.location.c, e.location.v.len() as u32)),
-                        phase: ErrorPhase::Lexer,
-                    }]
-                })
+            all_tokens_builder: |source| {
+                let (tokens, lex_errors) = Lexer::tokenize_with_trivia(source);
+
+                lexer_errors_cell
+                    .borrow_mut()
+                    .extend(lex_errors.iter().map(|e| {
+                        ModuleError::from_xeno(module_path, &error_path, ErrorPhase::Lexer, e)
+                    }));
+
+                tokens
+            },
+            tokens_builder: |all_tokens| {
+                all_tokens
+                    .iter()
+                    .filter(|t| !t.0.is_trivia())
+                    .cloned()
+                    .collect()
             },
             ast_builder: |tokens| {
-                let (ast, parse_errors) = Parser::parse(tokens);
+                let ((ast, parse_errors), spans) = match previous {
+                    Some(previous) => {
+                        let previous = PreviousParse {
+                            tokens: previous.borrow_tokens(),
+                            ast: previous.borrow_ast(),
+                            spans: previous.borrow_declaration_spans(),
+                        };
+                        let range = Parser::changed_range(previous.tokens, tokens);
+                        Parser::parse_range(tokens, previous, range)
+                    }
+                    None => Parser::parse_with_spans(tokens),
+                };
+                *spans_cell.borrow_mut() = spans;
 
                 parser_errors_cell
                     .borrow_mut()
-                    .extend(parse_errors.iter().map(|e| ModuleError {
-                        module_path: module_path.clone(),
-                        message: format!("{}", e.message),
-                        location: Some((e.location.l, e.location.c, e.location.v.len() as u32)),
-                        phase: ErrorPhase::Parser,
+                    .extend(parse_errors.iter().map(|e| {
+                        ModuleError::from_xeno(module_path, &error_path, ErrorPhase::Parser, e)
                     }));
 
-                Ok(ast)
+                ast
+            },
+            syntax_tree_builder: |source, all_tokens| {
+                SyntaxTree::new(source, all_tokens, &spans_cell.borrow())
             },
             declarations_builder: |ast: &XenoAst, abs_path: &PathBuf, module_path: &ModulePath| {
-                Ok(ast
-                    .iter()
-                    .filter_map(|d| match d {
-                        Declaration::TypeDecl { docs, name, t } => Some((
-                            name.v,
-                            DeclarationInfo {
-                                name: name.v.to_string(),
-                                module_path: module_path.to_string(),
-                                abs_path: abs_path.clone(),
-                                docs: docs.map(|d| d.to_string()),
-                                line: name.l,
-                                column: name.c,
-                                name_len: name.v.len() as u32,
-                                fields: {
-                                    let v = t
-                                        .iter()
-                                        .filter_map(|item| match item {
-                                            Expr::Struct(fields) => {
-                                                Some(fields.iter().filter_map(|(d, e)| {
-                                                    let t = e.get(0)?;
-                                                    match t {
-                                                        Expr::Identifier(id) => Some((
-                                                            d.v.to_string(),
-                                                            id.v.to_string(),
-                                                        )),
-                                                        _ => None,
-                                                    }
-                                                }))
-                                            }
-                                            _ => None,
-                                        })
-                                        .flatten()
-                                        .collect::<Vec<(String, String)>>();
-                                    (!v.is_empty()).then_some(v)
-                                },
-                            },
-                        )),
-                        _ => None,
-                    })
-                    .collect())
+                // The first declaration of a name wins, later ones are reported as duplicates
+                let mut declarations = HashMap::new();
+                let all = ast.iter().filter_map(|d| match d {
+                    Declaration::TypeDecl {
+                        docs,
+                        name,
+                        generics,
+                        t,
+                    } => Some((
+                        name.v,
+                        DeclarationInfo {
+                            name: name.v.to_string(),
+                            module_path: module_path.to_string(),
+                            abs_path: abs_path.clone(),
+                            docs: docs.map(|d| d.to_string()),
+                            line: name.l,
+                            column: name.c,
+                            name_len: name.v.len() as u32,
+                            generics: generics.iter().map(|g| g.v.to_string()).collect(),
+                            fields: collect_fields(t),
+                        },
+                    )),
+                    _ => None,
+                });
+                for (name, info) in all {
+                    declarations.entry(name).or_insert(info);
+                }
+                declarations
             },
         }
-        .try_build()?;
+        .build();
 
-        // Populate parser_errors field from what was collected during build
+        // Populate error fields from what was collected during build
+        let collected_lexer_errors = lexer_errors_cell.into_inner();
+        md.with_lexer_errors_mut(|errs| *errs = collected_lexer_errors);
         let collected_parser_errors = parser_errors_cell.into_inner();
         md.with_parser_errors_mut(|errs| *errs = collected_parser_errors);
+        let collected_spans = spans_cell.into_inner();
+        md.with_declaration_spans_mut(|spans| *spans = collected_spans);
 
         // Populate imports list
-        let import_list: Vec<ModulePath> = md
+        let import_list: Vec<ModuleImport> = md
             .borrow_ast()
             .iter()
             .filter_map(|d| match d {
-                Declaration::Import { path, .. } => Some(path.join("/")),
+                Declaration::Import {
+                    path,
+                    items,
+                    location,
+                    exported,
+                } => Some(ModuleImport {
+                    module_path: path.join("/"),
+                    names: items.as_ref().map(|items| {
+                        items
+                            .iter()
+                            .map(|item| {
+                                let local = item.alias.unwrap_or(item.name);
+                                ImportedName {
+                                    name: item.name.v.to_string(),
+                                    alias: item.alias.map(|a| a.v.to_string()),
+                                    location: (local.l, local.c, local.v.len() as u32),
+                                }
+                            })
+                            .collect()
+                    }),
+                    location: (location.l, location.c, location.v.len() as u32),
+                    exported: *exported,
+                }),
                 _ => None,
             })
             .collect();
         md.with_imports_mut(|imports| *imports = import_list);
 
-        Ok(md)
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::test_support::{registry, temp_workspace, workspace};
+    use crate::semantic::{AnalyzerListener, MemberError};
+
+    fn conflicts(cache: &HashMap<ModulePath, ModuleData>, module: &str) -> Vec<ModuleError> {
+        cache[module].declaration_conflicts(cache)
+    }
+
+    #[test]
+    fn duplicate_declarations_point_at_each_other() {
+        let cache = workspace(&[("main", "type User = string;\ntype User = u32;\n")]);
+        let errors = conflicts(&cache, "main");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Duplicate declaration of 'User'");
+        assert_eq!(errors[0].location, Some((1, 5, 4)));
+        assert_eq!(errors[0].related.len(), 1);
+        assert_eq!(errors[0].related[0].location, (0, 5, 4));
+        // The first declaration is the one the module exposes
+        assert_eq!(cache["main"].borrow_declarations()["User"].line, 0);
+    }
+
+    #[test]
+    fn names_imported_from_two_modules_are_ambiguous() {
+        let cache = workspace(&[
+            ("main", "import a;\nimport b;\ntype X = User;\n"),
+            ("a", "type User = string;"),
+            ("b", "type User = u32;"),
+        ]);
+        let errors = conflicts(&cache, "main");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            "'User' is ambiguous, it is imported from 'a' and 'b'"
+        );
+        assert_eq!(errors[0].location, Some((1, 0, 6)));
+        let related: Vec<&str> = errors[0]
+            .related
+            .iter()
+            .map(|r| r.module_path.as_str())
+            .collect();
+        assert_eq!(related, vec!["main", "a", "b"]);
+    }
+
+    #[test]
+    fn aliases_and_repeated_imports_resolve_conflicts() {
+        let cache = workspace(&[
+            (
+                "main",
+                "import a;\nimport a { User };\nimport b { User as Other };\n",
+            ),
+            ("a", "type User = string;"),
+            ("b", "type User = u32;"),
+        ]);
+        assert!(conflicts(&cache, "main").is_empty());
+    }
+
+    #[test]
+    fn reexports_are_followed_transitively() {
+        let cache = workspace(&[
+            ("main", "import index;\nimport common/base;\n"),
+            ("index", "export import shared;\nimport hidden;\n"),
+            (
+                "shared",
+                "export import common/base { Id as BaseId };\ntype Shared = u32;\n",
+            ),
+            ("common/base", "type Id = string;\nexport import shared;\n"),
+            ("hidden", "type Hidden = u32;"),
+        ]);
+
+        let visible: Vec<(&str, &str, &str)> = cache["main"]
+            .imported_declarations(&cache)
+            .iter()
+            .map(|i| (i.local, i.name, i.declaration.module_path.as_str()))
+            .collect();
+        assert!(visible.contains(&("Shared", "Shared", "shared")));
+        assert!(visible.contains(&("BaseId", "BaseId", "common/base")));
+        assert!(!visible.iter().any(|(local, _, _)| *local == "Hidden"));
+
+        // Reaching a declaration through a re-export and directly is not ambiguous
+        assert!(conflicts(&cache, "main").is_empty());
+    }
+
+    fn cycles(
+        modules: &[(&str, &str)],
+        severity: Severity,
+    ) -> HashMap<ModulePath, Vec<ModuleError>> {
+        let reg = registry(PathBuf::from("/ws"), workspace(modules));
+        let cache = reg.module_cache.blocking_read();
+        let importers = reg.importers.blocking_read();
+        import_cycle_errors(&cache, &importers, severity)
+    }
+
+    #[test]
+    fn import_cycles_report_the_whole_cycle() {
+        let errors = cycles(
+            &[
+                ("a", "import b;\nimport leaf;\n"),
+                ("b", "import c;"),
+                ("c", "import leaf;\nimport a;\n"),
+                ("leaf", "type Leaf = u32;"),
+            ],
+            Severity::Warning,
+        );
+
+        let a = &errors["a"];
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].message, "Import cycle: a -> b -> c -> a");
+        assert_eq!(a[0].location, Some((0, 0, 6)));
+        assert_eq!(a[0].severity, Severity::Warning);
+        let related: Vec<(&str, (u32, u32, u32))> = a[0]
+            .related
+            .iter()
+            .map(|r| (r.module_path.as_str(), r.location))
+            .collect();
+        assert_eq!(related, vec![("b", (0, 0, 6)), ("c", (1, 0, 6))]);
+
+        // The same cycle seen from the other modules
+        assert_eq!(errors["b"].len(), 1);
+        assert_eq!(errors["b"][0].message, "Import cycle: b -> c -> a -> b");
+        assert_eq!(errors["c"].len(), 1);
+        assert_eq!(errors["c"][0].message, "Import cycle: c -> a -> b -> c");
+        assert_eq!(errors["c"][0].location, Some((1, 0, 6)));
+
+        assert!(!errors.contains_key("leaf"));
+    }
+
+    #[test]
+    fn every_import_of_overlapping_cycles_is_reported() {
+        let errors = cycles(
+            &[
+                ("a", "import b;\nimport c;\n"),
+                ("b", "import a;"),
+                ("c", "import a;"),
+            ],
+            Severity::Error,
+        );
+        let messages: Vec<&str> = errors["a"].iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["Import cycle: a -> b -> a", "Import cycle: a -> c -> a"]
+        );
+        assert_eq!(errors["b"][0].message, "Import cycle: b -> a -> b");
+        assert_eq!(errors["c"][0].message, "Import cycle: c -> a -> c");
+    }
+
+    #[test]
+    fn self_imports_are_not_cycles() {
+        assert!(cycles(&[("a", "import a;")], Severity::Error).is_empty());
+
+        let errors = cycles(
+            &[("a", "import a;\nimport b;\n"), ("b", "import a;")],
+            Severity::Error,
+        );
+        assert_eq!(errors["a"].len(), 1);
+        assert_eq!(errors["a"][0].message, "Import cycle: a -> b -> a");
+        assert_eq!(errors["a"][0].code, codes::IMPORT_CYCLE);
+    }
+
+    #[test]
+    fn import_cycles_are_stored_once_per_import_and_cleared_when_broken() {
+        let root = temp_workspace(
+            "cycles",
+            &[
+                ("main", "import a;\n"),
+                ("a", "import b;\n"),
+                ("b", "import a;\n"),
+            ],
+        );
+        let reg = registry(root.clone(), HashMap::new());
+        let cycle_errors = |errors: &[ModuleError]| -> Vec<String> {
+            errors
+                .iter()
+                .filter(|e| e.code == codes::IMPORT_CYCLE)
+                .map(|e| e.module_path.clone())
+                .collect()
+        };
+
+        let errors = reg.load_module_tree(&["main"]);
+        assert_eq!(cycle_errors(&errors), ["a", "b"]);
+        assert_eq!(cycle_errors(&reg.get_all_errors_for("b")), ["b"]);
+
+        // Breaking the cycle in `b` clears the error of `a` as well
+        reg.load_module_from_source(&root.join("b.xen"), "type B = u8;\n".into());
+        assert!(cycle_errors(&reg.get_all_errors_for("a")).is_empty());
+        assert!(cycle_errors(&reg.get_all_errors_for("b")).is_empty());
+
+        reg.load_module_from_source(&root.join("b.xen"), "import a;\n".into());
+        assert_eq!(cycle_errors(&reg.get_all_errors_for("a")), ["a"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn explicit_imports_clash_with_own_declarations() {
+        let cache = workspace(&[
+            ("main", "import a { User };\ntype User = u32;\n"),
+            ("a", "type User = string;"),
+        ]);
+        let errors = conflicts(&cache, "main");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            "'User' is imported from 'a' and also declared in this module"
+        );
+        assert_eq!(errors[0].location, Some((1, 5, 4)));
+        assert_eq!(errors[0].related[0].location, (0, 11, 4));
+
+        // Importing the whole module lets the own declaration shadow it
+        let cache = workspace(&[
+            ("main", "import a;\ntype User = u32;\n"),
+            ("a", "type User = string;"),
+        ]);
+        assert!(conflicts(&cache, "main").is_empty());
+    }
+
+    #[test]
+    fn member_paths_resolve_field_types_where_they_are_declared() {
+        let cache = workspace(&[
+            (
+                "main",
+                "import a { Team };\ntype User = { id: u8 };\ntype Ref = { x: Team.lead.id };\n",
+            ),
+            (
+                "a",
+                "type User = { id: string };\ntype Team = { lead: User };\n",
+            ),
+        ]);
+        let scope = ScopeInfo::new(&cache["main"], &cache, &[]);
+
+        let (owner, field) = scope
+            .resolve_member_declaration("Team", &["lead", "id"])
+            .unwrap();
+        assert_eq!(
+            (owner.module_path.as_str(), owner.name.as_str()),
+            ("a", "User")
+        );
+        assert_eq!(field.type_name, "string");
+
+        assert_eq!(
+            scope.resolve_member_path("Team", &["lead", "name"]).err(),
+            Some(MemberError::UnknownField {
+                index: 1,
+                owner: "User".to_string()
+            })
+        );
+        assert_eq!(
+            scope
+                .resolve_member_path("Team", &["lead", "id", "len"])
+                .err(),
+            Some(MemberError::NoFields {
+                index: 2,
+                owner: "string".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn type_users_are_found_across_modules() {
+        let reg = registry(
+            PathBuf::from("/ws"),
+            workspace(&[
+                (
+                    "main",
+                    "import a;\ntype Admin = User & { level: u8 };\ntype Team = { lead: User, members: User[] };\ntype Name = Id @minlen(1);\n",
+                ),
+                ("a", "type User = { id: Id };\ntype Id = string;\ntype Page<User> = User[];\n"),
+            ]),
+        );
+        let uses = |uses: Vec<TypeUse>| -> Vec<(String, TypeRelation, usize)> {
+            uses.into_iter()
+                .map(|u| (u.declaration.name, u.relation, u.locations.len()))
+                .collect()
+        };
+
+        let user = reg.find_declaration("a", "User").unwrap();
+        assert_eq!(
+            uses(reg.type_users(&user)),
+            [
+                ("Admin".to_string(), TypeRelation::Intersects, 1),
+                ("Team".to_string(), TypeRelation::Embeds, 2)
+            ]
+        );
+        let id = reg.find_declaration("a", "Id").unwrap();
+        assert_eq!(
+            uses(reg.type_users(&id)),
+            [
+                ("User".to_string(), TypeRelation::Embeds, 1),
+                ("Name".to_string(), TypeRelation::Extends, 1)
+            ]
+        );
+
+        let team = reg.find_declaration("main", "Team").unwrap();
+        assert_eq!(
+            uses(reg.types_used_by(&team)),
+            [("User".to_string(), TypeRelation::Embeds, 2)]
+        );
+    }
+
+    #[test]
+    fn field_references_follow_siblings_and_member_paths() {
+        let reg = registry(
+            PathBuf::from("/ws"),
+            workspace(&[
+                (
+                    "main",
+                    "import a;\ntype Ref = { owner: User.id, other: User.name, nested: Team.lead.id };\n",
+                ),
+                (
+                    "a",
+                    "type User = { id: string, name: string @if($id, @minlen(1)) };\ntype Team = { lead: User, id: u8 @if($id, @min(1)) };\n",
+                ),
+            ]),
+        );
+
+        let id = reg.field_references("a", 0, 14).unwrap();
+        assert_eq!((id.owner.as_str(), id.name.as_str()), ("User", "id"));
+        assert_eq!(id.declaration, (0, 14, 2));
+        assert_eq!(id.siblings, [(0, 44, 2)]);
+        assert_eq!(
+            id.members
+                .iter()
+                .map(|m| (m.module_path.as_str(), m.locations.clone()))
+                .collect::<Vec<_>>(),
+            [("main", vec![(1, 25, 2), (1, 65, 2)])]
+        );
+        assert_eq!(id.taken, ["name"]);
+
+        // Not reachable through `Team.id` paths from `User.id`, and the reverse
+        let team_id = reg.field_references("a", 1, 26).unwrap();
+        assert_eq!(team_id.siblings, [(1, 38, 2)]);
+        assert!(team_id.members.is_empty());
+        assert!(reg.field_references("a", 0, 0).is_none());
+    }
+
+    #[test]
+    fn field_references_resolve_member_paths_where_fields_are_declared() {
+        // `Team` is imported on its own and `main` has a `User` of its own,
+        // `lead` is still the `User` of `a`
+        let reg = registry(
+            PathBuf::from("/ws"),
+            workspace(&[
+                (
+                    "main",
+                    "import a { Team };\ntype User = { id: u8 };\ntype Ref = { x: Team.lead.id, y: User.id };\n",
+                ),
+                ("a", "type User = { id: string };\ntype Team = { lead: User };\n"),
+            ]),
+        );
+        let members = |references: FieldReferences| {
+            references
+                .members
+                .into_iter()
+                .map(|m| (m.module_path, m.locations))
+                .collect::<Vec<_>>()
+        };
+
+        let imported = reg.field_references("a", 0, 14).unwrap();
+        assert_eq!(members(imported), [("main".to_string(), vec![(2, 26, 2)])]);
+        let shadowing = reg.field_references("main", 1, 14).unwrap();
+        assert_eq!(members(shadowing), [("main".to_string(), vec![(2, 38, 2)])]);
+    }
+
+    #[test]
+    fn dependents_include_indirect_importers() {
+        let reg = registry(
+            PathBuf::from("/ws"),
+            workspace(&[
+                ("top", "import main;"),
+                ("main", "import a;"),
+                ("a", "type User = string;"),
+                ("other", "type X = u8;"),
+            ]),
+        );
+        assert_eq!(reg.dependents_of(&["a".to_string()]), vec!["main", "top"]);
+        assert!(reg.dependents_of(&["top".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn refresh_reanalyzes_dependents_of_changed_files() {
+        let root = temp_workspace(
+            "refresh",
+            &[
+                ("main", "import a;\ntype X = User;\n"),
+                ("a", "type User = string;\n"),
+            ],
+        );
+
+        let reg = registry(root.clone(), HashMap::new());
+        assert!(reg.load_module(&["main"], true, None).is_empty());
+
+        // Unchanged content is skipped
+        let summary = reg.refresh(&[root.join("a.xen")]);
+        assert!(summary.changed.is_empty() && summary.dependents.is_empty());
+
+        fs::write(root.join("a.xen"), "type Person = string;\n").unwrap();
+        let summary = reg.refresh(&[root.join("a.xen")]);
+        assert_eq!(summary.changed, vec!["a"]);
+        assert_eq!(summary.dependents, vec!["main"]);
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(summary.errors[0].module_path, "main");
+        assert_eq!(summary.errors[0].code, codes::UNKNOWN_TYPE);
+
+        fs::remove_file(root.join("a.xen")).unwrap();
+        let summary = reg.refresh(&[root.join("a.xen")]);
+        assert_eq!(summary.removed, vec!["a"]);
+        assert_eq!(summary.dependents, vec!["main"]);
+        assert!(!reg.module_cache.blocking_read().contains_key("a"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Writes the module path of every module it generates next to its source.
+    #[derive(Default)]
+    struct OutputWriter {
+        written: Vec<PathBuf>,
+    }
+
+    impl AnalyzerListener<'_> for OutputWriter {
+        fn on_after_module(&mut self, scope: &ScopeInfo) {
+            let path = scope.abs_path.with_extension("out");
+            fs::write(&path, &scope.module_path).unwrap();
+            self.written.push(path);
+        }
+
+        fn outputs(&self) -> Vec<PathBuf> {
+            self.written.clone()
+        }
+    }
+
+    fn output_writer() -> Box<dyn for<'a> AnalyzerListener<'a>> {
+        Box::new(OutputWriter::default())
+    }
+
+    static GENERATOR: XenoPlugin = XenoPlugin {
+        name: "output_writer",
+        version: "0.1.0",
+        initialize: None,
+        provide_types: None,
+        provide_annotations: None,
+        provide_config_schema: None,
+        documentation: None,
+        register_generator: Some(output_writer),
+        register_analyzer: None,
+    };
+
+    #[test]
+    fn syntax_tree_nodes_span_the_declarations_of_the_ast() {
+        let source = "// Users\nimport a { B };\n\n/** A user */\ntype User = {\n  name: string, // the name\n  role: B\n};\ntype Broken = { a: ;\ntype Page<T> = { items: T[] }; /* pages */\n";
+        let edited = source.replace("role: B", "role: B,\n  age: u8");
+        let module_path = "main".to_string();
+        let md = XenoRegistry::_create_module_data(
+            &module_path,
+            PathBuf::from("/ws/main.xen"),
+            source.to_string(),
+            0,
+        );
+        // Declarations of a reparsed module are partly reused from the previous version
+        let reparsed = XenoRegistry::_reparse_module_data(
+            &module_path,
+            PathBuf::from("/ws/main.xen"),
+            edited.clone(),
+            1,
+            Some(&md),
+        );
+
+        for md in [&md, &reparsed] {
+            let source = md.borrow_source();
+            let tree = md.borrow_syntax_tree();
+            assert_eq!(&tree.to_string(), source);
+
+            let offset = |data: &TokenData| data.v.as_ptr() as usize - source.as_ptr() as usize;
+            let tokens = md.borrow_tokens();
+            let spans = md.borrow_declaration_spans();
+            assert_eq!(spans.len(), md.borrow_ast().len());
+            assert_eq!(tree.declarations().count(), spans.len());
+            for (node, &(start, end)) in tree.declarations().zip(spans) {
+                let last = &tokens[end - 1].1;
+                let node_tokens = node.tokens();
+                let node_last = node_tokens.iter().rfind(|t| !t.kind.is_trivia()).unwrap();
+                assert_eq!(node.range.start, offset(&tokens[start].1));
+                assert_eq!(node_last.range().end, offset(last) + last.v.len());
+            }
+        }
+    }
+
+    #[test]
+    fn deleted_outputs_are_generated_again() {
+        let root = temp_workspace("outputs", &[("main", "type X = string;\n")]);
+        let output = root.join("main.out");
+
+        // Every run is a new registry, with the build cache of the previous one
+        let generate = |build_cache: Option<BuildCache>| {
+            let plugins: &'static Vec<_> = Box::leak(Box::new(vec![&GENERATOR]));
+            let mut reg = registry(root.clone(), HashMap::new());
+            reg.plugins = plugins;
+            reg.analyzer = Analyzer::new(true, plugins);
+            reg.build_cache = build_cache;
+            assert!(reg.load_module(&["main"], true, None).is_empty());
+            reg.snapshot_build_cache()
+        };
+        let is_fresh = |build_cache: &BuildCache| {
+            let source = fs::read_to_string(root.join("main.xen")).unwrap();
+            let hash = calculate_hash(&source);
+            let md = XenoRegistry::_create_module_data(
+                &"main".to_string(),
+                root.join("main.xen"),
+                source,
+                hash,
+            );
+            build_cache.is_fresh("main", &HashMap::from([("main".to_string(), md)]))
+        };
+
+        let build_cache = generate(None);
+        assert_eq!(build_cache.outputs("main"), vec![output.clone()]);
+
+        // Up to date modules aren't generated, their outputs are kept
+        fs::write(&output, "edited").unwrap();
+        assert!(!is_fresh(&build_cache));
+        fs::write(&output, "main").unwrap();
+        assert!(is_fresh(&build_cache));
+        let build_cache = generate(Some(build_cache));
+        assert_eq!(build_cache.outputs("main"), vec![output.clone()]);
+
+        fs::remove_file(&output).unwrap();
+        assert!(!is_fresh(&build_cache));
+        let build_cache = generate(Some(build_cache));
+        assert_eq!(fs::read_to_string(&output).unwrap(), "main");
+        assert!(is_fresh(&build_cache));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn edited_sources_reanalyze_their_importers() {
+        let root = temp_workspace(
+            "importers",
+            &[
+                ("main", "import a;\ntype X = User;\n"),
+                ("a", "type User = string;\n"),
+            ],
+        );
+
+        let reg = registry(root.clone(), HashMap::new());
+        assert!(reg.load_module(&["main"], true, None).is_empty());
+        assert_eq!(reg.dependents_of(&["a".to_string()]), vec!["main"]);
+
+        // Renaming `User` in the unsaved buffer of `a` invalidates `main`
+        let errors =
+            reg.load_module_from_source(&root.join("a.xen"), "type Person = string;".into());
+        assert!(errors.is_empty());
+        let main_errors = reg.get_all_errors_for("main");
+        assert_eq!(main_errors.len(), 1);
+        assert_eq!(main_errors[0].code, codes::UNKNOWN_TYPE);
+
+        // Dropping the import removes `main` from the importers of `a`
+        reg.load_module_from_source(&root.join("main.xen"), "type X = u8;".into());
+        assert!(reg.dependents_of(&["a".to_string()]).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn edited_sources_are_parsed_incrementally() {
+        let root = temp_workspace("reparse", &[("main", "type A = u8;\ntype B = { a: A };\n")]);
+
+        let reg = registry(root.clone(), HashMap::new());
+        assert!(reg.load_module(&["main"], true, None).is_empty());
+
+        let edited = "type A = u8 | u16;\n\ntype B = { a: A };\ntype C = B;\n";
+        assert!(reg
+            .load_module_from_source(&root.join("main.xen"), edited.into())
+            .is_empty());
+
+        let fresh = XenoRegistry::_create_module_data(
+            &"main".to_string(),
+            root.join("main.xen"),
+            edited.to_string(),
+            0,
+        );
+        let cache = reg.module_cache.blocking_read();
+        let main = &cache["main"];
+        assert_eq!(
+            format!("{:?}", main.borrow_ast()),
+            format!("{:?}", fresh.borrow_ast())
+        );
+        assert_eq!(
+            main.borrow_declaration_spans(),
+            &[(0, 7), (7, 16), (16, 21)]
+        );
+        assert_eq!(main.borrow_declarations()["B"].line, 2);
+        drop(cache);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn bench_fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../tests/bench")
+            .canonicalize()
+            .unwrap()
+    }
+
+    fn debug_errors(errors: &[ModuleError]) -> Vec<String> {
+        errors.iter().map(|e| format!("{:?}", e)).collect()
+    }
+
+    #[test]
+    fn parallel_loading_matches_sequential_loading() {
+        let parallel = registry(bench_fixture(), HashMap::new());
+        assert!(parallel.load_module_tree(&["index"]).is_empty());
+        let sequential = registry(bench_fixture(), HashMap::new());
+        assert!(sequential.load_module(&["index"], true, None).is_empty());
+
+        let modules = |reg: &XenoRegistry| {
+            let mut paths: Vec<ModulePath> =
+                reg.module_cache.blocking_read().keys().cloned().collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(modules(&parallel).len(), 121);
+        assert_eq!(modules(&parallel), modules(&sequential));
+        assert_eq!(
+            parallel.dependents_of(&["core/c00".to_string()]),
+            sequential.dependents_of(&["core/c00".to_string()])
+        );
+    }
+
+    #[test]
+    fn parallel_loading_reports_errors_deterministically() {
+        let dir = std::env::temp_dir().join(format!("xeno-parallel-{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        let root = dir.canonicalize().unwrap();
+        fs::write(
+            root.join("main.xen"),
+            "import lib/a;\nimport lib/b;\nimport missing;\ntype M = A | B | Nope;\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("lib/a.xen"),
+            "import lib/b;\ntype A = B;\ntype A = u8;\n",
+        )
+        .unwrap();
+        fs::write(root.join("lib/b.xen"), "import lib/c;\ntype B = Unknown;\n").unwrap();
+        fs::write(
+            root.join("lib/c.xen"),
+            "import lib/a;\ntype C = @min(1) string;\n",
+        )
+        .unwrap();
+
+        let runs: Vec<Vec<String>> = (0..8)
+            .map(|_| {
+                debug_errors(&registry(root.clone(), HashMap::new()).load_module_tree(&["main"]))
+            })
+            .collect();
+        assert!(!runs[0].is_empty());
+        assert!(runs.iter().all(|run| run == &runs[0]));
+
+        // Same errors as loading one module at a time, only the order differs
+        let mut sequential = debug_errors(&registry(root.clone(), HashMap::new()).load_module(
+            &["main"],
+            true,
+            None,
+        ));
+        let mut parallel = runs[0].clone();
+        sequential.sort();
+        parallel.sort();
+        assert_eq!(parallel, sequential);
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,57 @@
+//! Workspaces and registries for the tests of the crate.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use tokio::sync::RwLock;
+
+use crate::module::types::ModulePath;
+use crate::module::{ModuleData, XenoRegistry};
+use crate::semantic::Analyzer;
+
+/// Module data of every `(module path, source)` of `modules`, as if they were files under `/ws`.
+pub fn workspace(modules: &[(&str, &str)]) -> HashMap<ModulePath, ModuleData> {
+    modules
+        .iter()
+        .map(|(path, src)| {
+            let module_path = path.to_string();
+            let abs_path = PathBuf::from(format!("/ws/{}.xen", path));
+            let md = XenoRegistry::_create_module_data(&module_path, abs_path, src.to_string(), 0);
+            (module_path, md)
+        })
+        .collect()
+}
+
+/// A registry for the workspace at `root` that already holds the modules of `cache`.
+pub fn registry(root: PathBuf, cache: HashMap<ModulePath, ModuleData>) -> XenoRegistry {
+    let reg = XenoRegistry {
+        module_cache: RwLock::new(HashMap::new()),
+        importers: RwLock::new(HashMap::new()),
+        build_cache: None,
+        root,
+        entry: "main".to_string(),
+        plugins: Box::leak(Box::default()),
+        analyzer: Analyzer::new(false, &[]),
+    };
+    for (path, md) in &cache {
+        reg._index_imports(path, &[], md.borrow_imports());
+    }
+    *reg.module_cache.blocking_write() = cache;
+    reg
+}
+
+/// Writes the `(module path, source)` files of `modules` to a fresh temporary directory named after `name`
+/// and returns its canonical path.
+pub fn temp_workspace(name: &str, modules: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xeno-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let root = dir.canonicalize().unwrap();
+    for (path, source) in modules {
+        let file = root.join(format!("{}.xen", path));
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, source).unwrap();
+    }
+    root
+}
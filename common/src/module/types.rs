@@ -1,4 +1,12 @@
-use std::{fmt, path::PathBuf};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diagnostic::{ErrorCode, Severity},
+    XenoError,
+};
 
 /// Error severity level.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -9,6 +17,17 @@ pub enum ErrorPhase {
     Module,
 }
 
+impl fmt::Display for ErrorPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorPhase::Lexer => write!(f, "lexer"),
+            ErrorPhase::Parser => write!(f, "parser"),
+            ErrorPhase::Analyzer => write!(f, "analyzer"),
+            ErrorPhase::Module => write!(f, "module"),
+        }
+    }
+}
+
 /// Errors that can occur during module loading.
 #[derive(Debug, Clone)]
 pub struct ModuleError {
@@ -16,11 +35,71 @@ pub struct ModuleError {
     pub message: String,
     pub location: Option<(u32, u32, u32)>, // line, column, length
     pub phase: ErrorPhase,
+    pub severity: Severity,
+    pub code: ErrorCode,
+    /// Other locations involved in the error, e.g. every declaration of a duplicated name.
+    pub related: Vec<RelatedLocation>,
+}
+
+/// What `XenoRegistry::refresh` did with a batch of changed files.
+#[derive(Debug, Clone, Default)]
+pub struct RefreshSummary {
+    /// Modules whose content changed and were parsed again.
+    pub changed: Vec<ModulePath>,
+    /// Modules whose file was deleted.
+    pub removed: Vec<ModulePath>,
+    /// Modules analyzed again because they import a changed or removed module.
+    pub dependents: Vec<ModulePath>,
+    /// Every error of the changed modules, their dependents and newly imported modules.
+    pub errors: Vec<ModuleError>,
+}
+
+/// A secondary location attached to a `ModuleError`.
+#[derive(Debug, Clone)]
+pub struct RelatedLocation {
+    pub module_path: ModulePath,
+    pub abs_path: PathBuf,
+    pub location: (u32, u32, u32), // line, column, length
+    pub message: String,
+}
+
+impl ModuleError {
+    /// Converts an error reported while processing a module, its labels become related locations in the same file.
+    pub fn from_xeno(
+        module_path: &str,
+        abs_path: &Path,
+        phase: ErrorPhase,
+        error: &XenoError,
+    ) -> Self {
+        let span = |t: &crate::TokenData| (t.l, t.c, t.v.len() as u32);
+        ModuleError {
+            module_path: module_path.to_string(),
+            message: error.message.clone(),
+            location: Some(span(&error.location)),
+            phase,
+            severity: error.severity,
+            code: error.code,
+            related: error
+                .labels
+                .iter()
+                .map(|(location, message)| RelatedLocation {
+                    module_path: module_path.to_string(),
+                    abs_path: abs_path.to_path_buf(),
+                    location: span(location),
+                    message: message.clone(),
+                })
+                .collect(),
+        }
+    }
 }
 
 impl fmt::Display for ModuleError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[{}] {}", self.module_path, self.message)
+        write!(
+            f,
+            "[{}] {}[{}]: {}",
+            self.module_path, self.severity, self.code, self.message
+        )
     }
 }
 
@@ -28,6 +107,62 @@ impl fmt::Display for ModuleError {
 /// This is the canonical key into the ModuleMap.
 pub type ModulePath = String;
 
+/// An import declaration of a module, resolved to the imported module path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleImport {
+    /// The imported module.
+    pub module_path: ModulePath,
+    /// The names listed in braces, `None` imports every declaration of the module.
+    pub names: Option<Vec<ImportedName>>,
+    /// Line, column and length of the import path in the importing module.
+    pub location: (u32, u32, u32),
+    /// `export import`, the imported names are visible to importers of the importing module too.
+    pub exported: bool,
+}
+
+/// A name listed in a selective import, e.g. `Role as UserRole`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedName {
+    /// The name of the declaration in the imported module.
+    pub name: String,
+    /// The name it is bound to in the importing module, if renamed.
+    pub alias: Option<String>,
+    /// Line, column and length of the local name in the importing module.
+    pub location: (u32, u32, u32),
+}
+
+impl ImportedName {
+    /// The name the declaration is visible as in the importing module.
+    pub fn local_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl ModuleImport {
+    /// Location of the import of `local`: the listed name for selective imports, the path otherwise.
+    pub fn location_of(&self, local: &str) -> (u32, u32, u32) {
+        self.names
+            .iter()
+            .flatten()
+            .find(|n| n.local_name() == local)
+            .map_or(self.location, |n| n.location)
+    }
+
+    /// Pairs of `(local name, declared name)` this import brings into scope,
+    /// given the names declared by the imported module.
+    /// Listed names the module doesn't declare are left out.
+    pub fn bindings<'a>(&'a self, declared: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+        match &self.names {
+            None => declared.iter().map(|name| (*name, *name)).collect(),
+            Some(names) => names
+                .iter()
+                .filter(|n| declared.contains(&n.name.as_str()))
+                .map(|n| (n.local_name(), n.name.as_str()))
+                .collect(),
+        }
+    }
+}
+
 /// A cache entry for a declaration found during analysis.
 /// Records which module the declaration lives in.
 #[derive(Debug, Clone)]
@@ -46,6 +181,89 @@ pub struct DeclarationInfo {
     pub column: u32,
     /// Length of the declaration name.
     pub name_len: u32,
+    /// Type parameter names of a generic declaration, in order.
+    pub generics: Vec<String>,
     /// For struct declarations, the fields of the struct.
-    pub fields: Option<Vec<(String, String)>>,
+    pub fields: Option<Vec<FieldInfo>>,
+}
+
+impl DeclarationInfo {
+    /// Looks up a struct field of this declaration by name.
+    pub fn field(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.as_ref()?.iter().find(|f| f.name == name)
+    }
+}
+
+/// A struct field of a declared type.
+#[derive(Debug, Clone)]
+pub struct FieldInfo {
+    /// The field name.
+    pub name: String,
+    /// The field's type as written in the source, without annotations.
+    pub type_name: String,
+    /// Line number (0-indexed) of the field name.
+    pub line: u32,
+    /// Column number (0-indexed) of the field name.
+    pub column: u32,
+}
+
+/// How a type declaration refers to another type, from the weakest to the strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TypeRelation {
+    /// Anywhere inside, e.g. as the type of a field or the items of a list
+    Embeds,
+    /// As an operand of `&`, `type Admin = User & { ... }`
+    Intersects,
+    /// As the whole type, possibly refined by annotations, `type Name = string @minlen(1)`
+    Extends,
+}
+
+impl fmt::Display for TypeRelation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeRelation::Embeds => write!(f, "embeds"),
+            TypeRelation::Intersects => write!(f, "intersects"),
+            TypeRelation::Extends => write!(f, "extends"),
+        }
+    }
+}
+
+/// A struct field with the names referring to it, see `XenoRegistry::field_references`.
+#[derive(Debug, Clone)]
+pub struct FieldReferences {
+    /// The module declaring the field
+    pub module_path: ModulePath,
+    pub abs_path: PathBuf,
+    /// The type declaration the field's struct is part of
+    pub owner: String,
+    pub name: String,
+    /// The name of the field as `(line, column, length)` where it's declared
+    pub declaration: (u32, u32, u32),
+    /// `$name` references in the field's own struct, in the declaring module
+    pub siblings: Vec<(u32, u32, u32)>,
+    /// Member paths like `User.name` naming the field, only fields of top-level structs have them
+    pub members: Vec<MemberPaths>,
+    /// Field names the field can't be renamed to: the other fields of its struct, and
+    /// of every top-level struct of the declaration for fields member paths can name.
+    pub taken: Vec<String>,
+}
+
+/// The members of the paths in one module that name a field, see `FieldReferences`.
+#[derive(Debug, Clone)]
+pub struct MemberPaths {
+    pub module_path: ModulePath,
+    pub abs_path: PathBuf,
+    /// Every member as `(line, column, length)`
+    pub locations: Vec<(u32, u32, u32)>,
+}
+
+/// One type declaration referring to another, see `XenoRegistry::type_users`.
+#[derive(Debug, Clone)]
+pub struct TypeUse {
+    /// The declaration on the other side of the relation
+    pub declaration: DeclarationInfo,
+    /// The strongest way the type is referred to
+    pub relation: TypeRelation,
+    /// Every reference as `(line, column, length)` in the referring module.
+    pub locations: Vec<(u32, u32, u32)>,
 }
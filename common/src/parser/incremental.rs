@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+
+use crate::{
+    lexer::{Token, TokenVariant, XenoTokens},
+    parser::{
+        AnonymType, Declaration, DeclarationSpan, Expr, ImportItem, KeyValExpr, Literal,
+        NumberType, Parser, XenoParseResult,
+    },
+    utils::extract_documentation,
+    TokenData,
+};
+
+/// What an earlier version of the same module was parsed into, see `Parser::parse_range`.
+#[derive(Clone, Copy)]
+pub struct PreviousParse<'a, 'old> {
+    pub tokens: &'a XenoTokens<'old>,
+    pub ast: &'a [Declaration<'old>],
+    pub spans: &'a [DeclarationSpan],
+}
+
+impl<'src> Parser<'src> {
+    /**
+    Range `[start, end)` of `tokens` that differs from `old`: the tokens in front of it
+    and the tokens after it are the same as the first and last tokens of `old`.
+    Only the kind and text of tokens are compared, edits that only move them don't count.
+    */
+    pub fn changed_range(old: &XenoTokens<'_>, tokens: &XenoTokens<'_>) -> (usize, usize) {
+        let same = |(a, b): (&Token<'_>, &Token<'_>)| a.0 == b.0 && a.1.v == b.1.v;
+        let start = old.iter().zip(tokens).take_while(|&p| same(p)).count();
+        let end = old
+            .iter()
+            .rev()
+            .zip(tokens.iter().rev())
+            .take(old.len().min(tokens.len()) - start)
+            .take_while(|&p| same(p))
+            .count();
+        (start, tokens.len() - end)
+    }
+
+    /**
+    Parses `tokens`, reusing the declarations of `previous` outside of the changed `range`
+    (see `changed_range`) instead of parsing them again. Declarations that had errors are
+    always parsed again, so the result is the same as what `parse_with_spans` returns.
+    */
+    pub fn parse_range(
+        tokens: &'src XenoTokens<'src>,
+        previous: PreviousParse<'_, '_>,
+        range: (usize, usize),
+    ) -> (XenoParseResult<'src>, Vec<DeclarationSpan>) {
+        let (start, end) = range;
+        let old_end = previous.tokens.len() - (tokens.len() - end);
+        let mut parser = Self::new(tokens);
+        let mut ast = Vec::new();
+        let mut errs = Vec::new();
+        let mut spans = Vec::new();
+
+        while parser.is_not_eof() {
+            let from = parser.current;
+            // Where the same tokens started in the previous version
+            let old_from = if from < start {
+                Some(from)
+            } else if from >= end {
+                Some(from - end + old_end)
+            } else {
+                None
+            };
+            let reusable = old_from.and_then(|old_from| {
+                let i = previous
+                    .spans
+                    .binary_search_by_key(&old_from, |(s, _)| *s)
+                    .ok()?;
+                let len = previous.spans[i].1 - old_from;
+                (from >= end || from + len <= start).then_some((i, len))
+            });
+
+            // A declaration that can't be moved onto the new tokens is parsed again
+            let rebased = reusable.and_then(|(i, len)| {
+                let old_start = previous.spans[i].0;
+                let rebase = Rebase::new(
+                    previous.tokens.get(old_start..old_start + len)?,
+                    tokens.get(from..from + len)?,
+                );
+                Some((rebase.declaration(previous.ast.get(i)?)?, len))
+            });
+
+            match rebased {
+                Some((declaration, len)) => {
+                    ast.push(declaration);
+                    spans.push((from, from + len));
+                    parser.current = from + len;
+                }
+                None => parser.parse_next(&mut ast, &mut errs, &mut spans),
+            }
+        }
+
+        ((ast, errs), spans)
+    }
+}
+
+/**
+Moves a declaration of the previous tokens onto the same tokens of the new version.
+Tokens are matched by their index in the declaration's span, found through their line and column.
+Anything the span doesn't contain makes the declaration fail to move instead of guessing.
+*/
+struct Rebase<'a, 'old, 'src> {
+    /// Tokens of the declaration's span in the previous version
+    old: &'a [Token<'old>],
+    /// The same tokens in the new version
+    new: &'src [Token<'src>],
+    /// Index in the span of every token of `old` by its line and column
+    index: HashMap<(u32, u32), usize>,
+}
+
+impl<'a, 'old, 'src> Rebase<'a, 'old, 'src> {
+    fn new(old: &'a [Token<'old>], new: &'src [Token<'src>]) -> Self {
+        let index = old
+            .iter()
+            .enumerate()
+            .map(|(i, (_, t))| ((t.l, t.c), i))
+            .collect();
+        Self { old, new, index }
+    }
+
+    /// Index in the span of the token `t` refers to.
+    fn index_of(&self, t: &TokenData<'old>) -> Option<usize> {
+        let &i = self.index.get(&(t.l, t.c))?;
+        (self.old[i].1.v == t.v).then_some(i)
+    }
+
+    fn token(&self, t: &TokenData<'old>) -> Option<&'src TokenData<'src>> {
+        self.index_of(t).map(|i| &self.new[i].1)
+    }
+
+    /// Documentation of the declaration, taken from the new documentation token.
+    fn docs(&self, docs: &str) -> Option<&'src str> {
+        let (variant, token) = self.new.iter().find(|t| t.0 != TokenVariant::Error)?;
+        let rebased =
+            (*variant == TokenVariant::Documentation).then(|| extract_documentation(token))?;
+        (rebased == docs).then_some(rebased)
+    }
+
+    /// Segments of an import path, the identifiers following the `import` keyword at `location`.
+    fn path(&self, location: &TokenData<'old>, path: &[&str]) -> Option<Vec<&'src str>> {
+        let start = self.index_of(location)? + 1;
+        let segments: Vec<&'src str> = self.new[start..]
+            .iter()
+            .filter(|t| !matches!(t.0, TokenVariant::Error | TokenVariant::Slash))
+            .take(path.len())
+            .map(|t| t.1.v)
+            .collect();
+        (segments == path).then_some(segments)
+    }
+
+    fn declaration(&self, d: &Declaration<'old>) -> Option<Declaration<'src>> {
+        Some(match d {
+            Declaration::Import {
+                path,
+                items,
+                location,
+                exported,
+            } => Declaration::Import {
+                path: self.path(location, path)?,
+                items: match items {
+                    Some(items) => Some(
+                        items
+                            .iter()
+                            .map(|item| {
+                                Some(ImportItem {
+                                    name: self.token(item.name)?,
+                                    alias: match item.alias {
+                                        Some(alias) => Some(self.token(alias)?),
+                                        None => None,
+                                    },
+                                })
+                            })
+                            .collect::<Option<_>>()?,
+                    ),
+                    None => None,
+                },
+                location: self.token(location)?,
+                exported: *exported,
+            },
+            Declaration::TypeDecl {
+                docs,
+                name,
+                generics,
+                t,
+            } => Declaration::TypeDecl {
+                docs: match docs {
+                    Some(docs) => Some(self.docs(docs)?),
+                    None => None,
+                },
+                name: self.token(name)?,
+                generics: generics
+                    .iter()
+                    .map(|g| self.token(g))
+                    .collect::<Option<_>>()?,
+                t: self.anonym_type(t)?,
+            },
+        })
+    }
+
+    fn anonym_type(&self, t: &AnonymType<'old>) -> Option<AnonymType<'src>> {
+        t.iter().map(|e| self.expr(e)).collect()
+    }
+
+    fn type_list(&self, list: &[AnonymType<'old>]) -> Option<Vec<AnonymType<'src>>> {
+        list.iter().map(|t| self.anonym_type(t)).collect()
+    }
+
+    fn fields(&self, fields: &[KeyValExpr<'old>]) -> Option<Vec<KeyValExpr<'src>>> {
+        fields
+            .iter()
+            .map(|(name, t)| Some((self.token(name)?, self.anonym_type(t)?)))
+            .collect()
+    }
+
+    fn expr(&self, e: &Expr<'old>) -> Option<Expr<'src>> {
+        Some(match e {
+            Expr::Identifier(t) => Expr::Identifier(self.token(t)?),
+            Expr::Literal(literal) => Expr::Literal(match literal {
+                Literal::Number(NumberType::Int(n, t)) => {
+                    Literal::Number(NumberType::Int(*n, self.token(t)?))
+                }
+                Literal::Number(NumberType::Float(n, t)) => {
+                    Literal::Number(NumberType::Float(*n, self.token(t)?))
+                }
+                Literal::String(s, t) => Literal::String(s.clone(), self.token(t)?),
+                Literal::Boolean(b, t) => Literal::Boolean(*b, self.token(t)?),
+            }),
+            Expr::Regex(t) => Expr::Regex(self.token(t)?),
+            Expr::Annotation(t, args) => Expr::Annotation(self.token(t)?, self.type_list(args)?),
+            Expr::Not(e) => Expr::Not(Box::new(self.expr(e)?)),
+            Expr::FieldAccess(t) => Expr::FieldAccess(self.token(t)?),
+            Expr::BinaryExpr(op, operands) => Expr::BinaryExpr(
+                *op,
+                Box::new((self.expr(&operands.0)?, self.expr(&operands.1)?)),
+            ),
+            Expr::List(list) => Expr::List(self.type_list(list)?),
+            Expr::Set(list) => Expr::Set(self.type_list(list)?),
+            Expr::Array(t) => Expr::Array(self.token(t)?),
+            Expr::TypeApplication(t, args) => {
+                Expr::TypeApplication(self.token(t)?, self.type_list(args)?)
+            }
+            Expr::MemberAccess(t, members) => Expr::MemberAccess(
+                self.token(t)?,
+                members
+                    .iter()
+                    .map(|m| self.token(m))
+                    .collect::<Option<_>>()?,
+            ),
+            Expr::Struct(fields) => Expr::Struct(self.fields(fields)?),
+            Expr::Enum(fields) => Expr::Enum(self.fields(fields)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+    use crate::lexer::Lexer;
+
+    const SCHEMA: &str = r#"import common/types { Id, Role as UserRole };
+export import common/base;
+
+/** A user of the system */
+type User = {
+    _id: Id,
+    name: string @min(1) @max(64),
+    email: string @regex(/^[^@]+@[^@]+$/),
+    role: UserRole | "guest",
+    tags: set[string],
+    friends: User._id[],
+};
+
+type Page<T> = { items: T[], total: u32 @min(0) };
+type Status = enum { Active: 1, Banned: 2 };
+type Score = f64 & !0.5;
+"#;
+
+    /// Pieces that are likely to break or repair declarations when typed into the schema.
+    const FRAGMENTS: &[&str] = &[
+        ";",
+        "{",
+        "}",
+        "[",
+        "]",
+        "(",
+        ")",
+        "<",
+        ">",
+        ",",
+        ":",
+        "|",
+        "&",
+        "@",
+        "$",
+        "\"",
+        "/",
+        "\n",
+        " ",
+        "type ",
+        "import ",
+        "export ",
+        "/** doc */\n",
+        "// note\n",
+        "x",
+        "Ü",
+        "42",
+        "1.5",
+        "u8",
+        "type A = u8;\n",
+        "a: string, ",
+        "enum { A: 1 }",
+        "User._id",
+        "Page<User>",
+    ];
+
+    /// Small xorshift generator so failures can be reproduced from the seed.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn boundary(&mut self, text: &str) -> usize {
+            let mut i = self.below(text.len() + 1);
+            while !text.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        }
+    }
+
+    fn edit(rng: &mut Rng, text: &str) -> String {
+        let from = rng.boundary(text);
+        let to = (from + rng.below(12)).min(text.len());
+        let to = (to..=text.len())
+            .find(|&i| text.is_char_boundary(i))
+            .unwrap();
+        let inserted = match rng.below(3) {
+            0 => "",
+            _ => FRAGMENTS[rng.below(FRAGMENTS.len())],
+        };
+        format!("{}{}{}", &text[..from], inserted, &text[to..])
+    }
+
+    fn parse_text<'src>(
+        tokens: &'src XenoTokens<'src>,
+        previous: Option<PreviousParse<'_, '_>>,
+    ) -> (String, Vec<DeclarationSpan>) {
+        let ((ast, errs), spans) = match previous {
+            Some(previous) => Parser::parse_range(
+                tokens,
+                previous,
+                Parser::changed_range(previous.tokens, tokens),
+            ),
+            None => Parser::parse_with_spans(tokens),
+        };
+        (format!("{:?}\n{:?}", ast, errs), spans)
+    }
+
+    #[test]
+    fn incremental_parses_match_full_parses_after_random_edits() {
+        for seed in 1..=40u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+            let mut versions = vec![SCHEMA.to_string()];
+            for _ in 0..50 {
+                let next = edit(&mut rng, versions.last().unwrap());
+                versions.push(next);
+            }
+
+            for (step, pair) in versions.windows(2).enumerate() {
+                let (old_tokens, _) = Lexer::tokenize(&pair[0]);
+                let (tokens, _) = Lexer::tokenize(&pair[1]);
+                let ((old_ast, _), old_spans) = Parser::parse_with_spans(&old_tokens);
+                let previous = PreviousParse {
+                    tokens: &old_tokens,
+                    ast: &old_ast,
+                    spans: &old_spans,
+                };
+
+                assert_eq!(
+                    parse_text(&tokens, Some(previous)),
+                    parse_text(&tokens, None),
+                    "seed {} step {}, after editing\n{}\ninto\n{}",
+                    seed,
+                    step,
+                    pair[0],
+                    pair[1]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn declarations_after_an_edit_move_with_their_tokens() {
+        let old_text = "type A = u8;\n/** Docs */\ntype B = { x: A };\n";
+        let new_text = "type A = u8 | u16;\n\n/** Docs */\ntype B = { x: A };\n";
+        let (old_tokens, _) = Lexer::tokenize(old_text);
+        let (new_tokens, _) = Lexer::tokenize(new_text);
+        let ((old_ast, _), old_spans) = Parser::parse_with_spans(&old_tokens);
+
+        let range = Parser::changed_range(&old_tokens, &new_tokens);
+        assert_eq!(range, (4, 6));
+        let previous = PreviousParse {
+            tokens: &old_tokens,
+            ast: &old_ast,
+            spans: &old_spans,
+        };
+        let ((ast, errs), spans) = Parser::parse_range(&new_tokens, previous, range);
+
+        assert!(errs.is_empty());
+        assert_eq!(spans, vec![(0, 7), (7, 17)]);
+        let Declaration::TypeDecl { docs, name, .. } = &ast[1] else {
+            panic!("expected a type declaration");
+        };
+        assert_eq!(*docs, Some("Docs"));
+        assert_eq!((name.v, name.l, name.c), ("B", 3, 5));
+        assert!(ptr::eq(*name, &new_tokens[9].1));
+    }
+
+    #[test]
+    fn declarations_of_other_buffers_are_moved_by_position_or_parsed_again() {
+        let text = "import common/types { Id };\n/** Docs */\ntype A = { id: Id @min(1) };\n";
+        let (old_tokens, _) = Lexer::tokenize(text);
+        let (tokens, _) = Lexer::tokenize(text);
+        let (_, old_spans) = Parser::parse_with_spans(&old_tokens);
+
+        // The same declarations parsed from a copy of the tokens
+        let (copy, _) = Lexer::tokenize(text);
+        let ((copy_ast, _), _) = Parser::parse_with_spans(&copy);
+        // Declarations of an unrelated source that don't line up with the spans
+        let (other, _) = Lexer::tokenize("type B = string;\n\n\ntype C = u8;\n");
+        let ((other_ast, _), _) = Parser::parse_with_spans(&other);
+
+        for ast in [&copy_ast, &other_ast] {
+            let previous = PreviousParse {
+                tokens: &old_tokens,
+                ast,
+                spans: &old_spans,
+            };
+            let range = Parser::changed_range(&old_tokens, &tokens);
+            let ((rebased, errs), spans) = Parser::parse_range(&tokens, previous, range);
+            assert!(errs.is_empty());
+            assert_eq!(spans, old_spans);
+            assert_eq!(
+                format!("{:?}", rebased),
+                format!("{:?}", Parser::parse(&tokens).0)
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
+mod incremental;
 mod parser;
 mod parser_expr;
 
+pub use incremental::*;
 pub use parser::*;
 pub use parser_expr::*;
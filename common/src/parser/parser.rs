@@ -1,7 +1,9 @@
 use crate::{
+    diagnostic::{codes, Severity},
     lexer::{Token, TokenVariant, XenoTokens},
     parser::{
-        AnonymType, BinaryExprType, Declaration, Expr, KeyValExpr, Literal, NumberType, TypeList,
+        AnonymType, BinaryExprType, Declaration, Expr, ImportItem, KeyValExpr, Literal, NumberType,
+        TypeList,
     },
     utils::extract_documentation,
     TokenData, XenoError,
@@ -15,38 +17,57 @@ pub struct Parser<'src> {
 
 pub type XenoAst<'src> = Vec<Declaration<'src>>;
 pub type XenoParseResult<'src> = (XenoAst<'src>, Vec<XenoError<'src>>);
+/// Token range `[start, end)` a declaration was parsed from, including the error tokens in front of it.
+pub type DeclarationSpan = (usize, usize);
 
 impl<'src> Parser<'src> {
-    fn new(tokens: &'src XenoTokens<'src>) -> Self {
+    pub(super) fn new(tokens: &'src XenoTokens<'src>) -> Self {
         Self { tokens, current: 0 }
     }
 
     pub fn parse(tokens: &'src XenoTokens<'src>) -> XenoParseResult<'src> {
+        Self::parse_with_spans(tokens).0
+    }
+
+    /// Like `parse`, also returns the span of every declaration in the AST.
+    pub fn parse_with_spans(
+        tokens: &'src XenoTokens<'src>,
+    ) -> (XenoParseResult<'src>, Vec<DeclarationSpan>) {
         Self::new(tokens)._parse()
     }
-    fn _parse(mut self) -> XenoParseResult<'src> {
+    fn _parse(mut self) -> (XenoParseResult<'src>, Vec<DeclarationSpan>) {
         let mut ast = Vec::new();
         let mut errs = Vec::new();
+        let mut spans = Vec::new();
 
         while self.is_not_eof() {
-            match self.parse_declaration() {
-                Err(e) => {
-                    errs.extend(e);
-                    self.recover_to(TokenVariant::Semicolon);
-                }
-                Ok(d) => ast.push(d),
-            }
+            self.parse_next(&mut ast, &mut errs, &mut spans);
         }
 
-        (ast, errs)
+        ((ast, errs), spans)
     }
 
-    pub fn parse_range(
-        _tokens: &'src XenoTokens<'src>,
-        _old_ast: Vec<Declaration<'src>>,
-        _range: (usize, usize),
-    ) -> XenoParseResult<'src> {
-        panic!("Not implemented yet")
+    /**
+    Parses the declaration at the current token. Spans are only kept for declarations
+    that parsed without errors, those are the ones a later `parse_range` can reuse.
+    */
+    pub(super) fn parse_next(
+        &mut self,
+        ast: &mut XenoAst<'src>,
+        errs: &mut Vec<XenoError<'src>>,
+        spans: &mut Vec<DeclarationSpan>,
+    ) {
+        let start = self.current;
+        match self.parse_declaration() {
+            Err(e) => {
+                errs.extend(e);
+                self.recover_to(TokenVariant::Semicolon);
+            }
+            Ok(d) => {
+                ast.push(d);
+                spans.push((start, self.current));
+            }
+        }
     }
 
     fn recover_to(&mut self, variant: TokenVariant) {
@@ -63,11 +84,24 @@ impl<'src> Parser<'src> {
         }
     }
 
-    fn is_not_eof(&self) -> bool {
-        self.current < self.tokens.len()
+    /**
+    Index of the first token at or after `from` that is not an error token.
+    Error tokens were already reported by the lexer, so the parser steps over them.
+    */
+    fn skip_lexer_errors(&self, from: usize) -> usize {
+        let mut i = from;
+        while let Some((TokenVariant::Error, _)) = self.tokens.get(i) {
+            i += 1;
+        }
+        i
+    }
+
+    pub(super) fn is_not_eof(&self) -> bool {
+        self.skip_lexer_errors(self.current) < self.tokens.len()
     }
 
     fn next(&mut self) -> Result<&'src Token<'src>, XenoError<'src>> {
+        self.current = self.skip_lexer_errors(self.current);
         let d = self.tokens.get(self.current);
         match d {
             None => {
@@ -75,6 +109,9 @@ impl<'src> Parser<'src> {
                 Err(XenoError {
                     location: prev.1.clone(),
                     message: "Unexpected end of file.".to_string(),
+                    severity: Severity::Error,
+                    code: codes::UNEXPECTED_EOF,
+                    labels: Vec::new(),
                 })
             }
             Some(t) => {
@@ -84,7 +121,7 @@ impl<'src> Parser<'src> {
         }
     }
     fn peek(&self) -> Option<&Token<'src>> {
-        self.tokens.get(self.current)
+        self.tokens.get(self.skip_lexer_errors(self.current))
     }
     fn expect(
         &mut self,
@@ -95,6 +132,9 @@ impl<'src> Parser<'src> {
             return Err(vec![XenoError {
                 location: d.clone(),
                 message: format!("Expected {} at {} instead got {}.", expected, d, var),
+                severity: Severity::Error,
+                code: codes::UNEXPECTED_TOKEN,
+                labels: Vec::new(),
             }]);
         }
         Ok(d)
@@ -119,20 +159,34 @@ impl<'src> Parser<'src> {
         };
         let dec = match var {
             TokenVariant::Type => self.parse_type_declaration(docs)?,
-            TokenVariant::Import => {
+            // `export` is only a keyword in front of `import`, so it stays usable as a name elsewhere
+            TokenVariant::Import | TokenVariant::Identifier
+                if *var == TokenVariant::Import || d.v == "export" =>
+            {
                 if docs.is_some() {
                     return Err(vec![XenoError {
                         location: d.clone(),
                         message: "Import declarations cannot have documentation comments."
                             .to_string(),
+                        severity: Severity::Error,
+                        code: codes::DOCUMENTED_IMPORT,
+                        labels: Vec::new(),
                     }]);
                 }
-                self.parse_import_declaration(d)?
+                if *var == TokenVariant::Import {
+                    self.parse_import_declaration(d, false)?
+                } else {
+                    let import = self.expect(TokenVariant::Import)?;
+                    self.parse_import_declaration(import, true)?
+                }
             }
             _ => {
                 return Err(vec![XenoError {
                     location: d.clone(),
                     message: format!("Expected declaration at {}, instead found {}.", d, var),
+                    severity: Severity::Error,
+                    code: codes::UNEXPECTED_TOKEN,
+                    labels: Vec::new(),
                 }])
             }
         };
@@ -146,13 +200,51 @@ impl<'src> Parser<'src> {
         docs: Option<&'src str>,
     ) -> Result<Declaration<'src>, Vec<XenoError<'src>>> {
         let name = self.expect(TokenVariant::Identifier)?;
+        let generics = self.parse_generic_params()?;
         self.expect(TokenVariant::Eq)?;
         let t = self.parse_anonym_type()?;
-        Ok(Declaration::TypeDecl { name, t, docs })
+        Ok(Declaration::TypeDecl {
+            docs,
+            name,
+            generics,
+            t,
+        })
+    }
+    /**
+    Parses the optional `<T, U>` parameter list after a type declaration's name
+     */
+    fn parse_generic_params(&mut self) -> Result<Vec<&'src TokenData<'src>>, Vec<XenoError<'src>>> {
+        let mut params = Vec::new();
+        if self.peek().map(|t| t.0) != Some(TokenVariant::Lt) {
+            return Ok(params);
+        }
+        self.next().map_err(Parser::map_err_vec)?; // consume '<'
+
+        loop {
+            params.push(self.expect(TokenVariant::Identifier)?);
+
+            let (var, d) = self.next().map_err(Parser::map_err_vec)?;
+            match var {
+                TokenVariant::Comma => continue,
+                TokenVariant::Gt => break,
+                _ => {
+                    return Err(vec![XenoError {
+                        location: d.clone(),
+                        message: format!("Expected Comma or Gt at {} instead got {}.", d, var),
+                        severity: Severity::Error,
+                        code: codes::UNEXPECTED_TOKEN,
+                        labels: Vec::new(),
+                    }])
+                }
+            }
+        }
+
+        Ok(params)
     }
     fn parse_import_declaration(
         &mut self,
         location: &'src TokenData<'src>,
+        exported: bool,
     ) -> Result<Declaration<'src>, Vec<XenoError<'src>>> {
         let first = self.expect(TokenVariant::Identifier)?;
         let mut path = vec![first.v];
@@ -163,7 +255,46 @@ impl<'src> Parser<'src> {
             path.push(segment.v);
         }
 
-        Ok(Declaration::Import { path, location })
+        let items = if self.peek().map(|t| t.0) == Some(TokenVariant::LCurly) {
+            self.next().map_err(Parser::map_err_vec)?; // consume '{'
+            Some(self.parse_import_items()?)
+        } else {
+            None
+        };
+
+        Ok(Declaration::Import {
+            path,
+            items,
+            location,
+            exported,
+        })
+    }
+    /**
+    Parses `X, Y as Z }` after the opening brace of a selective import.
+    `as` is only a keyword here so it stays usable as a name elsewhere.
+     */
+    fn parse_import_items(&mut self) -> Result<Vec<ImportItem<'src>>, Vec<XenoError<'src>>> {
+        let mut items = Vec::new();
+        while self.peek().map(|t| t.0) != Some(TokenVariant::RCurly) {
+            let name = self.expect(TokenVariant::Identifier)?;
+            let alias = match self.peek() {
+                Some((TokenVariant::Identifier, d)) if d.v == "as" => {
+                    self.next().map_err(Parser::map_err_vec)?; // consume 'as'
+                    Some(self.expect(TokenVariant::Identifier)?)
+                }
+                _ => None,
+            };
+            items.push(ImportItem { name, alias });
+
+            if self.peek().map(|t| t.0) == Some(TokenVariant::Comma) {
+                self.next().map_err(Parser::map_err_vec)?;
+            } else {
+                break;
+            }
+        }
+        self.expect(TokenVariant::RCurly)?;
+
+        Ok(items)
     }
     fn parse_anonym_type(&mut self) -> Result<AnonymType<'src>, Vec<XenoError<'src>>> {
         let mut list: Vec<Expr<'src>> = Vec::new();
@@ -190,6 +321,7 @@ impl<'src> Parser<'src> {
                     | TokenVariant::RBracket
                     | TokenVariant::RCurly
                     | TokenVariant::RParen
+                    | TokenVariant::Gt
                     | TokenVariant::Semicolon
             ) {
                 if terminator_variant == TokenVariant::Comma {
@@ -207,7 +339,7 @@ impl<'src> Parser<'src> {
         let (variant, loc) = self.next().map_err(Parser::map_err_vec)?;
 
         let res = match variant {
-            TokenVariant::Identifier => self.parse_identifier_or_array(loc)?,
+            TokenVariant::Identifier => self.parse_identifier_like(loc)?,
             TokenVariant::Dollar => Expr::FieldAccess(self.expect(TokenVariant::Identifier)?),
             TokenVariant::Number => self.parse_number(loc).map_err(Parser::map_err_vec)?,
             TokenVariant::True | TokenVariant::False => {
@@ -275,10 +407,17 @@ impl<'src> Parser<'src> {
             | TokenVariant::RParen
             | TokenVariant::RCurly
             | TokenVariant::RBracket
-            | TokenVariant::Documentation => {
+            | TokenVariant::Documentation
+            | TokenVariant::Whitespace
+            | TokenVariant::LineComment
+            | TokenVariant::BlockComment
+            | TokenVariant::Error => {
                 return Err(vec![XenoError {
                     location: loc.clone(),
                     message: format!("Unexpected token {}", variant),
+                    severity: Severity::Error,
+                    code: codes::UNEXPECTED_TOKEN,
+                    labels: Vec::new(),
                 }])
             }
         };
@@ -298,6 +437,9 @@ impl<'src> Parser<'src> {
             return Err(vec![XenoError {
                 location: loc.clone(),
                 message: "Expected expression before binary operator.".to_string(),
+                severity: Severity::Error,
+                code: codes::UNEXPECTED_TOKEN,
+                labels: Vec::new(),
             }]);
         }
 
@@ -308,16 +450,31 @@ impl<'src> Parser<'src> {
         ));
     }
 
-    fn parse_identifier_or_array(
+    fn parse_identifier_like(
         &mut self,
         d: &'src TokenData<'src>,
     ) -> Result<Expr<'src>, Vec<XenoError<'src>>> {
-        if self.peek().map(|t| t.0) == Some(TokenVariant::LBracket) {
-            self.next().map_err(Parser::map_err_vec)?; // consume '['
-            self.expect(TokenVariant::RBracket)?; // consume ']'
-            Ok(Expr::Array(d))
-        } else {
-            Ok(Expr::Identifier(d))
+        match self.peek().map(|t| t.0) {
+            Some(TokenVariant::LBracket) => {
+                self.next().map_err(Parser::map_err_vec)?; // consume '['
+                self.expect(TokenVariant::RBracket)?; // consume ']'
+                Ok(Expr::Array(d))
+            }
+            Some(TokenVariant::Lt) => {
+                self.next().map_err(Parser::map_err_vec)?; // consume '<'
+                let args = self.parse_list()?;
+                self.expect(TokenVariant::Gt)?;
+                Ok(Expr::TypeApplication(d, args))
+            }
+            Some(TokenVariant::Dot) => {
+                let mut members = Vec::new();
+                while self.peek().map(|t| t.0) == Some(TokenVariant::Dot) {
+                    self.next().map_err(Parser::map_err_vec)?; // consume '.'
+                    members.push(self.expect(TokenVariant::Identifier)?);
+                }
+                Ok(Expr::MemberAccess(d, members))
+            }
+            _ => Ok(Expr::Identifier(d)),
         }
     }
 
@@ -327,6 +484,7 @@ impl<'src> Parser<'src> {
         while !matches!(
             self.peek().map(|t| t.0),
             |Some(TokenVariant::RBracket)| Some(TokenVariant::RParen)
+                | Some(TokenVariant::Gt)
                 | Some(TokenVariant::Semicolon)
         ) {
             list.push(self.parse_anonym_type()?);
@@ -375,6 +533,9 @@ impl<'src> Parser<'src> {
                     return Err(XenoError {
                         location: d.clone(),
                         message: format!("Error parsing number: {}", e),
+                        severity: Severity::Error,
+                        code: codes::INVALID_NUMBER,
+                        labels: Vec::new(),
                     })
                 }
             }
@@ -386,6 +547,9 @@ impl<'src> Parser<'src> {
                     return Err(XenoError {
                         location: d.clone(),
                         message: format!("Error parsing number: {}", e),
+                        severity: Severity::Error,
+                        code: codes::INVALID_NUMBER,
+                        labels: Vec::new(),
                     })
                 }
             }
@@ -6,19 +6,30 @@ pub type KeyValExpr<'src> = (&'src TokenData<'src>, AnonymType<'src>);
 pub type AnonymType<'src> = Vec<Expr<'src>>;
 pub type TypeList<'src> = Vec<AnonymType<'src>>;
 
+/// A name in a selective import list, `Role as UserRole` has an alias.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportItem<'src> {
+    pub name: &'src TokenData<'src>,
+    pub alias: Option<&'src TokenData<'src>>,
+}
+
 #[derive(Debug)]
 pub enum Declaration<'src> {
     Import {
         path: Vec<&'src str>,
+        /// The braced list of imported names, `None` imports everything
+        items: Option<Vec<ImportItem<'src>>>,
         location: &'src TokenData<'src>,
+        /// `export import ...` makes the imported names visible to importers of this module
+        exported: bool,
     },
     TypeDecl {
         docs: Option<&'src str>,
         name: &'src TokenData<'src>,
+        /// Type parameters of a generic declaration, e.g. `T` in `type Page<T> = ...`
+        generics: Vec<&'src TokenData<'src>>,
         t: Vec<Expr<'src>>,
-        // TODO for boundary: location of the entire declaration, for better error messages and content finding
-        // from: Location
-        // to: Location
+        // The range of the entire declaration is its `DeclarationSpan`, or its node in the `cst::SyntaxTree`
     },
     // Custom {
     //     plugin_id: &'static str,
@@ -71,6 +82,10 @@ pub enum Expr<'src> {
     List(TypeList<'src>),
     Set(TypeList<'src>),
     Array(TokenRef<'src>),
+    /// A generic type applied to type arguments, e.g. `Page<User>`
+    TypeApplication(TokenRef<'src>, TypeList<'src>),
+    /// The type of a member of a declared type, e.g. `User._id`
+    MemberAccess(TokenRef<'src>, Vec<TokenRef<'src>>),
     Struct(Vec<KeyValExpr<'src>>),
     Enum(Vec<KeyValExpr<'src>>),
 }
@@ -78,11 +93,37 @@ pub enum Expr<'src> {
 impl<'src> fmt::Display for Declaration<'src> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Declaration::Import { path, .. } => {
-                write!(f, "import {}", path.join("/"))
+            Declaration::Import {
+                path,
+                items,
+                exported,
+                ..
+            } => {
+                if *exported {
+                    write!(f, "export ")?;
+                }
+                write!(f, "import {}", path.join("/"))?;
+                if let Some(items) = items {
+                    let items: Vec<String> = items
+                        .iter()
+                        .map(|item| match item.alias {
+                            Some(alias) => format!("{} as {}", item.name.v, alias.v),
+                            None => item.name.v.to_string(),
+                        })
+                        .collect();
+                    write!(f, " {{ {} }}", items.join(", "))?;
+                }
+                Ok(())
             }
-            Declaration::TypeDecl { name, t, .. } => {
-                write!(f, "type {} = ", name.v)?;
+            Declaration::TypeDecl {
+                name, generics, t, ..
+            } => {
+                write!(f, "type {}", name.v)?;
+                if !generics.is_empty() {
+                    let params: Vec<&str> = generics.iter().map(|g| g.v).collect();
+                    write!(f, "<{}>", params.join(", "))?;
+                }
+                write!(f, " = ")?;
                 for (i, item) in t.iter().enumerate() {
                     if i > 0 {
                         write!(f, " | ")?;
@@ -141,6 +182,23 @@ impl<'src> fmt::Display for Expr<'src> {
             Expr::Literal(lit) => write!(f, "{}", lit),
 
             Expr::Array(tok) => write!(f, "{}[]", tok.v),
+            Expr::MemberAccess(tok, members) => {
+                write!(f, "{}", tok.v)?;
+                for member in members {
+                    write!(f, ".{}", member.v)?;
+                }
+                Ok(())
+            }
+            Expr::TypeApplication(tok, args) => {
+                write!(f, "{}<", tok.v)?;
+                for (i, item) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?
+                    }
+                    format_vector_expr(f, item)?;
+                }
+                write!(f, ">")
+            }
 
             Expr::List(items) => {
                 write!(f, "[")?;
@@ -1,4 +1,7 @@
-use crate::{config::Config, semantic::AnalyzerListener};
+use crate::{
+    config::Config,
+    semantic::{AnalyzerListener, XenoParam},
+};
 use libloading::{Library, Symbol};
 use std::{
     path::{Path, PathBuf},
@@ -10,6 +13,8 @@ pub struct PluginCompletion {
     pub label: &'static str,
     pub detail: Option<&'static str>,
     pub documentation: Option<&'static str>,
+    /// Parameters of an annotation, shown while its arguments are typed. `None` for types.
+    pub params: Option<&'static [&'static XenoParam]>,
 }
 
 #[derive(Debug)]
@@ -23,6 +28,9 @@ pub struct XenoPlugin<'a> {
     /// Returns a JSON Schema (as a string) describing this plugin's
     /// `[plugins.<name>]` configuration section in `xenomorph.toml`.
     pub provide_config_schema: Option<fn() -> &'static str>,
+    /// Path of the plugin's documentation relative to the plugins directory,
+    /// editors open it from the annotations the plugin provides.
+    pub documentation: Option<&'a str>,
     pub register_generator: Option<fn() -> Box<dyn for<'b> AnalyzerListener<'b>>>,
     pub register_analyzer: Option<fn() -> Box<dyn for<'b> AnalyzerListener<'b>>>,
     // execute: fn(&[&str]),
@@ -55,6 +63,11 @@ impl<'a> XenoPlugin<'a> {
         PLUGINS.get_or_init(|| Self::load_plugins())
     }
 
+    pub fn documentation_path(&self) -> Option<PathBuf> {
+        self.documentation
+            .map(|documentation| Self::plugins_directory().join(documentation))
+    }
+
     fn plugins_directory() -> PathBuf {
         let config = Config::get();
         config.workdir.join(&config.plugins.path)
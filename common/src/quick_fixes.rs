@@ -0,0 +1,359 @@
+use std::ops::Range;
+
+use crate::cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxTree};
+use crate::diagnostic::{codes, ErrorCode};
+use crate::lexer::TokenVariant;
+use crate::module::XenoRegistry;
+use crate::semantic::BUILTIN_TYPES;
+
+/// Text replacing a byte range of a module's source, an insertion when the range is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+/// A fix for a diagnostic, editing the module the diagnostic is reported in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickFix {
+    pub title: String,
+    pub edit: SourceEdit,
+    /// The fix to apply when only one is asked for
+    pub preferred: bool,
+}
+
+/**
+Fixes for a diagnostic reported at the byte range `range` of a module whose source is `source`.
+Unknown types can be changed to a similar name in scope or imported from a module exporting them,
+misplaced and inapplicable annotations can be removed and a misplaced `@elseif` turned into an `@if`.
+Ranges that don't fit the source, e.g. of a diagnostic published for an older version, get no fixes.
+*/
+pub fn quick_fixes(
+    registry: &XenoRegistry,
+    module_path: &str,
+    source: &str,
+    code: ErrorCode,
+    range: Range<usize>,
+) -> Vec<QuickFix> {
+    let Some(name) = source.get(range.clone()) else {
+        return Vec::new();
+    };
+
+    let mut fixes = Vec::new();
+    if code == codes::UNKNOWN_TYPE {
+        let in_scope = registry
+            .get_all_declarations_in_scope(module_path)
+            .into_iter()
+            .map(|(name, _)| name);
+        for (i, suggestion) in similar_type_names(name, in_scope).into_iter().enumerate() {
+            fixes.push(QuickFix {
+                title: format!("Change to '{}'", suggestion),
+                edit: SourceEdit {
+                    range: range.clone(),
+                    new_text: suggestion,
+                },
+                preferred: i == 0,
+            });
+        }
+
+        let mut exporters: Vec<String> = registry
+            .module_cache
+            .blocking_read()
+            .keys()
+            .filter(|path| path.as_str() != module_path)
+            .cloned()
+            .collect();
+        exporters.retain(|path| {
+            registry
+                .get_exported_declarations(path)
+                .iter()
+                .any(|(exported, _)| exported == name)
+        });
+        exporters.sort();
+        for (import_path, edit) in missing_imports(source, name, exporters) {
+            fixes.push(QuickFix {
+                title: format!("Import '{}' from '{}'", name, import_path),
+                edit,
+                preferred: false,
+            });
+        }
+    } else if code == codes::MISPLACED_ELSE || code == codes::ANNOTATION_NOT_APPLICABLE {
+        if let Some(annotation) = annotation_range(source, range.clone()) {
+            fixes.push(QuickFix {
+                title: format!("Remove '@{}'", name),
+                edit: SourceEdit {
+                    range: annotation,
+                    new_text: String::new(),
+                },
+                preferred: true,
+            });
+        }
+        // `@elseif(cond, validator)` takes the same arguments as `@if`
+        if code == codes::MISPLACED_ELSE && name == "elseif" {
+            fixes.push(QuickFix {
+                title: "Convert to '@if'".to_string(),
+                edit: SourceEdit {
+                    range,
+                    new_text: "if".to_string(),
+                },
+                preferred: false,
+            });
+        }
+    }
+    fixes
+}
+
+/// Names among `in_scope` and the builtin types that look like a misspelling of `name`, closest first.
+fn similar_type_names(name: &str, in_scope: impl Iterator<Item = String>) -> Vec<String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut candidates: Vec<(usize, String)> = in_scope
+        .chain(BUILTIN_TYPES.iter().map(|t| t.name.to_string()))
+        .filter(|candidate| candidate != name)
+        .map(|candidate| (edit_distance(name, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+    candidates.dedup_by(|a, b| a.1 == b.1);
+    candidates
+        .into_iter()
+        .take(3)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Case-insensitive Levenshtein distance, a change of case alone counts as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    if a != b && a.eq_ignore_ascii_case(b) {
+        return 1;
+    }
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Edits importing `name` into the module, one for every module of `exporters`.
+/// A selective import of that module gets the name added, otherwise a new import follows the last one.
+fn missing_imports(source: &str, name: &str, exporters: Vec<String>) -> Vec<(String, SourceEdit)> {
+    let tree = SyntaxTree::parse(source);
+    let imports: Vec<_> = tree
+        .declarations()
+        .filter(|node| node.kind == SyntaxKind::Import)
+        .collect();
+    let import_path = |node: &&SyntaxNode| -> String {
+        node.children
+            .iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Token(t)
+                    if matches!(
+                        t.kind,
+                        SyntaxKind::Token(TokenVariant::Identifier | TokenVariant::Slash)
+                    ) && t.text != "export" =>
+                {
+                    Some(t.text)
+                }
+                _ => None,
+            })
+            .collect()
+    };
+
+    exporters
+        .into_iter()
+        .map(|exporter| {
+            // `import a/b { X }` gets `, Name` before its closing brace
+            let items = imports
+                .iter()
+                .filter(|node| import_path(node) == exporter)
+                .find_map(|node| {
+                    node.nodes()
+                        .find(|items| items.kind == SyntaxKind::ImportItems)
+                });
+            let (offset, new_text) = match items {
+                Some(items) => {
+                    let empty = !items
+                        .tokens()
+                        .iter()
+                        .any(|t| t.kind == SyntaxKind::Token(TokenVariant::Identifier));
+                    let close = items.range.end - 1;
+                    let before = source[..close].trim_end().len();
+                    match empty {
+                        true => (close, name.to_string()),
+                        false => (before, format!(", {}", name)),
+                    }
+                }
+                None => match imports.last() {
+                    Some(last) => (
+                        last.range.end,
+                        format!("\nimport {} {{ {} }};", exporter, name),
+                    ),
+                    None => (0, format!("import {} {{ {} }};\n\n", exporter, name)),
+                },
+            };
+            let edit = SourceEdit {
+                range: offset..offset,
+                new_text,
+            };
+            (exporter, edit)
+        })
+        .collect()
+}
+
+/// Byte range of the whole `@name(args)` whose name is at `name`, with the whitespace in front of it.
+fn annotation_range(source: &str, name: Range<usize>) -> Option<Range<usize>> {
+    let tree = SyntaxTree::parse(source);
+    let node = tree.covering_node(name.clone());
+    let i = node
+        .children
+        .iter()
+        .position(|child| child.range() == name)?;
+    let at = match node.children.get(i.checked_sub(1)?)? {
+        SyntaxElement::Token(t) if t.kind == SyntaxKind::Token(TokenVariant::At) => t,
+        _ => return None,
+    };
+
+    let start = match i.checked_sub(2).map(|j| &node.children[j]) {
+        Some(SyntaxElement::Token(t)) if t.kind == SyntaxKind::Whitespace => t.offset,
+        _ => at.offset,
+    };
+    let end = match node.children.get(i + 1) {
+        Some(SyntaxElement::Node(args)) if args.kind == SyntaxKind::Args => args.range.end,
+        _ => name.end,
+    };
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::module::test_support::{registry, temp_workspace};
+
+    /// A registry with every module of `modules` loaded from a temporary workspace.
+    fn loaded(name: &str, modules: &[(&str, &str)]) -> (XenoRegistry, PathBuf) {
+        let root = temp_workspace(name, modules);
+        let reg = registry(root.clone(), HashMap::new());
+        for (path, _) in modules {
+            reg.load_module(&[path], true, None);
+        }
+        (reg, root)
+    }
+
+    fn range_of(source: &str, text: &str) -> Range<usize> {
+        let start = source.find(text).unwrap();
+        start..start + text.len()
+    }
+
+    #[test]
+    fn unknown_types_are_corrected_or_imported() {
+        let main = "import a { Team };\ntype X = { t: Teem, u: User };\n";
+        let (registry, root) = loaded(
+            "quick-fixes-types",
+            &[
+                ("main", main),
+                ("a", "type User = string;\ntype Team = u8;\n"),
+                ("b", "type User = u8;\n"),
+            ],
+        );
+
+        let teem = range_of(main, "Teem");
+        let fixes = quick_fixes(&registry, "main", main, codes::UNKNOWN_TYPE, teem.clone());
+        assert_eq!(
+            fixes,
+            [QuickFix {
+                title: "Change to 'Team'".to_string(),
+                edit: SourceEdit {
+                    range: teem,
+                    new_text: "Team".to_string(),
+                },
+                preferred: true,
+            }]
+        );
+
+        let user = range_of(main, "User");
+        let fixes = quick_fixes(&registry, "main", main, codes::UNKNOWN_TYPE, user);
+        let edits: Vec<(&str, Range<usize>, &str)> = fixes
+            .iter()
+            .map(|f| {
+                (
+                    f.title.as_str(),
+                    f.edit.range.clone(),
+                    f.edit.new_text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            edits,
+            [
+                ("Import 'User' from 'a'", 15..15, ", User"),
+                ("Import 'User' from 'b'", 18..18, "\nimport b { User };"),
+            ]
+        );
+
+        // Ranges of an older version of the source
+        assert!(quick_fixes(&registry, "main", main, codes::UNKNOWN_TYPE, 60..80).is_empty());
+        assert!(quick_fixes(
+            &registry,
+            "main",
+            main,
+            codes::UNKNOWN_TYPE,
+            Range { start: 30, end: 20 }
+        )
+        .is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn misplaced_annotations_are_removed_with_their_arguments() {
+        let main = "type X = string @minlen(1) @elseif($a, @maxlen(2)) @uuid;\n";
+        let (registry, root) = loaded("quick-fixes-annotations", &[("main", main)]);
+
+        let elseif = range_of(main, "elseif");
+        let fixes = quick_fixes(
+            &registry,
+            "main",
+            main,
+            codes::MISPLACED_ELSE,
+            elseif.clone(),
+        );
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(fixes[0].title, "Remove '@elseif'");
+        assert_eq!(
+            &main[fixes[0].edit.range.clone()],
+            " @elseif($a, @maxlen(2))"
+        );
+        assert_eq!(fixes[1].edit.range, elseif);
+        assert_eq!(fixes[1].edit.new_text, "if");
+
+        // Without arguments only the name goes, with the space in front of it
+        let uuid = range_of(main, "uuid");
+        let fixes = quick_fixes(
+            &registry,
+            "main",
+            main,
+            codes::ANNOTATION_NOT_APPLICABLE,
+            uuid,
+        );
+        assert_eq!(&main[fixes[0].edit.range.clone()], " @uuid");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn case_changes_are_single_edits() {
+        assert_eq!(edit_distance("user", "User"), 1);
+        assert_eq!(edit_distance("Usr", "User"), 1);
+        assert_eq!(edit_distance("Role", "User"), 4);
+        assert_eq!(similar_type_names("strng", std::iter::empty()), ["string"]);
+    }
+}
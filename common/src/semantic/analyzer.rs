@@ -4,7 +4,11 @@ use std::path::PathBuf;
 
 use crate::{
     config::PluginConfigs,
-    module::ModuleData,
+    diagnostic::{codes, Severity},
+    module::{
+        types::{DeclarationInfo, FieldInfo},
+        ImportedDeclaration, ModuleData,
+    },
     parser::{AnonymType, Declaration, Expr, KeyValExpr, TypeList},
     plugins::XenoPlugin,
     semantic::{
@@ -30,9 +34,146 @@ pub struct ScopeInfo {
     pub builtin_types: HashSet<String>,
     /// All known annotation names (builtins + plugins, flat set).
     pub known_annotations: HashSet<String>,
+    /// Declarations visible in this module (own and imported), keyed by name.
+    pub declarations: HashMap<String, DeclarationInfo>,
+    /// Where each imported name comes from, keyed by the name it is visible as.
+    pub imports: HashMap<String, ImportedType>,
+    /// Declarations visible in the other modules declaring structs that member paths can reach
+    /// or generic types this module can apply, keyed by module path.
+    /// Field types and the types a generic declaration uses are named in the module declaring them.
+    pub field_scopes: HashMap<String, HashMap<String, DeclarationInfo>>,
+    /// Source of the generic types declared in the modules of `field_scopes`, keyed by module path
+    /// and name, for generators instantiating them.
+    pub generic_declarations: HashMap<String, HashMap<String, String>>,
+}
+
+/// Why a member path like `User.address.city` names no field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemberError {
+    /// The type the member at `index` is looked up in, as written, has no fields
+    NoFields { index: usize, owner: String },
+    /// The type has fields, none of them is named like the member at `index`
+    UnknownField { index: usize, owner: String },
+}
+
+/// Where an imported name comes from, `Role as UserRole` maps `UserRole` to `Role`.
+/// The declaration itself may live further down a chain of re-exports, see `ScopeInfo::declarations`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedType {
+    /// Module the type is imported from.
+    pub module_path: String,
+    /// Name the type is exported as by that module.
+    pub name: String,
 }
 
 impl ScopeInfo {
+    /// Builds the scope of a module from builtins, plugins, its own declarations and its imports.
+    pub fn new(
+        module_data: &ModuleData,
+        cache: &HashMap<String, ModuleData>,
+        plugins: &[&'static XenoPlugin<'static>],
+    ) -> Self {
+        let mut builtin_types: HashSet<String> = HashSet::new();
+        let mut known_annotations: HashSet<String> = HashSet::new();
+
+        // Builtins
+        for t in BUILTIN_TYPES {
+            builtin_types.insert(t.name.to_string());
+        }
+        for a in BUILTIN_ANNOTATIONS {
+            known_annotations.insert(a.name.to_string());
+        }
+
+        // Plugin-provided names
+        for plugin in plugins {
+            if let Some(provide) = plugin.provide_types {
+                for pc in provide() {
+                    builtin_types.insert(pc.label.to_string());
+                }
+            }
+            if let Some(provide) = plugin.provide_annotations {
+                for pc in provide() {
+                    known_annotations.insert(pc.label.to_string());
+                }
+            }
+        }
+
+        // Own declarations
+        let own_types: Vec<String> = module_data
+            .borrow_declarations()
+            .keys()
+            .map(|k| k.to_string())
+            .collect();
+
+        // Imported declarations grouped by module (skip self-imports)
+        let module_path_str = module_data.borrow_module_path().to_string();
+        let mut imported_types: HashMap<String, Vec<String>> = HashMap::new();
+        let mut imports: HashMap<String, ImportedType> = HashMap::new();
+        let mut declarations: HashMap<String, DeclarationInfo> = HashMap::new();
+        for import in module_data.borrow_imports() {
+            if import.module_path != module_path_str && cache.contains_key(&import.module_path) {
+                imported_types
+                    .entry(import.module_path.clone())
+                    .or_default();
+            }
+        }
+        for ImportedDeclaration {
+            import,
+            local,
+            name,
+            declaration: info,
+        } in module_data.imported_declarations(cache)
+        {
+            imported_types
+                .entry(import.module_path.clone())
+                .or_default()
+                .push(local.to_string());
+            // Ambiguous names are reported by the registry, the first import wins here
+            imports
+                .entry(local.to_string())
+                .or_insert_with(|| ImportedType {
+                    module_path: import.module_path.clone(),
+                    name: name.to_string(),
+                });
+            declarations
+                .entry(local.to_string())
+                .or_insert_with(|| info.clone());
+        }
+        // Own declarations shadow imported ones
+        for (name, info) in module_data.borrow_declarations() {
+            declarations.insert(name.to_string(), info.clone());
+        }
+
+        let mut field_scopes = HashMap::new();
+        let mut generic_declarations = HashMap::new();
+        let mut pending = scoped_modules(&declarations);
+        while let Some(module_path) = pending.pop() {
+            if module_path == module_path_str || field_scopes.contains_key(&module_path) {
+                continue;
+            }
+            let Some(module) = cache.get(&module_path) else {
+                continue;
+            };
+            let visible = visible_declarations(module, cache);
+            pending.extend(scoped_modules(&visible));
+            field_scopes.insert(module_path.clone(), visible);
+            generic_declarations.insert(module_path, generic_sources(module));
+        }
+
+        ScopeInfo {
+            module_path: module_path_str,
+            abs_path: module_data.borrow_abs_path().to_path_buf(),
+            own_types,
+            imported_types,
+            builtin_types,
+            known_annotations,
+            declarations,
+            imports,
+            field_scopes,
+            generic_declarations,
+        }
+    }
+
     /// Returns true if `name` is a known type (own, imported, or builtin).
     pub fn has_type(&self, name: &str) -> bool {
         self.builtin_types.contains(name)
@@ -57,6 +198,119 @@ impl ScopeInfo {
         }
         None
     }
+
+    /// Resolves a member path like `User.address.city` to the field it names,
+    /// following field types through the visible declarations.
+    pub fn resolve_member(&self, base: &str, members: &[&str]) -> Option<&FieldInfo> {
+        self.resolve_member_declaration(base, members)
+            .map(|(_, field)| field)
+    }
+
+    /// Like `resolve_member`, with the declaration the field is declared in.
+    pub fn resolve_member_declaration(
+        &self,
+        base: &str,
+        members: &[&str],
+    ) -> Option<(&DeclarationInfo, &FieldInfo)> {
+        self.resolve_member_path(base, members).ok()
+    }
+
+    /// Resolves a member path one member at a time: the base in this module, the type of
+    /// every field in the module declaring the field.
+    pub fn resolve_member_path<'a>(
+        &'a self,
+        base: &str,
+        members: &[&str],
+    ) -> Result<(&'a DeclarationInfo, &'a FieldInfo), MemberError> {
+        let mut owner_name = base;
+        let mut visible = Some(&self.declarations);
+        let mut resolved = None;
+        for (index, member) in members.iter().enumerate() {
+            let Some(owner) = visible
+                .and_then(|declarations| declarations.get(owner_name))
+                .filter(|d| d.fields.is_some())
+            else {
+                let owner = owner_name.to_string();
+                return Err(MemberError::NoFields { index, owner });
+            };
+            let Some(field) = owner.field(member) else {
+                let owner = owner_name.to_string();
+                return Err(MemberError::UnknownField { index, owner });
+            };
+            resolved = Some((owner, field));
+            owner_name = &field.type_name;
+            visible = self.visible_in(&owner.module_path);
+        }
+        resolved.ok_or(MemberError::NoFields {
+            index: 0,
+            owner: base.to_string(),
+        })
+    }
+
+    /// Declarations visible in a module, this one or one of the `field_scopes`.
+    fn visible_in(&self, module_path: &str) -> Option<&HashMap<String, DeclarationInfo>> {
+        if module_path == self.module_path {
+            Some(&self.declarations)
+        } else {
+            self.field_scopes.get(module_path)
+        }
+    }
+
+    /// Returns the type parameter names of a visible generic type, builtin or declared.
+    /// Empty for non-generic and unknown types.
+    pub fn generic_params_of(&self, name: &str) -> Vec<String> {
+        if let Some(decl) = self.declarations.get(name) {
+            return decl.generics.clone();
+        }
+        BUILTIN_TYPES
+            .iter()
+            .find(|t| t.name == name)
+            .and_then(|t| t.generic_params)
+            .map(|params| params.iter().map(|p| p.name.to_string()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Declarations visible in a module, its own ones shadowing imported ones.
+fn visible_declarations(
+    module_data: &ModuleData,
+    cache: &HashMap<String, ModuleData>,
+) -> HashMap<String, DeclarationInfo> {
+    let mut declarations = HashMap::new();
+    for imported in module_data.imported_declarations(cache) {
+        declarations
+            .entry(imported.local.to_string())
+            .or_insert_with(|| imported.declaration.clone());
+    }
+    for (name, info) in module_data.borrow_declarations() {
+        declarations.insert(name.to_string(), info.clone());
+    }
+    declarations
+}
+
+/// Modules declaring the structs and generic types among some declarations.
+fn scoped_modules(declarations: &HashMap<String, DeclarationInfo>) -> Vec<String> {
+    declarations
+        .values()
+        .filter(|d| d.fields.is_some() || !d.generics.is_empty())
+        .map(|d| d.module_path.clone())
+        .collect()
+}
+
+/// Source of the generic types a module declares, the first declaration of a name wins.
+fn generic_sources(module_data: &ModuleData) -> HashMap<String, String> {
+    let mut sources = HashMap::new();
+    let nodes = module_data.borrow_syntax_tree().declarations();
+    for (node, declaration) in nodes.zip(module_data.borrow_ast()) {
+        if let Declaration::TypeDecl { name, generics, .. } = declaration {
+            if !generics.is_empty() {
+                sources
+                    .entry(name.v.to_string())
+                    .or_insert_with(|| node.to_string());
+            }
+        }
+    }
+    sources
 }
 
 /// Trait for AST walk event listeners. All methods have default no-op
@@ -71,6 +325,11 @@ pub trait AnalyzerListener<'src> {
     fn on_before_module(&mut self, scope: &ScopeInfo) {}
     /// Called after the full AST walk completes, with scope information.
     fn on_after_module(&mut self, scope: &ScopeInfo) {}
+    /// Files written for the module, asked for after `on_after_module`.
+    /// The build cache generates the module again when one of them is missing or changed.
+    fn outputs(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
 
     fn on_before_ast(&mut self, ast: &[Declaration<'src>], errors: &mut Vec<XenoError<'src>>) {}
     fn on_after_ast(&mut self, ast: &[Declaration<'src>], errors: &mut Vec<XenoError<'src>>) {}
@@ -181,79 +440,26 @@ impl Analyzer {
     /// Analyze a module's AST with full scope from the cache.
     /// Builds known_types and known_annotations from builtins, plugins, own
     /// declarations, and imported module declarations.
+    /// Plugin listeners only run with `run_plugins`, e.g. not for modules whose output is up to date.
+    /// Returns the errors found and the files the listeners wrote.
     pub fn run<'src>(
         &self,
         ast: &[Declaration<'src>],
         module_data: &ModuleData,
-        imports: &[String],
         cache: &HashMap<String, ModuleData>,
         plugins: &[&'static XenoPlugin<'static>],
         plugin_configs: &PluginConfigs,
-    ) -> Vec<XenoError<'src>> {
-        // ── Build ScopeInfo ──
-        let mut builtin_types: HashSet<String> = HashSet::new();
-        let mut known_annotations: HashSet<String> = HashSet::new();
-
-        // Builtins
-        for t in BUILTIN_TYPES {
-            builtin_types.insert(t.name.to_string());
-        }
-        for a in BUILTIN_ANNOTATIONS {
-            known_annotations.insert(a.name.to_string());
-        }
-
-        // Plugin-provided names
-        for plugin in plugins {
-            if let Some(provide) = plugin.provide_types {
-                for pc in provide() {
-                    builtin_types.insert(pc.label.to_string());
-                }
-            }
-            if let Some(provide) = plugin.provide_annotations {
-                for pc in provide() {
-                    known_annotations.insert(pc.label.to_string());
-                }
-            }
-        }
-
-        // Own declarations
-        let own_types: Vec<String> = module_data
-            .borrow_declarations()
-            .keys()
-            .map(|k| k.to_string())
-            .collect();
-
-        // Imported declarations grouped by module (skip self-imports)
-        let module_path_str = module_data.borrow_module_path().to_string();
-        let mut imported_types: HashMap<String, Vec<String>> = HashMap::new();
-        for import in imports {
-            if import == &module_path_str {
-                continue; // skip self-import
-            }
-            if let Some(m) = cache.get(import) {
-                let names: Vec<String> = m
-                    .borrow_declarations()
-                    .keys()
-                    .map(|k| k.to_string())
-                    .collect();
-                imported_types.insert(import.clone(), names);
-            }
-        }
-
-        let scope = ScopeInfo {
-            module_path: module_path_str,
-            abs_path: module_data.borrow_abs_path().to_path_buf(),
-            own_types,
-            imported_types,
-            builtin_types,
-            known_annotations,
-        };
+        run_plugins: bool,
+    ) -> (Vec<XenoError<'src>>, Vec<PathBuf>) {
+        let scope = ScopeInfo::new(module_data, cache, plugins);
 
         // ── Create listeners ──
         let mut listeners: Vec<Box<dyn AnalyzerListener<'src>>> = Vec::new();
-        for f in &self.listener_factories {
-            let listener: Box<dyn AnalyzerListener<'src>> = f();
-            listeners.push(listener);
+        if run_plugins {
+            for f in &self.listener_factories {
+                let listener: Box<dyn AnalyzerListener<'src>> = f();
+                listeners.push(listener);
+            }
         }
 
         // Add the name validator (always present)
@@ -276,12 +482,15 @@ impl Analyzer {
 
         // Check for self-imports
         for decl in ast {
-            if let Declaration::Import { path, location } = decl {
+            if let Declaration::Import { path, location, .. } = decl {
                 let import_path = path.join("/");
                 if import_path == scope.module_path {
                     errors.push(XenoError {
                         location: (*location).clone(),
                         message: format!("Module '{}' cannot import itself", import_path),
+                        severity: Severity::Error,
+                        code: codes::SELF_IMPORT,
+                        labels: Vec::new(),
                     });
                 }
             }
@@ -294,7 +503,8 @@ impl Analyzer {
             l.on_after_module(&scope);
         }
 
-        errors
+        let outputs = listeners.iter().flat_map(|l| l.outputs()).collect();
+        (errors, outputs)
     }
 }
 
@@ -302,7 +512,7 @@ impl Analyzer {
 
 type Listeners<'src> = [Box<dyn AnalyzerListener<'src>>];
 
-fn walk_ast<'src>(
+pub(crate) fn walk_ast<'src>(
     ls: &mut Listeners<'src>,
     ast: &[Declaration<'src>],
     errors: &mut Vec<XenoError<'src>>,
@@ -453,6 +663,11 @@ fn walk_expr<'src>(ls: &mut Listeners<'src>, expr: &Expr<'src>, errors: &mut Vec
                 l.on_after_annotation(name, args, errors);
             }
         }
+        Expr::TypeApplication(_, args) => {
+            for anon_type in args {
+                walk_type(ls, anon_type, errors);
+            }
+        }
         Expr::Not(inner) => {
             walk_expr(ls, inner, errors);
         }
@@ -460,7 +675,11 @@ fn walk_expr<'src>(ls: &mut Listeners<'src>, expr: &Expr<'src>, errors: &mut Vec
             walk_expr(ls, &pair.0, errors);
             walk_expr(ls, &pair.1, errors);
         }
-        Expr::Identifier(_) | Expr::Literal(_) | Expr::Regex(_) | Expr::FieldAccess(_) => {}
+        Expr::Identifier(_)
+        | Expr::Literal(_)
+        | Expr::Regex(_)
+        | Expr::FieldAccess(_)
+        | Expr::MemberAccess(_, _) => {}
     }
     for l in ls.iter_mut() {
         l.on_after_expr(expr, errors);
@@ -484,7 +703,7 @@ impl XenoDefNode<'_> {
 
         for declaration in ast {
             match declaration {
-                Declaration::TypeDecl { name, docs, t } => {
+                Declaration::TypeDecl { name, docs, t, .. } => {
                     let node = XenoDefNode {
                         name: name.v,
                         docs: *docs,
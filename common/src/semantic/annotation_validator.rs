@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{
+    diagnostic::{codes, Severity},
     parser::{AnonymType, BinaryExpr, Declaration, Expr, Literal, NumberType, TypeList},
     semantic::{
         is_type_compatible, AnalyzerListener, ScopeInfo, XenoAnnotation, XenoParameterType,
@@ -46,11 +47,20 @@ impl AnnotationValidator {
             .find(|builtin_type| builtin_type.name == name)
     }
 
+    /// The declared type of the field a member path like `User._id` points to.
+    fn member_type(&self, base: &TokenData<'_>, members: &[&TokenData<'_>]) -> Option<String> {
+        let members: Vec<&str> = members.iter().map(|m| m.v).collect();
+        self.scope
+            .resolve_member(base.v, &members)
+            .map(|field| field.type_name.clone())
+    }
+
     fn current_types(&self) -> &[&'static XenoType] {
         self.type_stack.last().map_or(&[], Vec::as_slice)
     }
 
-    fn resolve_types(&self, exprs: &AnonymType<'_>) -> Vec<&'static XenoType> {
+    /// The builtin types behind a type expression, following the aliases of the module given to `on_before_ast`.
+    pub fn resolve_types(&self, exprs: &[Expr<'_>]) -> Vec<&'static XenoType> {
         let mut types = Vec::new();
         let mut visited_aliases = HashSet::new();
         for expr in exprs {
@@ -66,13 +76,21 @@ impl AnnotationValidator {
         visited_aliases: &mut HashSet<String>,
     ) {
         match expr {
-            Expr::Identifier(identifier) => {
+            Expr::Identifier(identifier) | Expr::TypeApplication(identifier, _) => {
                 if let Some(builtin_type) = self.find_builtin_type(identifier.v) {
                     types.push(builtin_type);
                 } else {
                     self.collect_alias_types(identifier.v, types, visited_aliases);
                 }
             }
+            Expr::MemberAccess(base, members) => {
+                if let Some(field_type) = self.member_type(base, members) {
+                    match self.find_builtin_type(&field_type) {
+                        Some(builtin_type) => types.push(builtin_type),
+                        None => self.collect_alias_types(&field_type, types, visited_aliases),
+                    }
+                }
+            }
             Expr::BinaryExpr(_, pair) => {
                 self.collect_binary_types(pair, types, visited_aliases);
             }
@@ -145,7 +163,7 @@ impl AnnotationValidator {
 
     fn collect_type_hint(&self, expr: &Expr<'_>, hints: &mut Vec<TypeHint>) {
         match expr {
-            Expr::Identifier(identifier) => {
+            Expr::Identifier(identifier) | Expr::TypeApplication(identifier, _) => {
                 if let Some(builtin_type) = self.find_builtin_type(identifier.v) {
                     hints.push(TypeHint::Builtin(builtin_type));
                 } else {
@@ -158,6 +176,14 @@ impl AnnotationValidator {
                 Literal::Boolean(_, _) => self.push_builtin_hint("bool", hints),
             },
             Expr::Regex(_) => self.push_builtin_hint("regex", hints),
+            Expr::MemberAccess(base, members) => {
+                if let Some(field_type) = self.member_type(base, members) {
+                    match self.find_builtin_type(&field_type) {
+                        Some(builtin_type) => hints.push(TypeHint::Builtin(builtin_type)),
+                        None => hints.push(TypeHint::Alias(field_type)),
+                    }
+                }
+            }
             Expr::BinaryExpr(_, pair) => {
                 self.collect_type_hint(&pair.0, hints);
                 self.collect_type_hint(&pair.1, hints);
@@ -206,6 +232,9 @@ impl AnnotationValidator {
                         candidate.name,
                         Self::format_types(applicable_to)
                     ),
+                    severity: Severity::Error,
+                    code: codes::ANNOTATION_NOT_APPLICABLE,
+                    labels: Vec::new(),
                 });
             }
         }
@@ -228,6 +257,9 @@ impl AnnotationValidator {
                     expected_params.len(),
                     args.len()
                 ),
+                severity: Severity::Error,
+                code: codes::ANNOTATION_ARGUMENT_COUNT,
+                labels: Vec::new(),
             });
             return;
         }
@@ -243,6 +275,9 @@ impl AnnotationValidator {
                         Self::param_type_name(param.param_type),
                         Self::arg_type_name(arg)
                     ),
+                    severity: Severity::Error,
+                    code: codes::ANNOTATION_ARGUMENT_TYPE,
+                    labels: Vec::new(),
                 });
             }
         }
@@ -314,6 +349,8 @@ impl AnnotationValidator {
             | Expr::Regex(token)
             | Expr::Annotation(token, _)
             | Expr::Array(token)
+            | Expr::TypeApplication(token, _)
+            | Expr::MemberAccess(token, _)
             | Expr::FieldAccess(token) => (*token).clone(),
 
             Expr::Not(inner) => Self::expr_location(inner),
@@ -340,6 +377,8 @@ impl AnnotationValidator {
             [Expr::Regex(_)] => "regex literal",
             [Expr::FieldAccess(_)] => "field reference",
             [Expr::Identifier(_)] => "identifier",
+            [Expr::TypeApplication(_, _)] => "generic type",
+            [Expr::MemberAccess(_, _)] => "member type",
             [Expr::Annotation(_, _)] => "annotation",
             [Expr::List(_)] => "list",
             [Expr::Set(_)] => "set",
@@ -440,6 +479,10 @@ mod tests {
                 .iter()
                 .map(|annotation| annotation.name.to_string())
                 .collect(),
+            declarations: HashMap::new(),
+            imports: HashMap::new(),
+            field_scopes: HashMap::new(),
+            generic_declarations: HashMap::new(),
         }
     }
 
@@ -474,6 +517,7 @@ mod tests {
             Declaration::TypeDecl {
                 docs: None,
                 name: &a_name,
+                generics: Vec::new(),
                 t: vec![Expr::Literal(Literal::String(
                     "literal".to_string(),
                     &string_literal,
@@ -482,6 +526,7 @@ mod tests {
             Declaration::TypeDecl {
                 docs: None,
                 name: &b_name,
+                generics: Vec::new(),
                 t: b_type.clone(),
             },
         ];
@@ -529,11 +574,13 @@ mod tests {
             Declaration::TypeDecl {
                 docs: None,
                 name: &a_name,
+                generics: Vec::new(),
                 t: vec![Expr::Identifier(&u8_type)],
             },
             Declaration::TypeDecl {
                 docs: None,
                 name: &b_name,
+                generics: Vec::new(),
                 t: b_type.clone(),
             },
         ];
@@ -7,7 +7,7 @@ pub enum XenoAnnotationKind {
     Meta,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum XenoParameterType {
     None,
     NumberLiteral,
@@ -23,6 +23,7 @@ pub enum XenoParameterType {
     List(&'static [XenoParameterType]),
 }
 
+#[derive(Debug)]
 pub struct XenoParam {
     pub name: &'static str,
     pub param_type: XenoParameterType,
@@ -1,4 +1,5 @@
 use crate::{
+    diagnostic::{codes, Severity},
     parser::{AnonymType, Expr, TypeList},
     semantic::AnalyzerListener,
     TokenData, XenoError,
@@ -62,6 +63,9 @@ impl<'src> AnalyzerListener<'src> for IfChainValidator {
                     errors.push(XenoError {
                         location: (*name).clone(),
                         message: "'@elseif' must follow an '@if' or another '@elseif'.".to_string(),
+                        severity: Severity::Error,
+                        code: codes::MISPLACED_ELSE,
+                        labels: Vec::new(),
                     });
                     self.set(IfChainState::None);
                 }
@@ -72,6 +76,9 @@ impl<'src> AnalyzerListener<'src> for IfChainValidator {
                     errors.push(XenoError {
                         location: (*name).clone(),
                         message: "'@else' must follow an '@if' or '@elseif'.".to_string(),
+                        severity: Severity::Error,
+                        code: codes::MISPLACED_ELSE,
+                        labels: Vec::new(),
                     });
                     self.set(IfChainState::None);
                 }
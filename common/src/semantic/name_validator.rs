@@ -1,31 +1,200 @@
 use crate::{
-    parser::{Expr, TypeList},
-    semantic::{AnalyzerListener, ScopeInfo},
+    diagnostic::{codes, Severity},
+    parser::{Declaration, Expr, ImportItem, TypeList},
+    semantic::{AnalyzerListener, MemberError, ScopeInfo},
     TokenData, XenoError,
 };
 
 /// Reports unknown type identifiers and unknown annotation names.
+/// Type parameters of the declaration being walked count as known types.
 pub struct NameValidator {
     scope: ScopeInfo,
+    type_params: Vec<String>,
 }
 
 impl NameValidator {
     pub fn new(scope: &ScopeInfo) -> Self {
         Self {
             scope: scope.clone(),
+            type_params: Vec::new(),
+        }
+    }
+
+    fn is_type_param(&self, name: &str) -> bool {
+        self.type_params.iter().any(|p| p == name)
+    }
+
+    fn validate_application<'src>(
+        &self,
+        id: &TokenData<'src>,
+        args: &TypeList<'src>,
+        errors: &mut Vec<XenoError<'src>>,
+    ) {
+        if self.is_type_param(id.v) {
+            errors.push(XenoError {
+                location: id.clone(),
+                message: format!("Type parameter '{}' cannot take type arguments", id.v),
+                severity: Severity::Error,
+                code: codes::TYPE_ARGUMENTS,
+                labels: Vec::new(),
+            });
+            return;
+        }
+        if !self.scope.has_type(id.v) {
+            errors.push(XenoError {
+                location: id.clone(),
+                message: format!("Unknown type '{}'", id.v),
+                severity: Severity::Error,
+                code: codes::UNKNOWN_TYPE,
+                labels: Vec::new(),
+            });
+            return;
+        }
+
+        let params = self.scope.generic_params_of(id.v);
+        if params.is_empty() {
+            errors.push(XenoError {
+                location: id.clone(),
+                message: format!("Type '{}' is not generic", id.v),
+                severity: Severity::Error,
+                code: codes::TYPE_ARGUMENTS,
+                labels: Vec::new(),
+            });
+        } else if params.len() != args.len() {
+            errors.push(XenoError {
+                location: id.clone(),
+                message: format!(
+                    "Type '{}<{}>' expects {} type argument(s), got {}",
+                    id.v,
+                    params.join(", "),
+                    params.len(),
+                    args.len()
+                ),
+                severity: Severity::Error,
+                code: codes::TYPE_ARGUMENTS,
+                labels: Vec::new(),
+            });
+        }
+    }
+
+    /// Every name in `import a/b { X, Y as Z }` has to be declared by `a/b`.
+    /// Modules that failed to load are reported elsewhere and skipped here.
+    fn validate_import_items<'src>(
+        &self,
+        module_path: &str,
+        items: &[ImportItem<'src>],
+        errors: &mut Vec<XenoError<'src>>,
+    ) {
+        let Some(visible) = self.scope.imported_types.get(module_path) else {
+            return;
+        };
+        for item in items {
+            let local = item.alias.unwrap_or(item.name).v;
+            if !visible.iter().any(|n| n == local) {
+                errors.push(XenoError {
+                    location: item.name.clone(),
+                    message: format!(
+                        "Module '{}' has no declaration named '{}'",
+                        module_path, item.name.v
+                    ),
+                    severity: Severity::Error,
+                    code: codes::UNKNOWN_IMPORTED_NAME,
+                    labels: Vec::new(),
+                });
+            }
+        }
+    }
+
+    fn validate_member_access<'src>(
+        &self,
+        base: &TokenData<'src>,
+        members: &[&TokenData<'src>],
+        errors: &mut Vec<XenoError<'src>>,
+    ) {
+        if !self.is_type_param(base.v) && !self.scope.has_type(base.v) {
+            errors.push(XenoError {
+                location: base.clone(),
+                message: format!("Unknown type '{}'", base.v),
+                severity: Severity::Error,
+                code: codes::UNKNOWN_TYPE,
+                labels: Vec::new(),
+            });
+            return;
+        }
+
+        let path: Vec<&str> = members.iter().map(|m| m.v).collect();
+        let (index, message) = match self.scope.resolve_member_path(base.v, &path) {
+            Ok(_) => return,
+            Err(MemberError::NoFields { index, owner }) => {
+                (index, format!("Type '{}' has no fields", owner))
+            }
+            Err(MemberError::UnknownField { index, owner }) => (
+                index,
+                format!("Unknown field '{}' on type '{}'", path[index], owner),
+            ),
+        };
+        if let Some(member) = members.get(index) {
+            errors.push(XenoError {
+                location: (*member).clone(),
+                message,
+                severity: Severity::Error,
+                code: codes::UNKNOWN_FIELD,
+                labels: Vec::new(),
+            });
         }
     }
 }
 
 impl<'src> AnalyzerListener<'src> for NameValidator {
+    fn on_before_decl(&mut self, decl: &Declaration<'src>, errors: &mut Vec<XenoError<'src>>) {
+        if let Declaration::Import {
+            path,
+            items: Some(items),
+            ..
+        } = decl
+        {
+            self.validate_import_items(&path.join("/"), items, errors);
+        }
+        if let Declaration::TypeDecl { generics, .. } = decl {
+            self.type_params.clear();
+            for (i, param) in generics.iter().enumerate() {
+                if self.is_type_param(param.v) {
+                    let first = generics[..i].iter().find(|p| p.v == param.v);
+                    errors.push(XenoError {
+                        location: (*param).clone(),
+                        message: format!("Duplicate type parameter '{}'", param.v),
+                        severity: Severity::Error,
+                        code: codes::DUPLICATE_TYPE_PARAMETER,
+                        labels: first
+                            .map(|p| ((*p).clone(), "first declared here".to_string()))
+                            .into_iter()
+                            .collect(),
+                    });
+                    continue;
+                }
+                self.type_params.push(param.v.to_string());
+            }
+        }
+    }
+
+    fn on_after_decl(&mut self, _decl: &Declaration<'src>, _errors: &mut Vec<XenoError<'src>>) {
+        self.type_params.clear();
+    }
+
     fn on_before_expr(&mut self, expr: &Expr<'src>, errors: &mut Vec<XenoError<'src>>) {
-        if let Expr::Identifier(id) = expr {
-            if !self.scope.has_type(id.v) {
+        match expr {
+            Expr::Identifier(id) if !self.is_type_param(id.v) && !self.scope.has_type(id.v) => {
                 errors.push(XenoError {
                     location: (*id).clone(),
                     message: format!("Unknown type '{}'", id.v),
+                    severity: Severity::Error,
+                    code: codes::UNKNOWN_TYPE,
+                    labels: Vec::new(),
                 });
             }
+            Expr::TypeApplication(id, args) => self.validate_application(id, args, errors),
+            Expr::MemberAccess(base, members) => self.validate_member_access(base, members, errors),
+            _ => {}
         }
     }
 
@@ -39,7 +208,167 @@ impl<'src> AnalyzerListener<'src> for NameValidator {
             errors.push(XenoError {
                 location: (*name).clone(),
                 message: format!("Unknown annotation '@{}'", name.v),
+                severity: Severity::Error,
+                code: codes::UNKNOWN_ANNOTATION,
+                labels: Vec::new(),
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        path::PathBuf,
+    };
+
+    use super::*;
+    use crate::{
+        lexer::Lexer,
+        module::types::{DeclarationInfo, FieldInfo},
+        parser::Parser,
+        semantic::{analyzer::walk_ast, BUILTIN_TYPES},
+    };
+
+    fn scope() -> ScopeInfo {
+        ScopeInfo {
+            module_path: "test".to_string(),
+            abs_path: PathBuf::new(),
+            own_types: vec!["Page".to_string(), "User".to_string()],
+            imported_types: HashMap::new(),
+            builtin_types: BUILTIN_TYPES.iter().map(|t| t.name.to_string()).collect(),
+            known_annotations: HashSet::new(),
+            declarations: HashMap::new(),
+            imports: HashMap::new(),
+            field_scopes: HashMap::new(),
+            generic_declarations: HashMap::new(),
+        }
+    }
+
+    fn validate(src: &str) -> Vec<String> {
+        let (tokens, _) = Lexer::tokenize(src);
+        let (ast, parse_errors) = Parser::parse(&tokens);
+        assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+
+        let mut scope = scope();
+        scope.declarations.insert(
+            "Page".to_string(),
+            DeclarationInfo {
+                name: "Page".to_string(),
+                module_path: "test".to_string(),
+                abs_path: PathBuf::new(),
+                docs: None,
+                line: 0,
+                column: 0,
+                name_len: 4,
+                generics: vec!["T".to_string()],
+                fields: None,
+            },
+        );
+        scope.declarations.insert(
+            "User".to_string(),
+            DeclarationInfo {
+                name: "User".to_string(),
+                module_path: "test".to_string(),
+                abs_path: PathBuf::new(),
+                docs: None,
+                line: 1,
+                column: 0,
+                name_len: 4,
+                generics: Vec::new(),
+                fields: Some(vec![FieldInfo {
+                    name: "_id".to_string(),
+                    type_name: "uuid".to_string(),
+                    line: 1,
+                    column: 14,
+                }]),
+            },
+        );
+        let mut listeners: Vec<Box<dyn AnalyzerListener>> =
+            vec![Box::new(NameValidator::new(&scope))];
+        let mut errors = Vec::new();
+        walk_ast(&mut listeners, &ast, &mut errors);
+        errors.into_iter().map(|e| e.message).collect()
+    }
+
+    #[test]
+    fn type_params_are_in_scope_of_their_declaration() {
+        assert!(validate("type Page<T> = { items: [T], total: u32 };").is_empty());
+        assert_eq!(
+            validate("type Other = T;"),
+            vec!["Unknown type 'T'".to_string()]
+        );
+    }
+
+    #[test]
+    fn type_application_checks_arity() {
+        assert!(validate("type A = Page<User>;").is_empty());
+        assert!(validate("type A = dict<string, User>;").is_empty());
+        assert_eq!(
+            validate("type A = Page<User, User>;"),
+            vec!["Type 'Page<T>' expects 1 type argument(s), got 2".to_string()]
+        );
+        assert_eq!(
+            validate("type A = User<string>;"),
+            vec!["Type 'User' is not generic".to_string()]
+        );
+    }
+
+    #[test]
+    fn duplicate_type_params_are_reported() {
+        assert_eq!(
+            validate("type P<T, T> = T;"),
+            vec!["Duplicate type parameter 'T'".to_string()]
+        );
+
+        let (tokens, _) = Lexer::tokenize("type P<T, T> = T;");
+        let (ast, _) = Parser::parse(&tokens);
+        let mut listeners: Vec<Box<dyn AnalyzerListener>> =
+            vec![Box::new(NameValidator::new(&scope()))];
+        let mut errors = Vec::new();
+        walk_ast(&mut listeners, &ast, &mut errors);
+        assert_eq!(errors[0].code, codes::DUPLICATE_TYPE_PARAMETER);
+        assert_eq!(errors[0].labels[0].0.c, 7);
+    }
+
+    #[test]
+    fn member_access_resolves_fields() {
+        assert!(validate("type A = { id: User._id };").is_empty());
+        assert_eq!(
+            validate("type A = User.name;"),
+            vec!["Unknown field 'name' on type 'User'".to_string()]
+        );
+        assert_eq!(
+            validate("type A = User._id.x;"),
+            vec!["Type 'uuid' has no fields".to_string()]
+        );
+        assert_eq!(
+            validate("type A = Nobody._id;"),
+            vec!["Unknown type 'Nobody'".to_string()]
+        );
+    }
+
+    #[test]
+    fn selective_imports_must_name_declared_types() {
+        let mut scope = scope();
+        scope
+            .imported_types
+            .insert("api/user".to_string(), vec!["UserRole".to_string()]);
+
+        let (tokens, _) = Lexer::tokenize("import api/user { Role as UserRole, Missing };");
+        let (ast, parse_errors) = Parser::parse(&tokens);
+        assert!(parse_errors.is_empty(), "{:?}", parse_errors);
+
+        let mut listeners: Vec<Box<dyn AnalyzerListener>> =
+            vec![Box::new(NameValidator::new(&scope))];
+        let mut errors = Vec::new();
+        walk_ast(&mut listeners, &ast, &mut errors);
+
+        let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+        assert_eq!(
+            messages,
+            vec!["Module 'api/user' has no declaration named 'Missing'".to_string()]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::highlight::{Highlight, HighlightKind};
+
+/**
+A highlight on a single line, positioned relative to the token before it as semantic tokens are sent to editors.
+Lengths and characters count UTF-16 code units.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub delta_line: u32,
+    /// From the start of the previous token when it's on the same line, from the start of the line otherwise
+    pub delta_start: u32,
+    pub length: u32,
+    pub kind: HighlightKind,
+    /// Bits of [`Highlight::declaration`], [`Highlight::builtin`], documentation, [`Highlight::imported`]
+    /// and [`Highlight::plugin`], from the lowest
+    pub modifiers: u32,
+}
+
+/// Tokens replacing `delete_count` tokens from the `start`th one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticTokensEdit {
+    pub start: usize,
+    pub delete_count: usize,
+    pub tokens: Vec<SemanticToken>,
+}
+
+/// Encodes the highlights of `source`, in source order, relative to each other.
+/// Multi-line highlights, like documentation comments, are split into one token per line.
+pub fn encode(source: &str, highlights: &[Highlight]) -> Vec<SemanticToken> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let utf16_len = |text: &str| text.encode_utf16().count() as u32;
+
+    let mut tokens = Vec::new();
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for h in highlights {
+        let modifiers = [
+            h.declaration,
+            h.builtin,
+            h.kind == HighlightKind::Documentation,
+            h.imported,
+            h.plugin,
+        ]
+        .iter()
+        .enumerate()
+        .filter(|(_, set)| **set)
+        .fold(0, |bits, (i, _)| bits | 1 << i);
+
+        let mut start = h.range.start;
+        while start < h.range.end {
+            let line = line_starts.partition_point(|&s| s <= start) - 1;
+            let line_end = line_starts
+                .get(line + 1)
+                .map_or(source.len(), |&next| next - 1);
+            let end = h.range.end.min(line_end);
+            if start == end {
+                start = line_end + 1;
+                continue;
+            }
+            let character = utf16_len(&source[line_starts[line]..start]);
+
+            let line = line as u32;
+            tokens.push(SemanticToken {
+                delta_line: line - previous_line,
+                delta_start: match line == previous_line {
+                    true => character - previous_start,
+                    false => character,
+                },
+                length: utf16_len(&source[start..end]),
+                kind: h.kind,
+                modifiers,
+            });
+            (previous_line, previous_start) = (line, character);
+            start = line_end + 1;
+        }
+    }
+    tokens
+}
+
+/// The single edit turning `old` into `new`, everything between their common prefix and suffix is replaced.
+pub fn edit(old: &[SemanticToken], new: &[SemanticToken]) -> SemanticTokensEdit {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    SemanticTokensEdit {
+        start: prefix,
+        delete_count: old.len() - prefix - suffix,
+        tokens: new[prefix..new.len() - suffix].to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::cst::SyntaxTree;
+    use crate::highlight::highlight;
+    use crate::semantic::{ScopeInfo, BUILTIN_TYPES};
+
+    fn highlight_at(source: &str, text: &str, kind: HighlightKind) -> Highlight {
+        let start = source.find(text).unwrap();
+        Highlight {
+            range: start..start + text.len(),
+            kind,
+            declaration: false,
+            builtin: false,
+            imported: false,
+            plugin: false,
+        }
+    }
+
+    /// `(delta_line, delta_start, length)` of each token.
+    fn positions(tokens: &[SemanticToken]) -> Vec<(u32, u32, u32)> {
+        tokens
+            .iter()
+            .map(|t| (t.delta_line, t.delta_start, t.length))
+            .collect()
+    }
+
+    /// Tokens of a module declaring `A`, `B` and `C`.
+    fn tokens_of(source: &str) -> Vec<SemanticToken> {
+        let scope = ScopeInfo {
+            module_path: "test".to_string(),
+            abs_path: PathBuf::new(),
+            own_types: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            imported_types: HashMap::new(),
+            builtin_types: BUILTIN_TYPES.iter().map(|t| t.name.to_string()).collect(),
+            known_annotations: HashSet::new(),
+            declarations: HashMap::new(),
+            imports: HashMap::new(),
+            field_scopes: HashMap::new(),
+            generic_declarations: HashMap::new(),
+        };
+        encode(source, &highlight(&SyntaxTree::parse(source), &scope))
+    }
+
+    #[test]
+    fn positions_and_lengths_count_utf16_code_units() {
+        let source = "type Café = { 😀: Café }";
+        let last = source.rfind("Café").unwrap();
+        let highlights = [
+            highlight_at(source, "Café", HighlightKind::Type),
+            highlight_at(source, "😀", HighlightKind::Field),
+            Highlight {
+                range: last..last + "Café".len(),
+                ..highlight_at(source, "Café", HighlightKind::Type)
+            },
+        ];
+
+        assert_eq!(
+            positions(&encode(source, &highlights)),
+            [(0, 5, 4), (0, 9, 2), (0, 4, 4)]
+        );
+    }
+
+    #[test]
+    fn multi_line_highlights_get_a_token_per_line() {
+        let source = "type A = int\n  /** é\n\n  done */ type B = A";
+        let mut documentation =
+            highlight_at(source, "/** é\n\n  done */", HighlightKind::Documentation);
+        documentation.declaration = true;
+        let highlights = [
+            highlight_at(source, "A", HighlightKind::Type),
+            documentation,
+            highlight_at(source, "B", HighlightKind::Type),
+        ];
+        let tokens = encode(source, &highlights);
+
+        // The empty line of the comment has no token
+        assert_eq!(
+            positions(&tokens),
+            [(0, 5, 1), (1, 2, 5), (2, 0, 9), (0, 15, 1)]
+        );
+        assert_eq!(tokens[1].modifiers, 0b101);
+        assert_eq!(tokens[2].modifiers, 0b101);
+    }
+
+    #[test]
+    fn edits_replace_only_the_tokens_that_changed() {
+        let apply = |old: &[SemanticToken], edit: &SemanticTokensEdit| {
+            let mut tokens = old.to_vec();
+            tokens.splice(
+                edit.start..edit.start + edit.delete_count,
+                edit.tokens.iter().copied(),
+            );
+            tokens
+        };
+        let before = tokens_of("type A = int\ntype B = { x: A }\ntype C = A");
+        let inserted = tokens_of("type A = int\ntype B = { x: A, y: A }\ntype C = A");
+        let removed = tokens_of("type A = int\ntype B = { y: A }\ntype C = A");
+
+        let insertion = edit(&before, &inserted);
+        assert_eq!(apply(&before, &insertion), inserted);
+        assert_eq!(insertion.start, 4);
+        assert_eq!(insertion.delete_count, 0);
+        assert_eq!(
+            insertion.tokens.iter().map(|t| t.kind).collect::<Vec<_>>(),
+            [HighlightKind::Field, HighlightKind::Type]
+        );
+
+        let removal = edit(&inserted, &removed);
+        assert_eq!(apply(&inserted, &removal), removed);
+        assert_eq!(removal.start, 4);
+        assert_eq!(removal.delete_count, 2);
+        assert!(removal.tokens.is_empty());
+
+        assert_eq!(edit(&removed, &removed).delete_count, 0);
+    }
+}
@@ -1,5 +1,3 @@
-use std::hash::{DefaultHasher, Hash, Hasher};
-
 use crate::TokenData;
 
 /// Just slices the value of the token to remove the comment boundries '/**' and '*/',
@@ -9,8 +7,25 @@ pub fn extract_documentation<'src>(token: &TokenData<'src>) -> &'src str {
     &token.v[3..len - 2].trim()
 }
 
-pub fn calculate_hash<T: Hash>(t: &T) -> u64 {
-    let mut s = DefaultHasher::new();
-    t.hash(&mut s);
-    s.finish()
+/// 64-bit FNV-1a hash of some bytes. Unlike `DefaultHasher` it's the same on every platform
+/// and Rust release, so it can be stored on disk.
+pub fn calculate_hash(data: impl AsRef<[u8]>) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    data.as_ref().iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_are_stable() {
+        assert_eq!(calculate_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(calculate_hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(calculate_hash("foobar"), 0x8594_4171_f739_67e8);
+    }
 }
@@ -1,26 +1,72 @@
-use crate::formatter::format_xenomorph;
 use std::collections::HashMap;
+use std::sync::Mutex;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 use xenomorph_common::{
-    lexer::{Token, TokenVariant},
+    config::Config,
+    diagnostic::{ErrorCode, Severity},
+    fields::{FieldName, FieldNames},
+    formatter::{format_xenomorph, format_xenomorph_range},
+    highlight::{highlight, HighlightKind},
+    inlay_hints::{inlay_hints, InlayHintKind as HintKind},
+    lexer::{Lexer, Token, TokenVariant},
     module::{
-        types::{DeclarationInfo, ErrorPhase},
+        types::{DeclarationInfo, FieldInfo, FieldReferences, ModuleError, ModulePath, TypeUse},
         XenoRegistry,
     },
     parser::Declaration,
-    TokenData,
+    plugins::{PluginCompletion, XenoPlugin},
+    quick_fixes,
+    semantic::{ScopeInfo, BUILTIN_ANNOTATIONS},
+    semantic_tokens, TokenData,
 };
+use xenomorph_lsp_common::text::{apply_change, byte_offset, offset_position};
 use xenomorph_lsp_common::types::{
-    create_completion_item, BUILTIN_ANNOTATION_COMPLETIONS, BUILTIN_TYPE_COMPLETIONS,
+    annotation_call_at, create_completion_item, create_signature_information,
+    format_annotation_documentation, format_annotation_signature, BUILTIN_ANNOTATION_COMPLETIONS,
+    BUILTIN_TYPE_COMPLETIONS,
 };
 
-mod formatter;
-
 struct Backend {
     client: Client,
     registry: XenoRegistry,
+    /// Text of the open documents, edited in place by incremental changes
+    documents: Mutex<HashMap<Url, String>>,
+    /// Last semantic tokens sent for each document with their result id, deltas are computed against them
+    semantic_tokens: Mutex<HashMap<Url, (u64, Vec<semantic_tokens::SemanticToken>)>>,
+}
+
+/// Indexed by `Backend::semantic_token_type`.
+const SEMANTIC_TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::TYPE,
+    SemanticTokenType::TYPE_PARAMETER,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::ENUM_MEMBER,
+    SemanticTokenType::DECORATOR,
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::REGEXP,
+    SemanticTokenType::COMMENT,
+];
+/// Bit `i` of a token's modifiers stands for the `i`th of these.
+const SEMANTIC_TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+    SemanticTokenModifier::DOCUMENTATION,
+    SemanticTokenModifier::new("imported"),
+    SemanticTokenModifier::new("plugin"),
+];
+
+/// A struct field picked in the editor, see `Backend::field_target`.
+struct FieldTarget {
+    /// The module declaring the field
+    module_path: ModulePath,
+    /// Position of the field's name where it's declared
+    line: u32,
+    column: u32,
+    /// The name under the cursor, the field's name or a reference to it
+    name: String,
+    range: Range,
 }
 
 trait EditorPosition {
@@ -83,9 +129,9 @@ impl Backend {
         self.registry
             .get_all_declarations_in_scope(module_path)
             .into_iter()
-            .map(|info| {
+            .map(|(local_name, info)| {
                 let mut item = create_completion_item(
-                    &info.name,
+                    &local_name,
                     info.docs.as_deref(),
                     CompletionItemKind::CLASS,
                 );
@@ -101,6 +147,21 @@ impl Backend {
             .collect()
     }
 
+    /// Returns completion items for the declarations of an imported module,
+    /// used inside the braces of `import a/b { ... }`.
+    fn get_import_item_completions(&self, import_path: &str) -> Vec<CompletionItem> {
+        let mut items: Vec<CompletionItem> = self
+            .registry
+            .get_exported_declarations(import_path)
+            .iter()
+            .map(|(name, info)| {
+                create_completion_item(name, info.docs.as_deref(), CompletionItemKind::CLASS)
+            })
+            .collect();
+        items.sort_by(|a, b| a.label.cmp(&b.label));
+        items
+    }
+
     /// Returns completion items for import path suggestions.
     fn get_import_completions(&self, path_so_far: &str) -> Vec<CompletionItem> {
         self.registry
@@ -168,6 +229,24 @@ impl Backend {
         Some(segments.join("/"))
     }
 
+    /// If the token is inside the braces of a selective import, returns the imported module path.
+    fn import_items_path(tokens: &[Token], current_token: &Token) -> Option<String> {
+        let idx = tokens.iter().position(|t| {
+            t.1.l == current_token.1.l && t.1.c == current_token.1.c && t.0 == current_token.0
+        })?;
+
+        // Walk backward over `X, Y as Z` until the opening brace
+        let mut i = idx;
+        loop {
+            match tokens[i].0 {
+                TokenVariant::Identifier | TokenVariant::Comma => {}
+                TokenVariant::LCurly => return Self::collect_import_path(tokens, &tokens[i]),
+                _ => return None,
+            }
+            i = i.checked_sub(1)?;
+        }
+    }
+
     // ── Token helpers ───────────────────────────────────────────────
 
     fn find_token_at_position<'a>(
@@ -196,16 +275,44 @@ impl Backend {
     // ── Document validation ─────────────────────────────────────────
 
     /// Reloads the module in the registry from the given source text,
-    /// then publishes diagnostics.
+    /// then publishes its diagnostics and those of every module importing it.
     async fn validate_document(&self, uri: &Url, source: String) {
         let file_path = match uri.to_file_path() {
             Ok(p) => p,
             Err(_) => return,
         };
+        let module_path = self.registry.abs_path_to_module_path(&file_path);
 
         let errors = self.registry.load_module_from_source(&file_path, source);
+        // Errors of modules loaded along the way belong to their own files
+        let own_errors: Vec<ModuleError> = errors
+            .into_iter()
+            .filter(|e| module_path.as_ref().is_none_or(|mp| &e.module_path == mp))
+            .collect();
+        self.client
+            .publish_diagnostics(uri.clone(), Self::to_diagnostics(&own_errors), None)
+            .await;
+
+        // Importers were analyzed again against the new source
+        let Some(module_path) = module_path else {
+            return;
+        };
+        for dependent in self.registry.dependents_of(&[module_path]) {
+            let abs_path = self
+                .registry
+                .with_module(&dependent, |_, _, module| module.borrow_abs_path().clone());
+            let Some(dependent_uri) = abs_path.and_then(|p| Url::from_file_path(p).ok()) else {
+                continue;
+            };
+            let errors = self.registry.get_all_errors_for(&dependent);
+            self.client
+                .publish_diagnostics(dependent_uri, Self::to_diagnostics(&errors), None)
+                .await;
+        }
+    }
 
-        let diagnostics: Vec<Diagnostic> = errors
+    fn to_diagnostics(errors: &[ModuleError]) -> Vec<Diagnostic> {
+        errors
             .iter()
             .filter_map(|err| {
                 let (line, col, len) = err.location?;
@@ -220,22 +327,43 @@ impl Backend {
                             character: col + len,
                         },
                     },
-                    severity: Some(match err.phase {
-                        ErrorPhase::Lexer | ErrorPhase::Parser | ErrorPhase::Module => {
-                            DiagnosticSeverity::ERROR
-                        }
-                        ErrorPhase::Analyzer => DiagnosticSeverity::ERROR,
+                    severity: Some(match err.severity {
+                        Severity::Error => DiagnosticSeverity::ERROR,
+                        Severity::Warning => DiagnosticSeverity::WARNING,
+                        Severity::Info => DiagnosticSeverity::INFORMATION,
+                        Severity::Hint => DiagnosticSeverity::HINT,
                     }),
+                    code: Some(NumberOrString::String(err.code.to_string())),
                     message: err.message.clone(),
                     source: Some("xenomorph".to_string()),
+                    related_information: (!err.related.is_empty()).then(|| {
+                        err.related
+                            .iter()
+                            .filter_map(|r| {
+                                let (line, col, len) = r.location;
+                                Some(DiagnosticRelatedInformation {
+                                    location: Location {
+                                        uri: Url::from_file_path(&r.abs_path).ok()?,
+                                        range: Range {
+                                            start: Position {
+                                                line,
+                                                character: col,
+                                            },
+                                            end: Position {
+                                                line,
+                                                character: col + len,
+                                            },
+                                        },
+                                    },
+                                    message: r.message.clone(),
+                                })
+                            })
+                            .collect()
+                    }),
                     ..Default::default()
                 })
             })
-            .collect();
-
-        self.client
-            .publish_diagnostics(uri.clone(), diagnostics, None)
-            .await;
+            .collect()
     }
 
     // ── Completions ─────────────────────────────────────────────────
@@ -288,6 +416,11 @@ impl Backend {
                 let _ = client.log_message(MessageType::INFO, msg).await;
             });
 
+            if let Some(import_path) = Self::import_items_path(tokens, current_token) {
+                items.extend(self.get_import_item_completions(&import_path));
+                return items;
+            }
+
             match current_token.0 {
                 TokenVariant::At => {
                     items.extend(self.get_builtin_annotations());
@@ -389,13 +522,21 @@ impl Backend {
 
     // ── Hover ───────────────────────────────────────────────────────
 
-    fn get_hover_for_location(
+    fn get_hover_for_location<'a>(
         &self,
-        tokens: &[Token],
-        ast: &[Declaration],
+        tokens: &'a [Token<'a>],
+        ast: &'a [Declaration<'a>],
         position: Position,
         module_path: Option<&str>,
+        scope: Option<&ScopeInfo>,
     ) -> Option<Hover> {
+        if let Some(name) = Self::annotation_name_at(tokens, position) {
+            return self.annotation_hover(name);
+        }
+        if let Some(field) = Self::field_name_at(tokens, ast, position) {
+            return Self::field_hover(field, scope);
+        }
+
         let token = Self::find_token_at_position(tokens, position)?;
 
         if token.0 != TokenVariant::Identifier {
@@ -463,8 +604,501 @@ impl Backend {
         })
     }
 
+    // ── Fields and annotations ──────────────────────────────────────
+
+    /// The name of the annotation at the position, on its name or its `@`.
+    fn annotation_name_at<'a>(
+        tokens: &'a [Token<'a>],
+        position: Position,
+    ) -> Option<&'a TokenData<'a>> {
+        let token = Self::find_token_at_position(tokens, position)?;
+        let i = tokens.iter().position(|t| std::ptr::eq(t, token))?;
+        match token.0 {
+            TokenVariant::At => tokens
+                .get(i + 1)
+                .filter(|t| t.0 == TokenVariant::Identifier)
+                .map(|t| &t.1),
+            TokenVariant::Identifier if i > 0 && tokens[i - 1].0 == TokenVariant::At => {
+                Some(&token.1)
+            }
+            _ => None,
+        }
+    }
+
+    /// The field name at the position, the `$` of a reference counts as part of it.
+    fn field_name_at<'a>(
+        tokens: &'a [Token<'a>],
+        ast: &'a [Declaration<'a>],
+        position: Position,
+    ) -> Option<FieldName<'a>> {
+        let mut token = Self::find_token_at_position(tokens, position)?;
+        if token.0 == TokenVariant::Dollar {
+            let i = tokens.iter().position(|t| std::ptr::eq(t, token))?;
+            token = tokens.get(i + 1)?;
+        }
+        FieldNames::of(ast).at(&token.1)
+    }
+
+    /// The plugin providing an annotation, with what it tells about it.
+    fn plugin_annotation(
+        &self,
+        name: &str,
+    ) -> Option<(&'static XenoPlugin<'static>, &'static PluginCompletion)> {
+        self.registry.plugins.iter().find_map(|plugin| {
+            let annotation = (plugin.provide_annotations?)()
+                .iter()
+                .find(|pc| pc.label == name)?;
+            Some((*plugin, annotation))
+        })
+    }
+
+    fn markdown_hover(value: String, range: Range) -> Hover {
+        Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range),
+        }
+    }
+
+    fn annotation_hover(&self, name: &TokenData) -> Option<Hover> {
+        if let Some(annotation) = BUILTIN_ANNOTATIONS.iter().find(|a| a.name == name.v) {
+            let signature = format_annotation_signature(annotation);
+            let value = format_annotation_documentation(annotation, &signature);
+            return Some(Self::markdown_hover(value, name.to_editor_range()));
+        }
+
+        let (plugin, annotation) = self.plugin_annotation(name.v)?;
+        let signature =
+            create_signature_information(annotation.label, annotation.params.unwrap_or(&[]), None);
+        let mut value = format!(
+            "```xenomorph\n{}\n```\n\n*(from the {} plugin)*",
+            signature.label, plugin.name
+        );
+        if let Some(docs) = annotation.documentation.or(annotation.detail) {
+            value.push_str("\n\n");
+            value.push_str(docs);
+        }
+        Some(Self::markdown_hover(value, name.to_editor_range()))
+    }
+
+    /// Member paths need the scope of the module, the other field names are resolved in the AST.
+    fn field_hover(field_name: FieldName, scope: Option<&ScopeInfo>) -> Option<Hover> {
+        let (value, token) = match field_name {
+            FieldName::Declaration(field) | FieldName::Sibling(_, Some(field)) => {
+                let value = format!(
+                    "**{}**: `{}`\n\nField of **{}**",
+                    field.name.v,
+                    field.type_name(),
+                    field.owner.v
+                );
+                let token = match field_name {
+                    FieldName::Sibling(reference, _) => reference,
+                    _ => field.name,
+                };
+                (value, token)
+            }
+            FieldName::Sibling(_, None) => return None,
+            FieldName::Member(base, members) => {
+                let scope = scope?;
+                let path: Vec<&str> = members.iter().map(|m| m.v).collect();
+                let (owner, info) = scope.resolve_member_declaration(base.v, &path)?;
+                let mut value = format!(
+                    "**{}**: `{}`\n\nField of **{}**",
+                    info.name, info.type_name, owner.name
+                );
+                if owner.module_path != scope.module_path {
+                    value.push_str(&format!(" *(from {})*", owner.module_path));
+                }
+                (value, *members.last()?)
+            }
+        };
+        Some(Self::markdown_hover(value, token.to_editor_range()))
+    }
+
+    /// Where an annotation provided by a plugin is described, at the first line of its
+    /// documentation that mentions it. Builtin annotations have nowhere to go.
+    fn annotation_definition(&self, name: &str) -> Option<Location> {
+        let (plugin, _) = self.plugin_annotation(name)?;
+        let path = plugin.documentation_path()?;
+        let text = std::fs::read_to_string(&path).ok()?;
+
+        let mention = format!("@{}", name);
+        let line = text
+            .lines()
+            .position(|line| {
+                line.match_indices(&mention).any(|(i, _)| {
+                    !line[i + mention.len()..]
+                        .starts_with(|c: char| c.is_alphanumeric() || c == '_')
+                })
+            })
+            .unwrap_or(0) as u32;
+        let start = Position { line, character: 0 };
+        Some(Location {
+            uri: Url::from_file_path(&path).ok()?,
+            range: Range { start, end: start },
+        })
+    }
+
+    fn field_definition(
+        uri: &Url,
+        field: FieldName,
+        scope: Option<&ScopeInfo>,
+    ) -> Option<Location> {
+        match field {
+            FieldName::Declaration(field) | FieldName::Sibling(_, Some(field)) => Some(Location {
+                uri: uri.clone(),
+                range: field.name.to_editor_range(),
+            }),
+            FieldName::Sibling(_, None) => None,
+            FieldName::Member(base, members) => {
+                let path: Vec<&str> = members.iter().map(|m| m.v).collect();
+                let (owner, info) = scope?.resolve_member_declaration(base.v, &path)?;
+                Self::field_info_to_location(owner, info)
+            }
+        }
+    }
+
+    /// The struct field named at the position, where it's declared in the workspace.
+    fn field_target(&self, module_path: &str, position: Position) -> Option<FieldTarget> {
+        let scope = self.registry.scope_info(module_path);
+        self.registry
+            .with_module(module_path, |tokens, ast, _| {
+                let (module_path, line, column, token) =
+                    match Self::field_name_at(tokens, ast, position)? {
+                        FieldName::Declaration(field) => (
+                            module_path.to_string(),
+                            field.name.l,
+                            field.name.c,
+                            field.name,
+                        ),
+                        FieldName::Sibling(reference, Some(field)) => (
+                            module_path.to_string(),
+                            field.name.l,
+                            field.name.c,
+                            reference,
+                        ),
+                        FieldName::Sibling(_, None) => return None,
+                        FieldName::Member(base, members) => {
+                            let path: Vec<&str> = members.iter().map(|m| m.v).collect();
+                            let (owner, info) =
+                                scope.as_ref()?.resolve_member_declaration(base.v, &path)?;
+                            (
+                                owner.module_path.clone(),
+                                info.line,
+                                info.column,
+                                *members.last()?,
+                            )
+                        }
+                    };
+                Some(FieldTarget {
+                    module_path,
+                    line,
+                    column,
+                    name: token.v.to_string(),
+                    range: token.to_editor_range(),
+                })
+            })
+            .flatten()
+    }
+
+    /// The location of a field's name where it's declared, and of every name referring to it.
+    fn field_locations(references: &FieldReferences) -> Option<(Location, Vec<Location>)> {
+        let location = |uri: &Url, span| Location {
+            uri: uri.clone(),
+            range: Self::span_to_range(span),
+        };
+        let uri = Url::from_file_path(&references.abs_path).ok()?;
+        let declaration = location(&uri, references.declaration);
+
+        let mut others: Vec<Location> = references
+            .siblings
+            .iter()
+            .map(|&span| location(&uri, span))
+            .collect();
+        for members in &references.members {
+            if let Ok(uri) = Url::from_file_path(&members.abs_path) {
+                others.extend(members.locations.iter().map(|&span| location(&uri, span)));
+            }
+        }
+        Some((declaration, others))
+    }
+
+    fn is_identifier(name: &str) -> bool {
+        matches!(
+            Lexer::tokenize(name).0.as_slice(),
+            [(TokenVariant::Identifier, token)] if token.v == name
+        )
+    }
+
+    /// Renames the field where it's declared, in `$name` references and in member paths.
+    fn rename_field(&self, target: &FieldTarget, new_name: &str) -> Result<Option<WorkspaceEdit>> {
+        let Some(references) =
+            self.registry
+                .field_references(&target.module_path, target.line, target.column)
+        else {
+            return Ok(None);
+        };
+        if !Self::is_identifier(new_name) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "'{}' is not a valid field name",
+                new_name
+            )));
+        }
+        if references.taken.iter().any(|name| name == new_name) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "'{}' already has a field named '{}'",
+                references.owner, new_name
+            )));
+        }
+
+        let Some((declaration, others)) = Self::field_locations(&references) else {
+            return Ok(None);
+        };
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for location in std::iter::once(declaration).chain(others) {
+            changes.entry(location.uri).or_default().push(TextEdit {
+                range: location.range,
+                new_text: new_name.to_string(),
+            });
+        }
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
+    // ── Code actions ────────────────────────────────────────────────
+
+    /// Quick fixes for a diagnostic this server published for the module.
+    fn quick_fixes(
+        &self,
+        uri: &Url,
+        module_path: &str,
+        diagnostic: &Diagnostic,
+    ) -> Vec<CodeActionOrCommand> {
+        let code = match &diagnostic.code {
+            Some(NumberOrString::String(code)) => {
+                code.strip_prefix("XEN").and_then(|n| n.parse().ok())
+            }
+            _ => None,
+        };
+        let Some(code) = code.map(ErrorCode) else {
+            return Vec::new();
+        };
+        let Some(source) = self
+            .registry
+            .with_module(module_path, |_, _, module| module.borrow_source().clone())
+        else {
+            return Vec::new();
+        };
+        let start = byte_offset(&source, diagnostic.range.start);
+        let end = byte_offset(&source, diagnostic.range.end).max(start);
+
+        quick_fixes::quick_fixes(&self.registry, module_path, &source, code, start..end)
+            .into_iter()
+            .map(|fix| {
+                let edit = TextEdit {
+                    range: Range {
+                        start: offset_position(&source, fix.edit.range.start),
+                        end: offset_position(&source, fix.edit.range.end),
+                    },
+                    new_text: fix.edit.new_text,
+                };
+                Self::quick_fix(fix.title, diagnostic, uri, vec![edit], fix.preferred)
+            })
+            .collect()
+    }
+
+    fn quick_fix(
+        title: String,
+        diagnostic: &Diagnostic,
+        uri: &Url,
+        edits: Vec<TextEdit>,
+        is_preferred: bool,
+    ) -> CodeActionOrCommand {
+        CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(uri.clone(), edits)])),
+                ..Default::default()
+            }),
+            is_preferred: Some(is_preferred),
+            ..Default::default()
+        })
+    }
+
+    // ── Semantic tokens ─────────────────────────────────────────────
+
+    /// Semantic tokens of a module, `None` if it isn't loaded.
+    fn semantic_tokens_of(&self, module_path: &str) -> Option<Vec<semantic_tokens::SemanticToken>> {
+        let scope = self.registry.scope_info(module_path)?;
+        self.registry.with_module(module_path, |_, _, module| {
+            let highlights = highlight(module.borrow_syntax_tree(), &scope);
+            semantic_tokens::encode(module.borrow_source(), &highlights)
+        })
+    }
+
+    fn semantic_token_type(kind: HighlightKind) -> u32 {
+        match kind {
+            HighlightKind::Type => 0,
+            HighlightKind::TypeParameter => 1,
+            HighlightKind::Field => 2,
+            HighlightKind::EnumMember => 3,
+            HighlightKind::Annotation => 4,
+            HighlightKind::Module => 5,
+            HighlightKind::Regex => 6,
+            HighlightKind::Documentation => 7,
+        }
+    }
+
+    /// A token as the protocol encodes it.
+    fn lsp_semantic_token(token: &semantic_tokens::SemanticToken) -> SemanticToken {
+        SemanticToken {
+            delta_line: token.delta_line,
+            delta_start: token.delta_start,
+            length: token.length,
+            token_type: Self::semantic_token_type(token.kind),
+            token_modifiers_bitset: token.modifiers,
+        }
+    }
+
+    // ── Signature help ──────────────────────────────────────────────
+
+    /// Signature of a builtin or plugin annotation.
+    fn annotation_signature(&self, name: &str) -> Option<SignatureInformation> {
+        if let Some(annotation) = BUILTIN_ANNOTATIONS.iter().find(|a| a.name == name) {
+            return Some(create_signature_information(
+                annotation.name,
+                annotation.params.unwrap_or(&[]),
+                annotation.documentation.map(str::to_string),
+            ));
+        }
+
+        self.plugin_annotation(name).map(|(_, pc)| {
+            create_signature_information(
+                pc.label,
+                pc.params.unwrap_or(&[]),
+                pc.documentation.or(pc.detail).map(str::to_string),
+            )
+        })
+    }
+
+    // ── Inlay hints ─────────────────────────────────────────────────
+
+    /// Hints of a module that fall inside the visible range.
+    fn inlay_hints_of(&self, module_path: &str, range: Range) -> Option<Vec<InlayHint>> {
+        let scope = self.registry.scope_info(module_path)?;
+        self.registry.with_module(module_path, |tokens, ast, _| {
+            inlay_hints(ast, tokens, &scope)
+                .into_iter()
+                .map(|hint| InlayHint {
+                    position: hint.after.to_editor_range().end,
+                    label: InlayHintLabel::String(hint.label),
+                    kind: match hint.kind {
+                        HintKind::Type => Some(InlayHintKind::TYPE),
+                        HintKind::Discriminant | HintKind::Range => None,
+                    },
+                    text_edits: None,
+                    tooltip: None,
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                })
+                .filter(|hint| range.start <= hint.position && hint.position <= range.end)
+                .collect()
+        })
+    }
+
+    // ── Workspace symbols, links and type hierarchy ─────────────────
+
+    /// Whether the letters of the query appear in the name in order, ignoring case.
+    fn matches_symbol_query(name: &str, query: &str) -> bool {
+        let mut letters = name.chars().flat_map(char::to_lowercase);
+        query
+            .chars()
+            .flat_map(char::to_lowercase)
+            .all(|q| letters.any(|c| c == q))
+    }
+
+    fn hierarchy_item(info: &DeclarationInfo, detail: String) -> Option<CallHierarchyItem> {
+        let location = Self::declaration_info_to_location(info)?;
+        Some(CallHierarchyItem {
+            name: info.name.clone(),
+            kind: SymbolKind::STRUCT,
+            tags: None,
+            detail: Some(detail),
+            uri: location.uri,
+            range: location.range,
+            selection_range: location.range,
+            data: None,
+        })
+    }
+
+    /// The declaration a hierarchy item stands for, looked up again in case the module changed.
+    fn hierarchy_declaration(&self, item: &CallHierarchyItem) -> Option<DeclarationInfo> {
+        let module_path = self.uri_to_module_path(&item.uri)?;
+        self.registry.find_declaration(&module_path, &item.name)
+    }
+
+    /// A related type with the ranges of its references in the declaration on the referring side.
+    fn type_use_item(type_use: &TypeUse) -> Option<(CallHierarchyItem, Vec<Range>)> {
+        let detail = format!(
+            "{} ({})",
+            type_use.relation, type_use.declaration.module_path
+        );
+        let item = Self::hierarchy_item(&type_use.declaration, detail)?;
+        let ranges = type_use
+            .locations
+            .iter()
+            .map(|&span| Self::span_to_range(span))
+            .collect();
+        Some((item, ranges))
+    }
+
     // ── Goto Definition helpers ─────────────────────────────────────
 
+    /// A `(line, column, length)` span of a single line.
+    fn span_to_range((line, character, len): (u32, u32, u32)) -> Range {
+        Range {
+            start: Position { line, character },
+            end: Position {
+                line,
+                character: character + len,
+            },
+        }
+    }
+
+    fn sort_locations(locations: &mut [Location]) {
+        locations.sort_by(|left, right| {
+            left.uri
+                .as_str()
+                .cmp(right.uri.as_str())
+                .then_with(|| left.range.start.line.cmp(&right.range.start.line))
+                .then_with(|| left.range.start.character.cmp(&right.range.start.character))
+        });
+    }
+
+    fn field_info_to_location(owner: &DeclarationInfo, field: &FieldInfo) -> Option<Location> {
+        let start = Position {
+            line: field.line,
+            character: field.column,
+        };
+        Some(Location {
+            uri: Url::from_file_path(&owner.abs_path).ok()?,
+            range: Range {
+                start,
+                end: Position {
+                    line: start.line,
+                    character: start.character + field.name.len() as u32,
+                },
+            },
+        })
+    }
+
     fn declaration_info_to_location(info: &DeclarationInfo) -> Option<Location> {
         let target_uri = Url::from_file_path(&info.abs_path).ok()?;
         Some(Location {
@@ -491,7 +1125,7 @@ impl LanguageServer for Backend {
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
                         open_close: Some(true),
-                        change: Some(TextDocumentSyncKind::FULL),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
                         ..Default::default()
                     },
                 )),
@@ -510,8 +1144,39 @@ impl LanguageServer for Backend {
                     completion_item: None,
                 }),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
+                inlay_hint_provider: Some(OneOf::Left(true)),
                 document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: SemanticTokensLegend {
+                                token_types: SEMANTIC_TOKEN_TYPES.to_vec(),
+                                token_modifiers: SEMANTIC_TOKEN_MODIFIERS.to_vec(),
+                            },
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                        ..Default::default()
+                    },
+                )),
                 document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: Default::default(),
+                }),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
                 definition_provider: Some(OneOf::Left(true)),
                 references_provider: Some(OneOf::Left(true)),
                 rename_provider: Some(OneOf::Right(RenameOptions {
@@ -526,9 +1191,7 @@ impl LanguageServer for Backend {
 
     async fn initialized(&self, _: InitializedParams) {
         // Load the entry module and its transitive imports on startup
-        let errors = self
-            .registry
-            .load_module(&[&self.registry.entry], true, None);
+        let errors = self.registry.load_module_tree(&[&self.registry.entry]);
         for e in &errors {
             self.client
                 .log_message(MessageType::WARNING, format!("Module error: {}", e))
@@ -547,19 +1210,37 @@ impl LanguageServer for Backend {
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri;
         let text = params.text_document.text;
+        self.documents
+            .lock()
+            .unwrap()
+            .insert(uri.clone(), text.clone());
         self.validate_document(&uri, text).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri;
 
-        // With TextDocumentSyncKind::FULL, last change contains the full text
-        if let Some(change) = params.content_changes.into_iter().last() {
-            self.validate_document(&uri, change.text).await;
-        }
+        // Changes only carry the edited ranges, they apply one after the other
+        let text = {
+            let mut documents = self.documents.lock().unwrap();
+            let text = documents.entry(uri.clone()).or_default();
+            for change in params.content_changes {
+                apply_change(text, change);
+            }
+            text.clone()
+        };
+        self.validate_document(&uri, text).await;
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents
+            .lock()
+            .unwrap()
+            .remove(&params.text_document.uri);
+        self.semantic_tokens
+            .lock()
+            .unwrap()
+            .remove(&params.text_document.uri);
         self.client
             .publish_diagnostics(params.text_document.uri, vec![], None)
             .await;
@@ -591,15 +1272,52 @@ impl LanguageServer for Backend {
         let position = params.text_document_position_params.position;
         let module_path = self.uri_to_module_path(&uri);
 
-        let hover =
-            self.registry
-                .with_module(module_path.as_deref().unwrap_or(""), |tokens, ast, _| {
-                    self.get_hover_for_location(tokens, ast, position, module_path.as_deref())
-                });
+        let current_module = module_path.as_deref().unwrap_or("");
+        let scope = self.registry.scope_info(current_module);
+
+        let hover = self.registry.with_module(current_module, |tokens, ast, _| {
+            self.get_hover_for_location(
+                tokens,
+                ast,
+                position,
+                module_path.as_deref(),
+                scope.as_ref(),
+            )
+        });
 
         Ok(hover.flatten())
     }
 
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let module_path = self.uri_to_module_path(&uri);
+
+        let call = self
+            .registry
+            .with_module(module_path.as_deref().unwrap_or(""), |tokens, _, _| {
+                annotation_call_at(tokens, position)
+                    .map(|(name, active)| (name.to_string(), active))
+            })
+            .flatten();
+        let Some((name, active)) = call else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .annotation_signature(&name)
+            .map(|signature| SignatureHelp {
+                signatures: vec![signature],
+                active_signature: Some(0),
+                active_parameter: Some(active),
+            }))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let module_path = self.uri_to_module_path(&params.text_document.uri);
+        Ok(self.inlay_hints_of(module_path.as_deref().unwrap_or(""), params.range))
+    }
+
     async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
         let uri = params.text_document.uri;
         let module_path = self.uri_to_module_path(&uri);
@@ -608,7 +1326,7 @@ impl LanguageServer for Backend {
             self.registry
                 .with_module(module_path.as_deref().unwrap_or(""), |_, _, module| {
                     let source = module.borrow_source();
-                    let formatted = format_xenomorph(source);
+                    let formatted = format_xenomorph(source, &Config::get().format);
 
                     vec![TextEdit {
                         range: Range {
@@ -628,6 +1346,108 @@ impl LanguageServer for Backend {
         Ok(result)
     }
 
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let module_path = self.uri_to_module_path(&uri);
+
+        let result =
+            self.registry
+                .with_module(module_path.as_deref().unwrap_or(""), |_, _, module| {
+                    let source = module.borrow_source();
+                    let range = byte_offset(source, params.range.start)
+                        ..byte_offset(source, params.range.end);
+                    let (replaced, formatted) =
+                        format_xenomorph_range(source, &Config::get().format, range)?;
+
+                    Some(vec![TextEdit {
+                        range: Range {
+                            start: offset_position(source, replaced.start),
+                            end: offset_position(source, replaced.end),
+                        },
+                        new_text: formatted,
+                    }])
+                });
+
+        Ok(result.flatten())
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let uri = params.text_document.uri;
+        let module_path = self.uri_to_module_path(&uri);
+        let Some(data) = self.semantic_tokens_of(module_path.as_deref().unwrap_or("")) else {
+            return Ok(None);
+        };
+
+        let mut sent = self.semantic_tokens.lock().unwrap();
+        let result_id = sent.get(&uri).map_or(0, |(id, _)| id + 1);
+        sent.insert(uri, (result_id, data.clone()));
+
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: Some(result_id.to_string()),
+            data: data.iter().map(Self::lsp_semantic_token).collect(),
+        })))
+    }
+
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        let uri = params.text_document.uri;
+        let module_path = self.uri_to_module_path(&uri);
+        let Some(data) = self.semantic_tokens_of(module_path.as_deref().unwrap_or("")) else {
+            return Ok(None);
+        };
+
+        let mut sent = self.semantic_tokens.lock().unwrap();
+        let previous = sent.remove(&uri);
+        let result_id = previous.as_ref().map_or(0, |(id, _)| id + 1);
+        let result = match previous {
+            // Only a delta against the tokens the client has can be applied
+            Some((id, old)) if id.to_string() == params.previous_result_id => {
+                let edit = semantic_tokens::edit(&old, &data);
+                // Edits count the integers of the encoded tokens, five per token
+                SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                    result_id: Some(result_id.to_string()),
+                    edits: vec![SemanticTokensEdit {
+                        start: edit.start as u32 * 5,
+                        delete_count: edit.delete_count as u32 * 5,
+                        data: Some(edit.tokens.iter().map(Self::lsp_semantic_token).collect()),
+                    }],
+                })
+            }
+            _ => SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: Some(result_id.to_string()),
+                data: data.iter().map(Self::lsp_semantic_token).collect(),
+            }),
+        };
+        sent.insert(uri, (result_id, data));
+
+        Ok(Some(result))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let Some(module_path) = self.uri_to_module_path(&uri) else {
+            return Ok(None);
+        };
+
+        let actions: Vec<CodeActionOrCommand> = params
+            .context
+            .diagnostics
+            .iter()
+            .filter(|d| d.source.as_deref() == Some("xenomorph"))
+            .flat_map(|d| self.quick_fixes(&uri, &module_path, d))
+            .collect();
+
+        Ok(Some(actions))
+    }
+
     async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
@@ -636,6 +1456,22 @@ impl LanguageServer for Backend {
         let position = params.text_document_position_params.position;
         let module_path = self.uri_to_module_path(&uri);
         let mp = module_path.as_deref().unwrap_or("");
+        let scope = self.registry.scope_info(mp);
+
+        // Fields and annotations are told apart from type names by where they are
+        let field_or_annotation = self
+            .registry
+            .with_module(mp, |tokens, ast, _| {
+                if let Some(name) = Self::annotation_name_at(tokens, position) {
+                    return Some(self.annotation_definition(name.v));
+                }
+                let field = Self::field_name_at(tokens, ast, position)?;
+                Some(Self::field_definition(&uri, field, scope.as_ref()))
+            })
+            .flatten();
+        if let Some(location) = field_or_annotation {
+            return Ok(location.map(GotoDefinitionResponse::Scalar));
+        }
 
         // First try: local definition or import navigation
         let local_result = self.registry.with_module(mp, |tokens, ast, _| {
@@ -644,7 +1480,28 @@ impl LanguageServer for Backend {
             // If cursor is on an import line, navigate to the imported file
             if token.0 == TokenVariant::Identifier {
                 for decl in ast.iter() {
-                    if let Declaration::Import { path, location } = decl {
+                    if let Declaration::Import {
+                        path,
+                        items,
+                        location,
+                        ..
+                    } = decl
+                    {
+                        // A name in the braces navigates to its declaration instead
+                        let imported_item = items.iter().flatten().find(|item| {
+                            std::ptr::eq(item.name, &token.1)
+                                || item.alias.is_some_and(|a| std::ptr::eq(a, &token.1))
+                        });
+                        if let Some(item) = imported_item {
+                            let (_, info) = self
+                                .registry
+                                .get_exported_declarations(&path.join("/"))
+                                .into_iter()
+                                .find(|(name, _)| name == item.name.v)?;
+                            return Self::declaration_info_to_location(&info)
+                                .map(GotoDefinitionResponse::Scalar);
+                        }
+
                         if token.1.l == location.l {
                             let segments: Vec<&str> = path.iter().copied().collect();
                             if let Ok((_, abs_path)) = self.registry.resolve_import(&segments, None)
@@ -705,6 +1562,22 @@ impl LanguageServer for Backend {
         let current_module = module_path.as_deref().unwrap_or("");
         let include_declaration = params.context.include_declaration;
 
+        if let Some(target) = self.field_target(current_module, position) {
+            let Some((declaration, mut locations)) = self
+                .registry
+                .field_references(&target.module_path, target.line, target.column)
+                .as_ref()
+                .and_then(Self::field_locations)
+            else {
+                return Ok(None);
+            };
+            if include_declaration {
+                locations.push(declaration);
+            }
+            Self::sort_locations(&mut locations);
+            return Ok(Some(locations));
+        }
+
         let searched_name = self
             .registry
             .with_module(current_module, |tokens, _, _| {
@@ -778,13 +1651,7 @@ impl LanguageServer for Backend {
             }
         }
 
-        locations.sort_by(|left, right| {
-            left.uri
-                .as_str()
-                .cmp(right.uri.as_str())
-                .then_with(|| left.range.start.line.cmp(&right.range.start.line))
-                .then_with(|| left.range.start.character.cmp(&right.range.start.character))
-        });
+        Self::sort_locations(&mut locations);
 
         Ok(Some(locations))
     }
@@ -821,6 +1688,156 @@ impl LanguageServer for Backend {
         Ok(symbols.map(DocumentSymbolResponse::Flat))
     }
 
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        let cache = self.registry.module_cache.blocking_read();
+
+        #[allow(deprecated)]
+        let mut symbols: Vec<SymbolInformation> = cache
+            .values()
+            .flat_map(|module| module.borrow_declarations().values())
+            .filter(|info| Self::matches_symbol_query(&info.name, &params.query))
+            .filter_map(|info| {
+                Some(SymbolInformation {
+                    name: info.name.clone(),
+                    kind: SymbolKind::STRUCT,
+                    tags: None,
+                    deprecated: None,
+                    location: Self::declaration_info_to_location(info)?,
+                    container_name: Some(info.module_path.clone()),
+                })
+            })
+            .collect();
+        symbols.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| a.container_name.cmp(&b.container_name))
+        });
+
+        Ok(Some(symbols))
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let uri = params.text_document.uri;
+        let module_path = self.uri_to_module_path(&uri);
+
+        // The path of every `import a/b`, with the range from its first to its last segment
+        let paths = self
+            .registry
+            .with_module(module_path.as_deref().unwrap_or(""), |tokens, _, _| {
+                let mut paths = Vec::new();
+                for (i, _) in tokens
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, t)| t.0 == TokenVariant::Import)
+                {
+                    let path: Vec<&Token> = tokens[i + 1..]
+                        .iter()
+                        .take_while(|t| {
+                            matches!(t.0, TokenVariant::Identifier | TokenVariant::Slash)
+                        })
+                        .collect();
+                    let (Some(first), Some(last)) = (path.first(), path.last()) else {
+                        continue;
+                    };
+                    let segments: Vec<String> = path
+                        .iter()
+                        .filter(|t| t.0 == TokenVariant::Identifier)
+                        .map(|t| t.1.v.to_string())
+                        .collect();
+                    let range = Range {
+                        start: first.1.to_editor_position(),
+                        end: last.1.to_editor_range().end,
+                    };
+                    paths.push((segments, range));
+                }
+                paths
+            })
+            .unwrap_or_default();
+
+        let links = paths
+            .into_iter()
+            .filter_map(|(segments, range)| {
+                let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+                let (import_path, abs_path) = self.registry.resolve_import(&segments, None).ok()?;
+                Some(DocumentLink {
+                    range,
+                    target: Some(Url::from_file_path(abs_path).ok()?),
+                    tooltip: Some(import_path),
+                    data: None,
+                })
+            })
+            .collect();
+
+        Ok(Some(links))
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let module_path = self.uri_to_module_path(&uri);
+        let current_module = module_path.as_deref().unwrap_or("");
+
+        let name = self
+            .registry
+            .with_module(current_module, |tokens, _, _| {
+                let token = Self::find_token_at_position(tokens, position)?;
+                (token.0 == TokenVariant::Identifier).then(|| token.1.v.to_string())
+            })
+            .flatten();
+        let Some(declaration) =
+            name.and_then(|n| self.registry.find_declaration(current_module, &n))
+        else {
+            return Ok(None);
+        };
+
+        let detail = declaration.module_path.clone();
+        Ok(Self::hierarchy_item(&declaration, detail).map(|item| vec![item]))
+    }
+
+    /// Declarations that embed, intersect or extend the type.
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        let Some(declaration) = self.hierarchy_declaration(&params.item) else {
+            return Ok(None);
+        };
+
+        let calls = self
+            .registry
+            .type_users(&declaration)
+            .iter()
+            .filter_map(Self::type_use_item)
+            .map(|(from, from_ranges)| CallHierarchyIncomingCall { from, from_ranges })
+            .collect();
+        Ok(Some(calls))
+    }
+
+    /// Types the declaration embeds, intersects or extends.
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let Some(declaration) = self.hierarchy_declaration(&params.item) else {
+            return Ok(None);
+        };
+
+        let calls = self
+            .registry
+            .types_used_by(&declaration)
+            .iter()
+            .filter_map(Self::type_use_item)
+            .map(|(to, from_ranges)| CallHierarchyOutgoingCall { to, from_ranges })
+            .collect();
+        Ok(Some(calls))
+    }
+
     async fn prepare_rename(
         &self,
         params: TextDocumentPositionParams,
@@ -829,6 +1846,13 @@ impl LanguageServer for Backend {
         let position = params.position;
         let module_path = self.uri_to_module_path(&uri);
 
+        if let Some(target) = self.field_target(module_path.as_deref().unwrap_or(""), position) {
+            return Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+                range: target.range,
+                placeholder: target.name,
+            }));
+        }
+
         let result =
             self.registry
                 .with_module(module_path.as_deref().unwrap_or(""), |tokens, ast, _| {
@@ -862,6 +1886,10 @@ impl LanguageServer for Backend {
         let new_name = params.new_name;
         let module_path = self.uri_to_module_path(&uri);
 
+        if let Some(target) = self.field_target(module_path.as_deref().unwrap_or(""), position) {
+            return self.rename_field(&target, &new_name);
+        }
+
         let result =
             self.registry
                 .with_module(module_path.as_deref().unwrap_or(""), |tokens, ast, _| {
@@ -920,6 +1948,8 @@ async fn main() {
     let (service, socket) = LspService::new(move |client| Backend {
         client,
         registry: reg,
+        documents: Mutex::new(HashMap::new()),
+        semantic_tokens: Mutex::new(HashMap::new()),
     });
 
     Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
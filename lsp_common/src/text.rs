@@ -0,0 +1,126 @@
+use tower_lsp::lsp_types::{Position, TextDocumentContentChangeEvent};
+
+/// Applies one change of an incremental `didChange` notification to the document text.
+pub fn apply_change(text: &mut String, change: TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = byte_offset(text, range.start);
+            let end = byte_offset(text, range.end).max(start);
+            text.replace_range(start..end, &change.text);
+        }
+        None => *text = change.text,
+    }
+}
+
+/// Byte offset of a position, whose character counts UTF-16 code units as the protocol requires.
+/// Positions past the end of a line or of the text are clamped.
+pub fn byte_offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let line = &text[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+
+    let mut units = 0;
+    for (i, ch) in line.char_indices() {
+        if units >= position.character {
+            return line_start + i;
+        }
+        units += ch.len_utf16() as u32;
+    }
+    line_start + line.len()
+}
+
+/// Position of a byte offset, the inverse of [`byte_offset`].
+pub fn offset_position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::Range;
+
+    use super::*;
+
+    fn position(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    fn change(start: Position, end: Position, text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range { start, end }),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn characters_count_utf16_code_units() {
+        let text = "aé😀b\nx";
+
+        assert_eq!(byte_offset(text, position(0, 1)), 1);
+        assert_eq!(byte_offset(text, position(0, 2)), 3);
+        assert_eq!(byte_offset(text, position(0, 4)), 7);
+        assert_eq!(&text[byte_offset(text, position(0, 4))..], "b\nx");
+        assert_eq!(byte_offset(text, position(1, 0)), 9);
+    }
+
+    #[test]
+    fn positions_past_the_end_are_clamped() {
+        let text = "ab\ncd";
+
+        assert_eq!(byte_offset(text, position(0, 2)), 2);
+        assert_eq!(byte_offset(text, position(0, 40)), 2);
+        assert_eq!(byte_offset(text, position(1, 2)), 5);
+        assert_eq!(byte_offset(text, position(1, 40)), 5);
+        assert_eq!(byte_offset(text, position(7, 0)), 5);
+    }
+
+    #[test]
+    fn offsets_and_positions_round_trip() {
+        let text = "aé😀b\n\nx😀";
+
+        for (offset, _) in text.char_indices().chain([(text.len(), ' ')]) {
+            assert_eq!(byte_offset(text, offset_position(text, offset)), offset);
+        }
+        assert_eq!(offset_position(text, 7), position(0, 4));
+        assert_eq!(offset_position(text, text.len()), position(2, 3));
+    }
+
+    #[test]
+    fn changes_are_applied_in_order() {
+        let mut text = "type A = {\n  x: string\n}".to_string();
+
+        for change in [
+            change(position(1, 2), position(1, 3), "name"),
+            change(position(1, 8), position(1, 14), "int"),
+            change(position(2, 1), position(2, 1), "\ntype B = A"),
+            change(position(0, 5), position(9, 0), ""),
+        ] {
+            apply_change(&mut text, change);
+        }
+        assert_eq!(text, "type ");
+
+        apply_change(&mut text, change(position(0, 9), position(0, 2), "é"));
+        assert_eq!(text, "type é");
+
+        apply_change(
+            &mut text,
+            TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "type C = int".to_string(),
+            },
+        );
+        assert_eq!(text, "type C = int");
+    }
+}
@@ -2,9 +2,11 @@ use std::sync::LazyLock;
 
 use tower_lsp::lsp_types::{
     CompletionItem, CompletionItemKind, Documentation, InsertTextFormat, MarkupContent, MarkupKind,
+    ParameterInformation, ParameterLabel, Position, SignatureInformation,
 };
+use xenomorph_common::lexer::{Token, TokenVariant};
 use xenomorph_common::semantic::{
-    XenoAnnotation, XenoParameterType, XenoType, BUILTIN_ANNOTATIONS, BUILTIN_TYPES,
+    XenoAnnotation, XenoParam, XenoParameterType, XenoType, BUILTIN_ANNOTATIONS, BUILTIN_TYPES,
 };
 
 pub fn create_completion_item(
@@ -69,21 +71,98 @@ pub fn format_annotation_documentation(annotation: &XenoAnnotation, signature: &
 }
 
 pub fn format_annotation_signature(annotation: &XenoAnnotation) -> String {
-    let params = annotation
-        .params
-        .unwrap_or(&[])
+    format_signature(annotation.name, annotation.params.unwrap_or(&[]))
+}
+
+fn format_signature(name: &str, params: &[&XenoParam]) -> String {
+    let params = params
         .iter()
-        .map(|param| {
-            format!(
-                "{}: {}",
-                param.name,
-                format_parameter_type(param.param_type)
-            )
-        })
+        .map(|param| format_parameter(param))
         .collect::<Vec<_>>()
         .join(", ");
 
-    format!("@{}({})", annotation.name, params)
+    format!("@{}({})", name, params)
+}
+
+fn format_parameter(param: &XenoParam) -> String {
+    format!(
+        "{}: {}",
+        param.name,
+        format_parameter_type(param.param_type)
+    )
+}
+
+/// Signature of an annotation whose parameters are labeled by their offsets in it,
+/// so editors can highlight the one being typed.
+pub fn create_signature_information(
+    name: &str,
+    params: &[&XenoParam],
+    documentation: Option<String>,
+) -> SignatureInformation {
+    let label = format_signature(name, params);
+
+    let mut offset = name.len() as u32 + 2; // `@name(`
+    let parameters = params
+        .iter()
+        .map(|param| {
+            let formatted = format_parameter(param);
+            let start = offset;
+            offset += formatted.len() as u32 + 2; // `, `
+            ParameterInformation {
+                label: ParameterLabel::LabelOffsets([start, start + formatted.len() as u32]),
+                documentation: Some(Documentation::String(format_parameter_type(
+                    param.param_type,
+                ))),
+            }
+        })
+        .collect();
+
+    SignatureInformation {
+        label,
+        documentation: documentation.map(|value| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            })
+        }),
+        parameters: Some(parameters),
+        active_parameter: None,
+    }
+}
+
+/// The annotation whose parentheses contain the position, with the index of the argument there.
+pub fn annotation_call_at<'a>(
+    tokens: &'a [Token<'a>],
+    position: Position,
+) -> Option<(&'a str, u32)> {
+    let before = tokens.iter().rposition(|(_, data)| {
+        Position {
+            line: data.l,
+            character: data.c,
+        } < position
+    })?;
+
+    // Walk back to the unclosed `(`, counting the commas of this call only
+    let mut depth = 0;
+    let mut active = 0;
+    for i in (0..=before).rev() {
+        match tokens[i].0 {
+            TokenVariant::RParen | TokenVariant::RBracket | TokenVariant::RCurly => depth += 1,
+            TokenVariant::LParen | TokenVariant::LBracket | TokenVariant::LCurly if depth > 0 => {
+                depth -= 1
+            }
+            TokenVariant::LParen => {
+                let name = &tokens[i.checked_sub(1)?];
+                let at = &tokens[i.checked_sub(2)?];
+                return (name.0 == TokenVariant::Identifier && at.0 == TokenVariant::At)
+                    .then_some((name.1.v, active));
+            }
+            TokenVariant::Comma if depth == 0 => active += 1,
+            TokenVariant::LBracket | TokenVariant::LCurly | TokenVariant::Semicolon => return None,
+            _ => {}
+        }
+    }
+    None
 }
 
 fn format_parameter_type(parameter_type: XenoParameterType) -> String {
@@ -130,3 +209,97 @@ pub static BUILTIN_TYPE_COMPLETIONS: LazyLock<Vec<CompletionItem>> = LazyLock::n
         })
         .collect()
 });
+
+#[cfg(test)]
+mod tests {
+    use xenomorph_common::lexer::Lexer;
+    use xenomorph_common::semantic::CONDITION_PARAM;
+
+    use super::*;
+
+    /// The call at the `|` of `source`, which is removed before lexing.
+    fn call_at(source: &str) -> Option<(String, u32)> {
+        let cursor = source.find('|').unwrap();
+        let source = source.replacen('|', "", 1);
+        let before = &source[..cursor];
+        let position = Position {
+            line: before.matches('\n').count() as u32,
+            character: (cursor - before.rfind('\n').map_or(0, |i| i + 1)) as u32,
+        };
+        let (tokens, _) = Lexer::tokenize(&source);
+        annotation_call_at(&tokens, position).map(|(name, active)| (name.to_string(), active))
+    }
+
+    #[test]
+    fn the_innermost_unclosed_call_is_found() {
+        assert_eq!(
+            call_at("type A = { x: int @if($a, @min(1), |"),
+            Some(("if".to_string(), 2))
+        );
+        assert_eq!(
+            call_at("type A = { x: int @if($a, @min(|"),
+            Some(("min".to_string(), 0))
+        );
+        assert_eq!(
+            call_at("type A = { x: int @if($a, @range(1, [2, 3], |"),
+            Some(("range".to_string(), 2))
+        );
+    }
+
+    #[test]
+    fn the_first_argument_is_active_right_after_the_parenthesis() {
+        assert_eq!(
+            call_at("type A = { x: int @min(|) }"),
+            Some(("min".to_string(), 0))
+        );
+        assert_eq!(
+            call_at("type A = { x: int @if(\n  |"),
+            Some(("if".to_string(), 0))
+        );
+    }
+
+    #[test]
+    fn positions_outside_of_calls_have_no_call() {
+        assert_eq!(call_at("type A = { x: int @min(1) | }"), None);
+        assert_eq!(call_at("type A = { x: int @min(1)|"), None);
+        assert_eq!(call_at("type A = { x: |int }"), None);
+        assert_eq!(call_at("|type A = int"), None);
+        assert_eq!(call_at("type A = { x: (|"), None);
+    }
+
+    #[test]
+    fn parameter_offsets_point_at_each_parameter_in_the_label() {
+        let params: &[&XenoParam] = &[
+            &XenoParam {
+                name: "min",
+                param_type: XenoParameterType::NumberLiteral,
+            },
+            &XenoParam {
+                name: "values",
+                param_type: XenoParameterType::List(&[XenoParameterType::StringLiteral]),
+            },
+            CONDITION_PARAM[0],
+        ];
+        let signature = create_signature_information("check", params, None);
+
+        assert_eq!(
+            signature.label,
+            "@check(min: number, values: [string], condition: expression)"
+        );
+        let labels = signature
+            .parameters
+            .unwrap()
+            .into_iter()
+            .map(|parameter| match parameter.label {
+                ParameterLabel::LabelOffsets([start, end]) => {
+                    signature.label[start as usize..end as usize].to_string()
+                }
+                ParameterLabel::Simple(label) => panic!("unexpected simple label {}", label),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            ["min: number", "values: [string]", "condition: expression"]
+        );
+    }
+}
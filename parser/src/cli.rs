@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use xenomorph_common::config::ConfigOverrides;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "xeno",
+    version,
+    about = "Validates xenomorph schemas and generates code from them"
+)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    /// Defaults to `generate`.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Args, Debug)]
+pub struct GlobalArgs {
+    /// Config file to use instead of the closest `xenomorph.toml`.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Entry module, relative to the workspace root.
+    #[arg(long, global = true, value_name = "MODULE")]
+    pub entry: Option<String>,
+
+    /// Workspace root.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub workdir: Option<PathBuf>,
+
+    /// Plugin to load instead of the configured ones, can be repeated.
+    #[arg(long = "plugin", global = true, value_name = "NAME")]
+    pub plugins: Vec<String>,
+
+    /// Only report errors.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Also report what is loaded and how long it takes.
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Validates the workspace without generating anything.
+    Check {
+        #[arg(long, value_enum, default_value_t = CheckFormat::Text)]
+        format: CheckFormat,
+    },
+    /// Validates the workspace and runs the generators of every plugin.
+    Generate {
+        /// Keep running and regenerate the modules affected by every change.
+        #[arg(long)]
+        watch: bool,
+        /// Generate every module, even those unchanged since the last run.
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Writes the `xenomorph.toml` JSON Schema to `.xenomorph/xenomorph.schema.json`.
+    Schema,
+    /// Prints the syntax tree of a file.
+    Ast {
+        /// Defaults to the entry module.
+        file: Option<PathBuf>,
+    },
+    /// Prints the tokens of a file.
+    Tokens {
+        /// Defaults to the entry module.
+        file: Option<PathBuf>,
+    },
+    /// Formats `.xen` files in place.
+    Fmt {
+        /// Only report unformatted files and fail if there are any.
+        #[arg(long)]
+        check: bool,
+        /// Defaults to every `.xen` file in the workspace.
+        files: Vec<PathBuf>,
+    },
+    /// Creates `xenomorph.toml` and an entry module in the workspace root.
+    Init,
+}
+
+/// Output formats of `xeno check`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+impl GlobalArgs {
+    pub fn overrides(&self) -> ConfigOverrides {
+        ConfigOverrides {
+            config: self.config.clone(),
+            workdir: self.workdir.clone(),
+            entry: self.entry.clone(),
+            plugins: (!self.plugins.is_empty()).then(|| self.plugins.clone()),
+        }
+    }
+
+    pub fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_flags_are_accepted_after_the_subcommand() {
+        let cli = Cli::try_parse_from([
+            "xeno",
+            "check",
+            "--format",
+            "sarif",
+            "--entry",
+            "api/index",
+            "--plugin",
+            "typescript",
+            "--plugin",
+            "json_schema",
+            "-q",
+        ])
+        .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Some(Command::Check {
+                format: CheckFormat::Sarif
+            })
+        ));
+        assert_eq!(cli.global.verbosity(), Verbosity::Quiet);
+        let overrides = cli.global.overrides();
+        assert_eq!(overrides.entry.as_deref(), Some("api/index"));
+        assert_eq!(
+            overrides.plugins,
+            Some(vec!["typescript".to_string(), "json_schema".to_string()])
+        );
+    }
+
+    #[test]
+    fn quiet_and_verbose_conflict() {
+        assert!(Cli::try_parse_from(["xeno", "-q", "-v"]).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use xenomorph_common::diagnostic::Severity;
+use xenomorph_common::module::types::ModuleError;
+
+/// Source text of a module and the path it is shown as in diagnostics.
+pub struct SourceFile<'a> {
+    pub path: String,
+    pub text: &'a str,
+}
+
+/// Renders diagnostics the way rustc does: a `path:line:col` header,
+/// the offending source line and an underline spanning the reported range.
+pub struct Renderer<'a> {
+    /// Sources keyed by module path.
+    files: HashMap<&'a str, SourceFile<'a>>,
+    color: bool,
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+
+const TAB_WIDTH: usize = 4;
+
+/// How a range is underlined: `^^^` for the primary location, `---` with a label for related ones.
+struct Underline<'s> {
+    marker: char,
+    style: &'s str,
+    label: &'s str,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(files: HashMap<&'a str, SourceFile<'a>>, color: bool) -> Self {
+        Self { files, color }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn severity_style(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Info => CYAN,
+            Severity::Hint => GREEN,
+        }
+    }
+
+    fn path_of(&self, module_path: &str) -> String {
+        self.files
+            .get(module_path)
+            .map_or_else(|| format!("{module_path}.xen"), |f| f.path.clone())
+    }
+
+    /// Renders one diagnostic with its related locations, ending in a blank line.
+    pub fn render(&self, err: &ModuleError) -> String {
+        let style = Self::severity_style(err.severity);
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(style, &format!("{}[{}]", err.severity, err.code)),
+            self.paint(BOLD, &format!(": {}", err.message))
+        );
+
+        // Gutter wide enough for every line number shown
+        let width = std::iter::once(err.location.map(|(l, _, _)| l))
+            .chain(err.related.iter().map(|r| Some(r.location.0)))
+            .flatten()
+            .map(|l| (l + 1).to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(width);
+
+        let path = self.path_of(&err.module_path);
+        match err.location {
+            Some(location) => {
+                let (line, col, _) = location;
+                let _ = writeln!(
+                    out,
+                    "{pad}{} {path}:{}:{}",
+                    self.paint(BLUE, "-->"),
+                    line + 1,
+                    col + 1
+                );
+                let underline = Underline {
+                    marker: '^',
+                    style,
+                    label: "",
+                };
+                self.snippet(&mut out, &err.module_path, location, underline, width);
+            }
+            None => {
+                let _ = writeln!(out, "{pad}{} {path}", self.paint(BLUE, "-->"));
+            }
+        }
+
+        for related in &err.related {
+            let (line, col, _) = related.location;
+            let _ = writeln!(
+                out,
+                "{pad}{} {}:{}:{}",
+                self.paint(BLUE, ":::"),
+                self.path_of(&related.module_path),
+                line + 1,
+                col + 1
+            );
+            let underline = Underline {
+                marker: '-',
+                style: BLUE,
+                label: &related.message,
+            };
+            self.snippet(
+                &mut out,
+                &related.module_path,
+                related.location,
+                underline,
+                width,
+            );
+        }
+
+        out.push('\n');
+        out
+    }
+
+    /// Writes the source line of `location` and underlines the reported range.
+    fn snippet(
+        &self,
+        out: &mut String,
+        module_path: &str,
+        location: (u32, u32, u32),
+        underline: Underline,
+        width: usize,
+    ) {
+        let (line, col, len) = location;
+        let gutter = self.paint(BLUE, &format!("{} |", " ".repeat(width)));
+        let Some(text) = self
+            .files
+            .get(module_path)
+            .and_then(|f| f.text.lines().nth(line as usize))
+        else {
+            return;
+        };
+
+        // Columns count characters, lengths count bytes
+        let start = text
+            .char_indices()
+            .nth(col as usize)
+            .map_or(text.len(), |(i, _)| i);
+        let mut end = (start + len as usize).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        let display_width = |s: &str| -> usize {
+            s.chars()
+                .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+                .sum()
+        };
+
+        let mut marks = format!(
+            "{}{}",
+            " ".repeat(display_width(&text[..start])),
+            underline
+                .marker
+                .to_string()
+                .repeat(display_width(&text[start..end]).max(1))
+        );
+        if !underline.label.is_empty() {
+            marks = format!("{marks} {}", underline.label);
+        }
+
+        let _ = writeln!(out, "{gutter}");
+        let _ = writeln!(
+            out,
+            "{} {}",
+            self.paint(BLUE, &format!("{:>width$} |", line + 1)),
+            text.replace('\t', &" ".repeat(TAB_WIDTH))
+        );
+        let _ = writeln!(out, "{gutter} {}", self.paint(underline.style, &marks));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use xenomorph_common::diagnostic::codes;
+    use xenomorph_common::module::types::{ErrorPhase, RelatedLocation};
+
+    fn renderer(text: &str) -> Renderer<'_> {
+        let files = HashMap::from([(
+            "api/user",
+            SourceFile {
+                path: "api/user.xen".to_string(),
+                text,
+            },
+        )]);
+        Renderer::new(files, false)
+    }
+
+    #[test]
+    fn underlines_the_reported_range() {
+        let source = "type User = string;\n\ttype User = Nope;\n";
+        let err = ModuleError {
+            module_path: "api/user".to_string(),
+            message: "Duplicate declaration of 'User'".to_string(),
+            location: Some((1, 6, 4)),
+            phase: ErrorPhase::Analyzer,
+            severity: Severity::Error,
+            code: codes::DUPLICATE_DECLARATION,
+            related: vec![RelatedLocation {
+                module_path: "api/user".to_string(),
+                abs_path: PathBuf::new(),
+                location: (0, 5, 4),
+                message: "'User' is also declared here".to_string(),
+            }],
+        };
+
+        assert_eq!(
+            renderer(source).render(&err),
+            "\
+error[XEN0040]: Duplicate declaration of 'User'
+ --> api/user.xen:2:7
+  |
+2 |     type User = Nope;
+  |          ^^^^
+ ::: api/user.xen:1:6
+  |
+1 | type User = string;
+  |      ---- 'User' is also declared here
+
+"
+        );
+    }
+
+    #[test]
+    fn errors_without_location_only_name_the_file() {
+        let err = ModuleError {
+            module_path: "api/user".to_string(),
+            message: "Failed to read file".to_string(),
+            location: None,
+            phase: ErrorPhase::Module,
+            severity: Severity::Warning,
+            code: codes::FILE_ACCESS,
+            related: Vec::new(),
+        };
+
+        assert_eq!(
+            renderer("").render(&err),
+            "warning[XEN0050]: Failed to read file\n --> api/user.xen\n\n"
+        );
+    }
+}
@@ -1,77 +1,490 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::Parser as _;
 use xenomorph_common::config::{write_rc_schema, Config, RC_SCHEMA_RELATIVE_PATH};
-use xenomorph_common::module::XenoRegistry;
+use xenomorph_common::diagnostic::Severity;
+use xenomorph_common::formatter::format_xenomorph;
+use xenomorph_common::lexer::{Lexer, XenoTokens};
+use xenomorph_common::module::build_cache::{BuildCache, BUILD_CACHE_RELATIVE_PATH};
+use xenomorph_common::module::types::{ErrorPhase, ModuleError, ModulePath};
+use xenomorph_common::module::{ModuleData, XenoRegistry};
+use xenomorph_common::parser::Parser;
 use xenomorph_common::plugins::XenoPlugin;
 
-fn main() {
-    if std::env::args().nth(1).as_deref() == Some("schema") {
-        generate_rc_schema();
-        return;
-    }
+use crate::cli::{CheckFormat, Cli, Command, Verbosity};
+use crate::diagnostics::{Renderer, SourceFile};
+use crate::report::{Report, ReportSource};
+
+mod cli;
+mod diagnostics;
+mod report;
+mod watch;
+
+const INIT_CONFIG: &str = r#"#:schema .xenomorph/xenomorph.schema.json
+
+[parser]
+entry = "index"
+
+[plugins]
+path = ""
+plugins = []
+"#;
 
-    run_parser();
+const INIT_ENTRY: &str = r#"type User = {
+    name: string,
+    age: u8,
+};
+"#;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    Config::init(cli.global.overrides());
+    let verbosity = cli.global.verbosity();
+
+    match cli.command.unwrap_or(Command::Generate {
+        watch: false,
+        no_cache: false,
+    }) {
+        Command::Check { format } => run_check(format, verbosity),
+        Command::Generate { watch, no_cache } => run_generate(watch, no_cache, verbosity),
+        Command::Schema => generate_rc_schema(),
+        Command::Ast { file } => print_syntax(file, false),
+        Command::Tokens { file } => print_syntax(file, true),
+        Command::Fmt { check, files } => run_fmt(check, files, verbosity),
+        Command::Init => run_init(),
+    }
 }
 
 /// Generates the `xenomorph.toml` JSON Schema (base + plugin contributions) and
 /// writes it to `.xenomorph/xenomorph.schema.json` in the workspace root.
-fn generate_rc_schema() {
+fn generate_rc_schema() -> ExitCode {
     let plugins = XenoPlugin::get_plugins();
     let out_path = Config::get().workdir.join(RC_SCHEMA_RELATIVE_PATH);
 
     match write_rc_schema(plugins, &out_path) {
-        Ok(()) => println!("✓ Wrote xenomorph.toml schema → {}", out_path.display()),
+        Ok(()) => {
+            println!("✓ Wrote xenomorph.toml schema → {}", out_path.display());
+            ExitCode::SUCCESS
+        }
         Err(e) => {
             eprintln!("✗ Failed to write xenomorph.toml schema: {}", e);
-            std::process::exit(1);
+            ExitCode::FAILURE
         }
     }
 }
 
-fn run_parser() {
-    let reg = match XenoRegistry::load_workspace(true) {
+fn open_registry(generation_mode: bool, verbosity: Verbosity) -> Option<XenoRegistry> {
+    let reg = match XenoRegistry::new(generation_mode) {
         Ok(r) => r,
         Err(e) => {
-            for err in e {
-                eprintln!("[Error]: {}", err);
-            }
-            std::process::exit(1);
+            eprintln!("{}", e);
+            return None;
+        }
+    };
+
+    if verbosity == Verbosity::Verbose {
+        eprintln!("Workspace root: {}", reg.root.display());
+        eprintln!("Entry module: {}", reg.entry);
+        for plugin in reg.plugins {
+            eprintln!("Loaded plugin {} {}", plugin.name, plugin.version);
+        }
+    }
+    Some(reg)
+}
+
+/// Loads the entry module and everything it imports, returns the diagnostics of all of them.
+fn load_workspace(reg: &XenoRegistry, verbosity: Verbosity) -> Vec<ModuleError> {
+    let started = Instant::now();
+    let diagnostics = reg.load_module_tree(&[&reg.entry]);
+    if verbosity == Verbosity::Verbose {
+        eprintln!(
+            "Loaded {} module(s) in {:.2?}",
+            reg.module_cache.blocking_read().len(),
+            started.elapsed()
+        );
+    }
+    print_debug_output(reg);
+    diagnostics
+}
+
+/// Prints the tokens and syntax trees of every loaded module when `[debug]` asks for them.
+fn print_debug_output(reg: &XenoRegistry) {
+    let debug = &Config::get().debug;
+    if !debug.tokens && !debug.ast {
+        return;
+    }
+
+    let cache = reg.module_cache.blocking_read();
+    let mut modules: Vec<_> = cache.iter().collect();
+    modules.sort_by_key(|(path, _)| path.as_str());
+    for (path, module) in modules {
+        if debug.tokens {
+            eprintln!("── tokens: {} ──", path);
+            eprint!("{}", format_tokens(module.borrow_tokens()));
         }
+        if debug.ast {
+            eprintln!("── ast: {} ──", path);
+            eprintln!("{:#?}", module.borrow_ast());
+        }
+    }
+}
+
+/// One token per line as `line:column Variant "value"`, 1-based.
+fn format_tokens(tokens: &XenoTokens) -> String {
+    let mut out = String::new();
+    for (variant, data) in tokens {
+        let _ = writeln!(
+            out,
+            "{}:{}\t{}\t{:?}",
+            data.l + 1,
+            data.c + 1,
+            variant,
+            data.v
+        );
+    }
+    out
+}
+
+fn run_generate(watch: bool, no_cache: bool, verbosity: Verbosity) -> ExitCode {
+    let Some(mut reg) = open_registry(true, verbosity) else {
+        return ExitCode::FAILURE;
+    };
+    let use_cache = !no_cache;
+    if use_cache {
+        let path = Config::get().workdir.join(BUILD_CACHE_RELATIVE_PATH);
+        reg.build_cache = BuildCache::load(&path, reg.plugins, &Config::get().plugins.config);
+        if verbosity == Verbosity::Verbose && reg.build_cache.is_some() {
+            eprintln!("Using build cache {}", path.display());
+        }
+    }
+
+    let diagnostics = load_workspace(&reg, verbosity);
+    save_build_cache(&mut reg, use_cache);
+    let has_errors = print_diagnostics(&reg, &diagnostics, verbosity);
+    if watch {
+        return watch::watch(&mut reg, use_cache, verbosity);
+    }
+    if has_errors {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Records what the generators ran on, and uses it for the next analysis too with `use_cache`.
+/// The cache is written even with `--no-cache`, every module was generated after all.
+fn save_build_cache(reg: &mut XenoRegistry, use_cache: bool) {
+    let build_cache = reg.snapshot_build_cache();
+    let path = Config::get().workdir.join(BUILD_CACHE_RELATIVE_PATH);
+    if let Err(e) = build_cache.save(&path) {
+        eprintln!("✗ Failed to write build cache '{}': {}", path.display(), e);
+    }
+    if use_cache {
+        reg.build_cache = Some(build_cache);
+    }
+}
+
+/// Validates the workspace without generating anything and reports every
+/// diagnostic stored in the module cache.
+fn run_check(format: CheckFormat, verbosity: Verbosity) -> ExitCode {
+    let Some(reg) = open_registry(false, verbosity) else {
+        return ExitCode::FAILURE;
     };
+    let loaded = load_workspace(&reg, verbosity);
 
     let cache = reg.module_cache.blocking_read();
-    let module_count = cache.len();
-    let total_errors: usize = cache
-        .values()
-        .map(|m| {
-            m.borrow_lexer_errors().len()
-                + m.borrow_parser_errors().len()
-                + m.borrow_analyzer_errors().len()
-                + m.borrow_module_errors().len()
+    let mut modules: Vec<_> = cache.iter().collect();
+    modules.sort_by_key(|(path, _)| path.as_str());
+    let mut diagnostics: Vec<ModuleError> = modules
+        .iter()
+        .flat_map(|(_, module)| {
+            module
+                .borrow_lexer_errors()
+                .iter()
+                .chain(module.borrow_parser_errors())
+                .chain(module.borrow_analyzer_errors())
+                .chain(module.borrow_module_errors())
+                .cloned()
         })
-        .sum();
+        .collect();
+    // Modules that couldn't be read never make it into the cache
+    diagnostics.extend(
+        loaded
+            .into_iter()
+            .filter(|d| !cache.contains_key(&d.module_path)),
+    );
 
-    for module in cache.values() {
-        let path = module.borrow_module_path();
-        let decl_count = module.borrow_declarations().len();
-        let errors: Vec<_> = module
-            .borrow_analyzer_errors()
-            .iter()
-            .chain(module.borrow_parser_errors())
-            .chain(module.borrow_lexer_errors())
-            .chain(module.borrow_module_errors())
-            .collect();
-
-        if errors.is_empty() {
-            println!("✓ {} ({} declarations)", path, decl_count);
-        } else {
-            eprintln!("✗ {} ({} errors)", path, errors.len());
-            for err in &errors {
-                eprintln!("  └ {}", err);
+    let has_errors = match format {
+        CheckFormat::Text => {
+            drop(cache);
+            print_diagnostics(&reg, &diagnostics, verbosity)
+        }
+        CheckFormat::Json | CheckFormat::Sarif => {
+            let sources = modules
+                .iter()
+                .map(|(module_path, module)| {
+                    (
+                        module_path.as_str(),
+                        ReportSource {
+                            abs_path: module.borrow_abs_path(),
+                            text: module.borrow_source(),
+                        },
+                    )
+                })
+                .collect();
+            let report = Report::new(&Config::get().workdir, sources);
+            let output = match format {
+                CheckFormat::Sarif => report.sarif(&diagnostics),
+                _ => report.json(&diagnostics),
+            };
+            println!("{:#}", output);
+            diagnostics.iter().any(|d| d.severity == Severity::Error)
+        }
+    };
+
+    if has_errors {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints the tokens or the syntax tree of a single file, without resolving its imports.
+fn print_syntax(file: Option<PathBuf>, tokens_only: bool) -> ExitCode {
+    let config = Config::get();
+    let path = file.unwrap_or_else(|| {
+        let mut entry = config.workdir.join(&config.parser.entry);
+        entry.add_extension("xen");
+        entry
+    });
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("✗ Failed to read '{}': {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let display_path = path.display().to_string();
+    let (tokens, lexer_errors) = Lexer::tokenize(&source);
+    let mut errors: Vec<ModuleError> = lexer_errors
+        .iter()
+        .map(|e| ModuleError::from_xeno(&display_path, &path, ErrorPhase::Lexer, e))
+        .collect();
+    if tokens_only {
+        print!("{}", format_tokens(&tokens));
+    } else {
+        let (ast, parser_errors) = Parser::parse(&tokens);
+        errors.extend(
+            parser_errors
+                .iter()
+                .map(|e| ModuleError::from_xeno(&display_path, &path, ErrorPhase::Parser, e)),
+        );
+        println!("{:#?}", ast);
+    }
+
+    let files = HashMap::from([(
+        display_path.as_str(),
+        SourceFile {
+            path: display_path.clone(),
+            text: &source,
+        },
+    )]);
+    let renderer = Renderer::new(files, use_color());
+    for error in &errors {
+        eprint!("{}", renderer.render(error));
+    }
+    if errors.iter().any(|e| e.severity == Severity::Error) {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Every `.xen` file under `dir`, skipping hidden directories and build output.
+fn collect_xen_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" && name != "node_modules" {
+                collect_xen_files(&path, files);
             }
+        } else if path.extension().is_some_and(|ext| ext == "xen") {
+            files.push(path);
         }
     }
+}
+
+fn run_fmt(check: bool, files: Vec<PathBuf>, verbosity: Verbosity) -> ExitCode {
+    let files = if files.is_empty() {
+        let mut files = Vec::new();
+        collect_xen_files(&Config::get().workdir, &mut files);
+        files.sort();
+        files
+    } else {
+        files
+    };
+
+    let mut failed = false;
+    for path in &files {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("✗ Failed to read '{}': {}", path.display(), e);
+                failed = true;
+                continue;
+            }
+        };
+        let formatted = format_xenomorph(&source, &Config::get().format);
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            println!("✗ {} is not formatted", path.display());
+            failed = true;
+        } else if let Err(e) = fs::write(path, formatted) {
+            eprintln!("✗ Failed to write '{}': {}", path.display(), e);
+            failed = true;
+        } else if verbosity != Verbosity::Quiet {
+            println!("✓ Formatted {}", path.display());
+        }
+    }
+
+    if failed {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Creates a minimal workspace: `xenomorph.toml`, its schema and an entry module.
+fn run_init() -> ExitCode {
+    let workdir = &Config::get().workdir;
+    let config_path = workdir.join("xenomorph.toml");
+    if config_path.exists() {
+        eprintln!("✗ {} already exists", config_path.display());
+        return ExitCode::FAILURE;
+    }
+
+    let entry_path = workdir.join("index.xen");
+    let written = fs::create_dir_all(workdir)
+        .and_then(|()| fs::write(&config_path, INIT_CONFIG))
+        .and_then(|()| {
+            if entry_path.exists() {
+                return Ok(());
+            }
+            fs::write(&entry_path, INIT_ENTRY)
+        });
+    if let Err(e) = written {
+        eprintln!("✗ Failed to initialize workspace: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    println!("✓ Created {}", config_path.display());
+    println!("✓ Entry module → {}", entry_path.display());
+    generate_rc_schema()
+}
+
+fn use_color() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Renders diagnostics to stderr with the source of loaded modules, quiet runs only render errors.
+fn render_diagnostics<'d>(
+    cache: &HashMap<ModulePath, ModuleData>,
+    diagnostics: impl IntoIterator<Item = &'d ModuleError>,
+    verbosity: Verbosity,
+) {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let files = cache
+        .iter()
+        .map(|(module_path, module)| {
+            let abs_path = module.borrow_abs_path();
+            let path = abs_path.strip_prefix(&cwd).unwrap_or(abs_path);
+            (
+                module_path.as_str(),
+                SourceFile {
+                    path: path.display().to_string(),
+                    text: module.borrow_source(),
+                },
+            )
+        })
+        .collect();
+    let renderer = Renderer::new(files, use_color());
+
+    for diagnostic in diagnostics {
+        if verbosity != Verbosity::Quiet || diagnostic.severity == Severity::Error {
+            eprint!("{}", renderer.render(diagnostic));
+        }
+    }
+}
+
+/// Renders diagnostics to stderr and a per-module summary to stdout.
+/// Returns whether any of them is an error. Quiet runs only render errors and skip the summary.
+fn print_diagnostics(
+    reg: &XenoRegistry,
+    diagnostics: &[ModuleError],
+    verbosity: Verbosity,
+) -> bool {
+    let cache = reg.module_cache.blocking_read();
+
+    // Diagnostics grouped by module, modules without any are listed in the summary too
+    let mut by_module: BTreeMap<&str, Vec<&ModuleError>> = cache
+        .keys()
+        .map(|path| (path.as_str(), Vec::new()))
+        .collect();
+    for diagnostic in diagnostics {
+        by_module
+            .entry(diagnostic.module_path.as_str())
+            .or_default()
+            .push(diagnostic);
+    }
+
+    render_diagnostics(&cache, by_module.values().flatten().copied(), verbosity);
+    let has_errors = diagnostics.iter().any(|d| d.severity == Severity::Error);
+    if verbosity == Verbosity::Quiet {
+        return has_errors;
+    }
+
+    let mut total_errors = 0;
+    let mut total_warnings = 0;
+    println!("Summary:");
+    for (path, diagnostics) in &by_module {
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count();
+        let warnings = diagnostics.len() - errors;
+        total_errors += errors;
+        total_warnings += warnings;
+
+        let declarations = cache
+            .get(*path)
+            .map_or(0, |m| m.borrow_declarations().len());
+        let mark = match (errors, warnings) {
+            (0, 0) => "✓",
+            (0, _) => "⚠",
+            _ => "✗",
+        };
+        println!(
+            "  {} {} ({} declarations, {} error(s), {} warning(s))",
+            mark, path, declarations, errors, warnings
+        );
+    }
 
     println!(
-        "\n{} module(s) processed, {} error(s)",
-        module_count, total_errors
+        "\n{} module(s) processed, {} error(s), {} warning(s)",
+        cache.len(),
+        total_errors,
+        total_warnings
     );
+    total_errors > 0
 }
@@ -0,0 +1,271 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use serde_json::{json, Value};
+use xenomorph_common::diagnostic::Severity;
+use xenomorph_common::module::types::ModuleError;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// A loaded module as seen by machine-readable reports.
+pub struct ReportSource<'a> {
+    pub abs_path: &'a Path,
+    pub text: &'a str,
+}
+
+/// Serialises diagnostics as JSON or SARIF 2.1.0.
+/// Lines and columns are 1-based, range ends are exclusive.
+pub struct Report<'a> {
+    root: &'a Path,
+    /// Sources keyed by module path, modules that failed to load have none.
+    sources: HashMap<&'a str, ReportSource<'a>>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(root: &'a Path, sources: HashMap<&'a str, ReportSource<'a>>) -> Self {
+        Self { root, sources }
+    }
+
+    /// `(start line, start column, end line, end column)` of a reported location.
+    /// Columns count characters, lengths count bytes and are clamped to the line.
+    fn range(&self, module_path: &str, (line, col, len): (u32, u32, u32)) -> (u32, u32, u32, u32) {
+        let start = col + 1;
+        let Some(text) = self
+            .sources
+            .get(module_path)
+            .and_then(|s| s.text.lines().nth(line as usize))
+        else {
+            return (line + 1, start, line + 1, start + len);
+        };
+
+        let from = text
+            .char_indices()
+            .nth(col as usize)
+            .map_or(text.len(), |(i, _)| i);
+        let mut to = (from + len as usize).min(text.len());
+        while !text.is_char_boundary(to) {
+            to += 1;
+        }
+        let width = text[from..to].chars().count() as u32;
+        (line + 1, start, line + 1, start + width)
+    }
+
+    fn json_range(&self, module_path: &str, location: (u32, u32, u32)) -> Value {
+        let (start_line, start_column, end_line, end_column) = self.range(module_path, location);
+        json!({
+            "start": { "line": start_line, "column": start_column },
+            "end": { "line": end_line, "column": end_column },
+        })
+    }
+
+    fn abs_path(&self, module_path: &str) -> Option<String> {
+        self.sources
+            .get(module_path)
+            .map(|s| s.abs_path.display().to_string())
+    }
+
+    /// Path of a module relative to the workspace root, with `/` separators.
+    fn relative_uri(&self, module_path: &str) -> String {
+        self.sources
+            .get(module_path)
+            .and_then(|s| s.abs_path.strip_prefix(self.root).ok())
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|| format!("{module_path}.xen"))
+    }
+
+    pub fn json(&self, diagnostics: &[ModuleError]) -> Value {
+        let entries: Vec<Value> = diagnostics
+            .iter()
+            .map(|d| {
+                json!({
+                    "module": d.module_path,
+                    "file": self.abs_path(&d.module_path),
+                    "phase": d.phase.to_string(),
+                    "severity": d.severity.to_string(),
+                    "code": d.code.to_string(),
+                    "message": d.message,
+                    "range": d.location.map(|l| self.json_range(&d.module_path, l)),
+                    "related": d.related.iter().map(|r| json!({
+                        "module": r.module_path,
+                        "file": r.abs_path.display().to_string(),
+                        "message": r.message,
+                        "range": self.json_range(&r.module_path, r.location),
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let count = |severity: Severity| {
+            diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count()
+        };
+        json!({
+            "diagnostics": entries,
+            "summary": {
+                "modules": self.sources.len(),
+                "error": count(Severity::Error),
+                "warning": count(Severity::Warning),
+                "info": count(Severity::Info),
+                "hint": count(Severity::Hint),
+            },
+        })
+    }
+
+    fn sarif_location(&self, module_path: &str, location: Option<(u32, u32, u32)>) -> Value {
+        let mut physical = json!({
+            "artifactLocation": {
+                "uri": self.relative_uri(module_path),
+                "uriBaseId": "%SRCROOT%",
+            },
+        });
+        if let Some(location) = location {
+            let (start_line, start_column, end_line, end_column) =
+                self.range(module_path, location);
+            physical["region"] = json!({
+                "startLine": start_line,
+                "startColumn": start_column,
+                "endLine": end_line,
+                "endColumn": end_column,
+            });
+        }
+        json!({ "physicalLocation": physical })
+    }
+
+    pub fn sarif(&self, diagnostics: &[ModuleError]) -> Value {
+        let rules: BTreeSet<String> = diagnostics.iter().map(|d| d.code.to_string()).collect();
+        let results: Vec<Value> = diagnostics
+            .iter()
+            .map(|d| {
+                let related: Vec<Value> = d
+                    .related
+                    .iter()
+                    .enumerate()
+                    .map(|(id, r)| {
+                        let mut location = self.sarif_location(&r.module_path, Some(r.location));
+                        location["id"] = json!(id);
+                        location["message"] = json!({ "text": r.message });
+                        location
+                    })
+                    .collect();
+                json!({
+                    "ruleId": d.code.to_string(),
+                    "level": match d.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                        Severity::Info | Severity::Hint => "note",
+                    },
+                    "message": { "text": d.message },
+                    "locations": [self.sarif_location(&d.module_path, d.location)],
+                    "relatedLocations": related,
+                })
+            })
+            .collect();
+
+        let root = self.root.to_string_lossy().replace('\\', "/");
+        let root_uri = format!(
+            "file://{}{}/",
+            if root.starts_with('/') { "" } else { "/" },
+            root.trim_end_matches('/')
+        );
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "xenomorph",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": "https://github.com/WingSMC/xenomorph",
+                        "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                    },
+                },
+                "originalUriBaseIds": {
+                    "%SRCROOT%": { "uri": root_uri },
+                },
+                "results": results,
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use xenomorph_common::diagnostic::codes;
+    use xenomorph_common::module::types::{ErrorPhase, RelatedLocation};
+
+    fn diagnostics() -> Vec<ModuleError> {
+        vec![ModuleError {
+            module_path: "api/user".to_string(),
+            message: "Duplicate declaration of 'Ü'".to_string(),
+            location: Some((1, 5, 2)),
+            phase: ErrorPhase::Analyzer,
+            severity: Severity::Warning,
+            code: codes::DUPLICATE_DECLARATION,
+            related: vec![RelatedLocation {
+                module_path: "api/user".to_string(),
+                abs_path: PathBuf::from("/ws/api/user.xen"),
+                location: (0, 5, 2),
+                message: "'Ü' is also declared here".to_string(),
+            }],
+        }]
+    }
+
+    fn with_report(f: impl FnOnce(&Report)) {
+        let root = PathBuf::from("/ws");
+        let abs_path = PathBuf::from("/ws/api/user.xen");
+        let sources = HashMap::from([(
+            "api/user",
+            ReportSource {
+                abs_path: &abs_path,
+                text: "type Ü = u8;\ntype Ü = u16;\n",
+            },
+        )]);
+        f(&Report::new(&root, sources));
+    }
+
+    #[test]
+    fn json_reports_ranges_in_characters() {
+        with_report(|report| {
+            let json = report.json(&diagnostics());
+            let d = &json["diagnostics"][0];
+            assert_eq!(d["code"], "XEN0040");
+            assert_eq!(d["phase"], "analyzer");
+            assert_eq!(d["severity"], "warning");
+            assert_eq!(d["file"], "/ws/api/user.xen");
+            assert_eq!(
+                d["range"],
+                json!({ "start": { "line": 2, "column": 6 }, "end": { "line": 2, "column": 7 } })
+            );
+            assert_eq!(d["related"][0]["range"]["start"]["line"], 1);
+            assert_eq!(json["summary"]["warning"], 1);
+            assert_eq!(json["summary"]["error"], 0);
+        });
+    }
+
+    #[test]
+    fn sarif_results_use_workspace_relative_uris() {
+        with_report(|report| {
+            let sarif = report.sarif(&diagnostics());
+            let run = &sarif["runs"][0];
+            assert_eq!(sarif["version"], "2.1.0");
+            assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "XEN0040");
+            assert_eq!(run["originalUriBaseIds"]["%SRCROOT%"]["uri"], "file:///ws/");
+
+            let result = &run["results"][0];
+            assert_eq!(result["level"], "warning");
+            let location = &result["locations"][0]["physicalLocation"];
+            assert_eq!(location["artifactLocation"]["uri"], "api/user.xen");
+            assert_eq!(location["region"]["startColumn"], 6);
+            assert_eq!(location["region"]["endColumn"], 7);
+            assert_eq!(
+                result["relatedLocations"][0]["message"]["text"],
+                "'Ü' is also declared here"
+            );
+        });
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use xenomorph_common::diagnostic::Severity;
+use xenomorph_common::module::types::{ModuleError, RefreshSummary};
+use xenomorph_common::module::XenoRegistry;
+
+use crate::cli::Verbosity;
+
+/// Changes arriving this close to each other are handled together,
+/// editors often save a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Regenerates the modules affected by every change to a `.xen` file in the workspace.
+/// Only returns if the watcher can't be started or stops.
+pub fn watch(reg: &mut XenoRegistry, use_cache: bool, verbosity: Verbosity) -> ExitCode {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("✗ Failed to start watching: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(e) = watcher.watch(&reg.root, RecursiveMode::Recursive) {
+        eprintln!("✗ Failed to watch '{}': {}", reg.root.display(), e);
+        return ExitCode::FAILURE;
+    }
+    if verbosity != Verbosity::Quiet {
+        println!("\nWatching {} for changes...", reg.root.display());
+    }
+
+    while let Ok(event) = rx.recv() {
+        let mut paths = BTreeSet::new();
+        collect_paths(event, &mut paths);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_paths(event, &mut paths);
+        }
+        if paths.is_empty() {
+            continue;
+        }
+
+        let started = Instant::now();
+        let paths: Vec<PathBuf> = paths.into_iter().collect();
+        let summary = reg.refresh(&paths);
+        crate::save_build_cache(reg, use_cache);
+        report(reg, &summary, started.elapsed(), verbosity);
+    }
+
+    eprintln!("✗ Stopped watching {}", reg.root.display());
+    ExitCode::FAILURE
+}
+
+/// Adds the `.xen` files created, modified or removed by `event`.
+fn collect_paths(event: notify::Result<Event>, paths: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) => {
+            if matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                paths.extend(
+                    event
+                        .paths
+                        .into_iter()
+                        .filter(|p| p.extension().is_some_and(|ext| ext == "xen")),
+                );
+            }
+        }
+        Err(e) => eprintln!("✗ Watch error: {}", e),
+    }
+}
+
+/// Prints one line per batch of changes, followed by the diagnostics of the affected modules.
+fn report(reg: &XenoRegistry, summary: &RefreshSummary, took: Duration, verbosity: Verbosity) {
+    if summary.changed.is_empty() && summary.removed.is_empty() {
+        return;
+    }
+
+    let mut diagnostics: Vec<&ModuleError> = summary.errors.iter().collect();
+    diagnostics.sort_by(|a, b| a.module_path.cmp(&b.module_path));
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+
+    if verbosity != Verbosity::Quiet {
+        let mut parts = Vec::new();
+        if !summary.changed.is_empty() {
+            parts.push(format!("changed {}", summary.changed.join(", ")));
+        }
+        if !summary.removed.is_empty() {
+            parts.push(format!("removed {}", summary.removed.join(", ")));
+        }
+        if !summary.dependents.is_empty() {
+            parts.push(format!("re-analyzed {}", summary.dependents.join(", ")));
+        }
+        let mark = match (errors, diagnostics.len()) {
+            (0, 0) => "✓",
+            (0, _) => "⚠",
+            _ => "✗",
+        };
+        println!(
+            "{} {} ({} error(s), {} warning(s) in {:.2?})",
+            mark,
+            parts.join("; "),
+            errors,
+            diagnostics.len() - errors,
+            took
+        );
+    }
+
+    let cache = reg.module_cache.blocking_read();
+    crate::render_diagnostics(&cache, diagnostics, verbosity);
+}
@@ -4,11 +4,13 @@ use std::path::PathBuf;
 
 use serde_json::{json, Map, Value};
 use xenomorph_common::config::{ConfigValue, PluginConfigs};
+use xenomorph_common::lexer::Lexer;
 use xenomorph_common::parser::{
-    AnonymType, BinaryExprType, Declaration, Expr, KeyValExpr, Literal, NumberType,
+    AnonymType, BinaryExprType, Declaration, Expr, KeyValExpr, Literal, NumberType, Parser,
 };
 use xenomorph_common::plugins::XenoPlugin;
-use xenomorph_common::semantic::{AnalyzerListener, ScopeInfo};
+use xenomorph_common::semantic::{AnalyzerListener, ImportedType, ScopeInfo};
+use xenomorph_common::utils::calculate_hash;
 
 // ── Plugin registration ─────────────────────────────────────────────
 
@@ -21,6 +23,7 @@ static PLUGIN: XenoPlugin = XenoPlugin {
     provide_types: None,
     provide_annotations: None,
     provide_config_schema: Some(provide_config_schema),
+    documentation: None,
     register_generator: Some(create_generator),
     register_analyzer: None,
 };
@@ -62,6 +65,23 @@ struct JsonSchemaGenerator {
     output_dir: Option<PathBuf>,
     /// Imported types keyed by module path, for resolving `$ref` targets.
     imported_types: HashMap<String, Vec<String>>,
+    /// Declaring module and name of every imported name, with aliases and re-exports resolved.
+    imports: HashMap<String, ImportedType>,
+    /// The schema file written for the module.
+    written: Option<PathBuf>,
+    /// Scope of the current module, member paths are resolved through it.
+    scope: Option<ScopeInfo>,
+    /// Generic declarations of the module and of the modules it can apply them from, keyed by
+    /// declaring module and name, with their type parameters, as schemas whose parameters are
+    /// still placeholders.
+    templates: HashMap<(String, String), (Vec<String>, Value)>,
+    /// Applications of generic types, keyed by their name like `Page_User`, written along `defs`.
+    instances: Map<String, Value>,
+    /// Type parameters of the declaration currently being converted.
+    type_params: Vec<String>,
+    /// Module declaring the declaration currently being converted when it isn't this one,
+    /// the names it uses are resolved there.
+    context: Option<String>,
 }
 
 impl JsonSchemaGenerator {
@@ -72,6 +92,13 @@ impl JsonSchemaGenerator {
             module_path: String::new(),
             output_dir: None,
             imported_types: HashMap::new(),
+            imports: HashMap::new(),
+            written: None,
+            scope: None,
+            templates: HashMap::new(),
+            instances: Map::new(),
+            type_params: Vec::new(),
+            context: None,
         }
     }
 
@@ -88,13 +115,57 @@ impl JsonSchemaGenerator {
     /// Builds a `$ref` value pointing at a named type, resolving cross-module
     /// references to a relative `.schema.json` file path.
     fn ref_for(&self, name: &str) -> Value {
-        match self.provider_of(name) {
-            Some(provider) => {
-                let rel = schema_ref_path(&self.module_path, provider);
-                json!({ "$ref": format!("{rel}#/$defs/{name}") })
-            }
-            None => json!({ "$ref": format!("#/$defs/{name}") }),
+        json!({ "$ref": self.ref_path_for(name) })
+    }
+
+    fn ref_path_for(&self, name: &str) -> String {
+        let (module_path, declared) = self.declaration_of(name);
+        let rel = schema_ref_path(&self.module_path, &module_path);
+        format!("{rel}#/$defs/{declared}")
+    }
+
+    /// Declaring module and name of a type named `name` in the declaration being converted.
+    fn declaration_of(&self, name: &str) -> (String, String) {
+        if let Some(context) = &self.context {
+            let declaration = self
+                .scope
+                .as_ref()
+                .and_then(|scope| scope.field_scopes.get(context)?.get(name));
+            return match declaration {
+                Some(d) => (d.module_path.clone(), d.name.clone()),
+                None => (context.clone(), name.to_string()),
+            };
         }
+        self.imports
+            .get(name)
+            .map(|i| (i.module_path.clone(), i.name.clone()))
+            .or_else(|| {
+                self.provider_of(name)
+                    .map(|provider| (provider.to_string(), name.to_string()))
+            })
+            .unwrap_or_else(|| (self.module_path.clone(), name.to_string()))
+    }
+
+    /// `User._id` points into the properties of the `User` definition, `Order.customer._id`
+    /// into those of the definition of the type of `customer`, wherever it's declared.
+    fn member_to_schema(&self, base: &str, members: &[&str]) -> Value {
+        let resolved = self
+            .scope
+            .as_ref()
+            .and_then(|scope| scope.resolve_member_declaration(base, members));
+        let path = match resolved {
+            Some((owner, field)) => {
+                let rel = schema_ref_path(&self.module_path, &owner.module_path);
+                format!("{rel}#/$defs/{}/properties/{}", owner.name, field.name)
+            }
+            // Unknown members are reported by the analyzer, keep the path as written
+            None => members
+                .iter()
+                .fold(self.ref_path_for(base), |path, member| {
+                    format!("{path}/properties/{member}")
+                }),
+        };
+        json!({ "$ref": path })
     }
 }
 
@@ -111,7 +182,36 @@ impl<'src> AnalyzerListener<'src> for JsonSchemaGenerator {
         self.abs_path = scope.abs_path.clone();
         self.module_path = scope.module_path.clone();
         self.imported_types = scope.imported_types.clone();
+        self.imports = scope
+            .imports
+            .keys()
+            .filter_map(|local| {
+                let d = scope.declarations.get(local)?;
+                Some((
+                    local.clone(),
+                    ImportedType {
+                        module_path: d.module_path.clone(),
+                        name: d.name.clone(),
+                    },
+                ))
+            })
+            .collect();
+        self.scope = Some(scope.clone());
+        self.templates.clear();
+        self.instances.clear();
         self.defs.clear();
+
+        // Generic types of other modules are converted where they're declared
+        for (module_path, sources) in &scope.generic_declarations {
+            self.context = Some(module_path.clone());
+            for (name, source) in sources {
+                if let Some(template) = self.template_of(source) {
+                    self.templates
+                        .insert((module_path.clone(), name.clone()), template);
+                }
+            }
+        }
+        self.context = None;
     }
 
     fn on_before_ast(
@@ -120,11 +220,56 @@ impl<'src> AnalyzerListener<'src> for JsonSchemaGenerator {
         _errors: &mut Vec<xenomorph_common::XenoError<'src>>,
     ) {
         for decl in ast {
-            if let Declaration::TypeDecl { docs, name, t } = decl {
-                let schema = self.type_decl_to_schema(docs, name.v, t);
-                self.defs.insert(name.v.to_string(), schema);
+            match decl {
+                Declaration::TypeDecl {
+                    docs,
+                    name,
+                    generics,
+                    t,
+                } => {
+                    self.type_params = generics.iter().map(|g| g.v.to_string()).collect();
+                    let schema = self.type_decl_to_schema(docs, name.v, t);
+                    if !self.type_params.is_empty() {
+                        let params = std::mem::take(&mut self.type_params);
+                        let key = (self.module_path.clone(), name.v.to_string());
+                        self.templates.insert(key, (params, schema.clone()));
+                    }
+                    self.defs.insert(name.v.to_string(), schema);
+                }
+                // Re-exported types get a definition pointing at the original one
+                Declaration::Import {
+                    path,
+                    exported: true,
+                    ..
+                } => {
+                    for local in self
+                        .imported_types
+                        .get(&path.join("/"))
+                        .into_iter()
+                        .flatten()
+                    {
+                        self.defs.insert(local.clone(), self.ref_for(local));
+                    }
+                }
+                _ => {}
             }
+            self.type_params.clear();
         }
+
+        // Applications are instantiated once every generic declaration of the module is known,
+        // parameters left unbound (those of the generic declarations themselves) accept anything
+        let mut defs = Map::new();
+        for (name, schema) in std::mem::take(&mut self.defs) {
+            let schema = self.expand(schema, &mut Vec::new());
+            defs.insert(name, substitute(schema, &HashMap::new()));
+        }
+        for (name, instance) in std::mem::take(&mut self.instances) {
+            defs.entry(name).or_insert(instance);
+        }
+        self.defs = defs
+            .into_iter()
+            .map(|(name, schema)| (name, without_names(schema)))
+            .collect();
     }
 
     fn on_after_module(&mut self, scope: &ScopeInfo) {
@@ -156,12 +301,35 @@ impl<'src> AnalyzerListener<'src> for JsonSchemaGenerator {
             Ok(_) => println!("✓ {} → {}", scope.module_path, out_path.display()),
             Err(e) => eprintln!("✗ {} — failed to write: {}", scope.module_path, e),
         }
+        self.written = Some(out_path);
+    }
+
+    fn outputs(&self) -> Vec<PathBuf> {
+        self.written.iter().cloned().collect()
     }
 }
 
 // ── Type declaration → schema ───────────────────────────────────────
 
 impl JsonSchemaGenerator {
+    /// Type parameters and schema of the generic declaration in `source`.
+    fn template_of(&mut self, source: &str) -> Option<(Vec<String>, Value)> {
+        let (tokens, _) = Lexer::tokenize(source);
+        let (ast, _) = Parser::parse(&tokens);
+        let Some(Declaration::TypeDecl {
+            docs,
+            name,
+            generics,
+            t,
+        }) = ast.first()
+        else {
+            return None;
+        };
+        self.type_params = generics.iter().map(|g| g.v.to_string()).collect();
+        let schema = self.type_decl_to_schema(docs, name.v, t);
+        Some((std::mem::take(&mut self.type_params), schema))
+    }
+
     fn type_decl_to_schema(&self, docs: &Option<&str>, name: &str, t: &[Expr]) -> Value {
         let mut schema = self.anonym_type_to_schema(t);
 
@@ -210,6 +378,13 @@ impl JsonSchemaGenerator {
                 "type": "array",
                 "items": self.identifier_to_schema(type_ident.v),
             }),
+            Expr::TypeApplication(type_ident, args) => {
+                self.application_to_schema(type_ident.v, args)
+            }
+            Expr::MemberAccess(base, members) => {
+                let members: Vec<&str> = members.iter().map(|m| m.v).collect();
+                self.member_to_schema(base.v, &members)
+            }
             Expr::List(inner) => self.list_to_schema(inner),
             Expr::Set(inner) => self.set_to_schema(inner),
             Expr::Struct(fields) => self.struct_to_schema(fields),
@@ -219,12 +394,115 @@ impl JsonSchemaGenerator {
     }
 
     fn identifier_to_schema(&self, name: &str) -> Value {
+        if self.type_params.iter().any(|p| p == name) {
+            return placeholder(PARAM, name);
+        }
         match builtin_to_schema(name) {
             Some(schema) => schema,
             None => self.ref_for(name),
         }
     }
 
+    /// `dict<K, V>` becomes a map of `V`, any other application is a placeholder
+    /// that `expand` replaces with the generic type's instance once it's known.
+    fn application_to_schema(&self, name: &str, args: &[AnonymType]) -> Value {
+        let arg_schemas: Vec<Value> = args
+            .iter()
+            .map(|a| {
+                let mut schema = self.anonym_type_to_schema(a);
+                // Builtin arguments name the instance, they have no definition to take it from
+                if let ([Expr::Identifier(id)], Value::Object(map)) = (a.as_slice(), &mut schema) {
+                    if builtin_to_schema(id.v).is_some() {
+                        map.insert(ARG_NAME.to_string(), json!(id.v));
+                    }
+                }
+                schema
+            })
+            .collect();
+
+        if name == "dict" {
+            return match arg_schemas.get(1) {
+                Some(value) => json!({ "type": "object", "additionalProperties": value }),
+                None => json!({ "type": "object" }),
+            };
+        }
+
+        let (module_path, declared) = self.declaration_of(name);
+        let mut schema = placeholder(APPLY, &declared);
+        if let Value::Object(map) = &mut schema {
+            map.insert(MODULE.to_string(), json!(module_path));
+            map.insert(ARGS.to_string(), Value::Array(arg_schemas));
+        }
+        schema
+    }
+
+    /**
+    Replaces application placeholders with references to instances of the generic declaration,
+    its parameters substituted by the arguments, which are added to `instances` the first time.
+    Applications with unbound parameters, those in generic declarations themselves, reference
+    the declaration instead, as do the ones of a generic type that would grow forever.
+    */
+    fn expand(&mut self, schema: Value, expanding: &mut Vec<(String, String)>) -> Value {
+        match schema {
+            Value::Object(mut map) => {
+                let Some(Value::String(name)) = map.remove(APPLY) else {
+                    return Value::Object(
+                        map.into_iter()
+                            .map(|(key, value)| (key, self.expand(value, expanding)))
+                            .collect(),
+                    );
+                };
+                let module_path = match map.remove(MODULE) {
+                    Some(Value::String(module_path)) => module_path,
+                    _ => self.module_path.clone(),
+                };
+                let args: Vec<Value> = match map.remove(ARGS) {
+                    Some(Value::Array(args)) => args
+                        .into_iter()
+                        .map(|a| self.expand(a, expanding))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                let instance = instance_name(&name, &args);
+                let key = (module_path, name);
+                let path = match self.templates.get(&key).cloned() {
+                    Some(_) if self.instances.contains_key(&instance) => {
+                        format!("#/$defs/{instance}")
+                    }
+                    Some((params, template))
+                        if !args.iter().any(has_params) && !expanding.contains(&key) =>
+                    {
+                        // Recursive applications find the instance while it's expanded
+                        self.instances.insert(instance.clone(), json!({}));
+                        let bindings = params.into_iter().zip(args).collect();
+                        expanding.push(key);
+                        let mut schema = self.expand(substitute(template, &bindings), expanding);
+                        expanding.pop();
+                        if let Value::Object(map) = &mut schema {
+                            map.remove("title");
+                        }
+                        self.instances.insert(instance.clone(), schema);
+                        format!("#/$defs/{instance}")
+                    }
+                    _ => {
+                        let rel = schema_ref_path(&self.module_path, &key.0);
+                        format!("{rel}#/$defs/{}", key.1)
+                    }
+                };
+                // Annotations and docs of the application apply on top of the instance
+                with_keywords(json!({ "$ref": path }), map)
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| self.expand(item, expanding))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
     fn binary_to_schema(&self, op: BinaryExprType, left: &Expr, right: &Expr) -> Option<Value> {
         let left_schema = self.expr_to_schema(left);
         let right_schema = self.expr_to_schema(right);
@@ -360,6 +638,103 @@ impl JsonSchemaGenerator {
     }
 }
 
+// ── Generic placeholders ────────────────────────────────────────────
+
+/// Keys of the placeholders generic types are converted with, none of them is written out.
+const PARAM: &str = "$xenoParam";
+const APPLY: &str = "$xenoApply";
+/// Module declaring the applied type
+const MODULE: &str = "$xenoModule";
+const ARGS: &str = "$xenoArgs";
+/// Name of a builtin type argument, for the name of the instance
+const ARG_NAME: &str = "$xenoName";
+
+fn placeholder(key: &str, name: &str) -> Value {
+    let mut map = Map::new();
+    map.insert(key.to_string(), json!(name));
+    Value::Object(map)
+}
+
+/// Replaces the parameter placeholders of a generic schema with the bound schemas,
+/// unbound parameters accept anything.
+fn substitute(schema: Value, bindings: &HashMap<String, Value>) -> Value {
+    match schema {
+        Value::Object(mut map) => match map.remove(PARAM) {
+            Some(Value::String(param)) => {
+                let bound = bindings.get(&param).cloned().unwrap_or_else(|| json!({}));
+                with_keywords(bound, map)
+            }
+            _ => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, substitute(value, bindings)))
+                    .collect(),
+            ),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| substitute(item, bindings))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Whether a schema has parameter placeholders left.
+fn has_params(schema: &Value) -> bool {
+    match schema {
+        Value::Object(map) => map.contains_key(PARAM) || map.values().any(has_params),
+        Value::Array(items) => items.iter().any(has_params),
+        _ => false,
+    }
+}
+
+/**
+Name of the instance of a generic type for some arguments, like `Page_User` for `Page<User>`.
+Arguments are named by the type they reference, other ones by a hash of their schema.
+*/
+fn instance_name(name: &str, args: &[Value]) -> String {
+    let mut parts = vec![name.to_string()];
+    for arg in args {
+        let named = arg.get(ARG_NAME).and_then(Value::as_str).or_else(|| {
+            let map = arg.as_object().filter(|map| map.len() == 1)?;
+            map.get("$ref")?.as_str()?.rsplit('/').next()
+        });
+        parts.push(match named {
+            Some(named) => named.to_string(),
+            None => format!("{:x}", calculate_hash(arg.to_string())),
+        });
+    }
+    parts.join("_")
+}
+
+/// Removes the names builtin arguments were given for their instances.
+fn without_names(schema: Value) -> Value {
+    match schema {
+        Value::Object(mut map) => {
+            map.remove(ARG_NAME);
+            Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, without_names(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(without_names).collect()),
+        other => other,
+    }
+}
+
+/// Adds the keywords a placeholder was annotated with to the schema replacing it.
+fn with_keywords(schema: Value, keywords: Map<String, Value>) -> Value {
+    match schema {
+        Value::Object(mut map) => {
+            map.extend(keywords);
+            Value::Object(map)
+        }
+        other => other,
+    }
+}
+
 // ── Schema combination & annotations ────────────────────────────────
 
 /// Combines multiple alternative schemas: 0 → permissive, 1 → itself,
@@ -579,6 +954,10 @@ fn module_path_parts(module_path: &str) -> Vec<&str> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+    use xenomorph_common::module::types::{DeclarationInfo, FieldInfo};
+    use xenomorph_common::module::XenoRegistry;
+    use xenomorph_common::semantic::Analyzer;
 
     #[test]
     fn test_builtin_string_mapping() {
@@ -630,6 +1009,249 @@ mod tests {
         assert_eq!(builtin_to_schema("MyCustomType"), None);
     }
 
+    #[test]
+    fn test_generic_declaration_and_application() {
+        let tokens = xenomorph_common::lexer::Lexer::tokenize(
+            "type Users = Page<User> @minlen(1); type Page<T> = { items: T[], next: Cursor<T> }; \
+             type Cursor<T> = { last: T, page: Page<T> }; type Names = Page<string>;",
+        )
+        .0;
+        let (ast, errors) = xenomorph_common::parser::Parser::parse(&tokens);
+        assert!(errors.is_empty());
+
+        let mut generator = JsonSchemaGenerator::new();
+        generator.on_before_ast(&ast, &mut Vec::new());
+
+        // Generic declarations accept anything for their parameters
+        let page = &generator.defs["Page"];
+        assert_eq!(page["title"], json!("Page"));
+        assert_eq!(page["properties"]["items"]["items"], json!({}));
+        assert_eq!(
+            page["properties"]["next"],
+            json!({ "$ref": "#/$defs/Cursor" })
+        );
+
+        let users = &generator.defs["Users"];
+        assert_eq!(users["$ref"], json!("#/$defs/Page_User"));
+        assert_eq!(users["title"], json!("Users"));
+        assert_eq!(users["minLength"], json!(1));
+
+        let page_user = &generator.defs["Page_User"];
+        assert!(page_user.get("title").is_none());
+        assert_eq!(
+            page_user["properties"]["items"]["items"],
+            json!({ "$ref": "#/$defs/User" })
+        );
+        assert_eq!(
+            page_user["properties"]["next"],
+            json!({ "$ref": "#/$defs/Cursor_User" })
+        );
+        // Recursive applications reference the instance being defined
+        let cursor_user = &generator.defs["Cursor_User"]["properties"];
+        assert_eq!(cursor_user["last"], json!({ "$ref": "#/$defs/User" }));
+        assert_eq!(cursor_user["page"], json!({ "$ref": "#/$defs/Page_User" }));
+
+        assert_eq!(
+            generator.defs["Page_string"]["properties"]["items"]["items"],
+            json!({ "type": "string" })
+        );
+        assert!(!generator
+            .defs
+            .values()
+            .any(|d| d.to_string().contains("$xeno")));
+    }
+
+    #[test]
+    fn test_imported_generic_instances_validate_their_arguments() {
+        let root = std::env::temp_dir().join(format!("xeno-json-schema-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("models")).unwrap();
+        fs::write(
+            root.join("models/page.xen"),
+            "/** A page of results */\ntype Page<T> = { items: T[], next: Page<T> | bool };\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("users.xen"),
+            "import models/page { Page };\ntype User = { name: string };\n\
+             type Users = Page<User>;\ntype Names = Page<string>;\n",
+        )
+        .unwrap();
+
+        let registry = XenoRegistry {
+            module_cache: Default::default(),
+            importers: Default::default(),
+            build_cache: None,
+            root: root.clone(),
+            entry: "users".to_string(),
+            plugins: Box::leak(Box::default()),
+            analyzer: Analyzer::new(false, &[]),
+        };
+        let errors = registry.load_module(&["users"], true, None);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        // The generator sees the module as the analyzer shows it, without writing it out
+        let mut generator = JsonSchemaGenerator::new();
+        generator.on_before_module(&registry.scope_info("users").unwrap());
+        registry.with_module("users", |_, ast, _| {
+            generator.on_before_ast(ast, &mut Vec::new())
+        });
+        let document = json!({ "$schema": DRAFT, "$defs": generator.defs });
+
+        let defs = &document["$defs"];
+        assert_eq!(defs["Users"]["$ref"], json!("#/$defs/Page_User"));
+        assert!(defs["Page_User"].get("title").is_none());
+        assert_eq!(defs["Page_User"]["description"], json!("A page of results"));
+        assert_eq!(
+            defs["Page_User"]["properties"]["next"]["anyOf"][0],
+            json!({ "$ref": "#/$defs/Page_User" })
+        );
+
+        let validator = |root_type: &str| {
+            let mut schema = document.clone();
+            schema["$ref"] = json!(format!("#/$defs/{root_type}"));
+            jsonschema::JSONSchema::options()
+                .with_draft(jsonschema::Draft::Draft202012)
+                .compile(&schema)
+                .unwrap()
+        };
+        let users = validator("Users");
+        assert!(users.is_valid(&json!({
+            "items": [{ "name": "Ada" }],
+            "next": { "items": [{ "name": "Bob" }], "next": false },
+        })));
+        assert!(!users.is_valid(&json!({ "items": [{ "name": 1 }], "next": false })));
+        // The argument is checked in recursive applications too
+        assert!(!users.is_valid(&json!({
+            "items": [],
+            "next": { "items": [{}], "next": false },
+        })));
+
+        let names = validator("Names");
+        assert!(names.is_valid(&json!({ "items": ["Ada"], "next": false })));
+        assert!(!names.is_valid(&json!({ "items": [1], "next": false })));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_member_access_refs_into_properties() {
+        let declaration =
+            |module_path: &str, name: &str, fields: &[(&str, &str)]| DeclarationInfo {
+                name: name.to_string(),
+                module_path: module_path.to_string(),
+                abs_path: PathBuf::new(),
+                docs: None,
+                line: 0,
+                column: 0,
+                name_len: name.len() as u32,
+                generics: Vec::new(),
+                fields: Some(
+                    fields
+                        .iter()
+                        .map(|(name, type_name)| FieldInfo {
+                            name: name.to_string(),
+                            type_name: type_name.to_string(),
+                            line: 0,
+                            column: 0,
+                        })
+                        .collect(),
+                ),
+            };
+        let user = declaration("models/user", "User", &[("_id", "uuid")]);
+        let scope = ScopeInfo {
+            module_path: "models/order".to_string(),
+            abs_path: PathBuf::new(),
+            own_types: vec!["Order".to_string(), "Line".to_string()],
+            imported_types: HashMap::new(),
+            builtin_types: HashSet::new(),
+            known_annotations: HashSet::new(),
+            declarations: HashMap::from([
+                ("User".to_string(), user.clone()),
+                (
+                    "Order".to_string(),
+                    declaration(
+                        "models/order",
+                        "Order",
+                        &[("customer", "User"), ("line", "Line")],
+                    ),
+                ),
+                (
+                    "Line".to_string(),
+                    declaration("models/order", "Line", &[("sku", "string")]),
+                ),
+            ]),
+            imports: HashMap::new(),
+            field_scopes: HashMap::from([(
+                "models/user".to_string(),
+                HashMap::from([("User".to_string(), user)]),
+            )]),
+            generic_declarations: HashMap::new(),
+        };
+        let mut generator = JsonSchemaGenerator::new();
+        generator.on_before_module(&scope);
+
+        assert_eq!(
+            generator.member_to_schema("User", &["_id"]),
+            json!({ "$ref": "./user.schema.json#/$defs/User/properties/_id" })
+        );
+        assert_eq!(
+            generator.member_to_schema("Order", &["customer", "_id"]),
+            json!({ "$ref": "./user.schema.json#/$defs/User/properties/_id" })
+        );
+        assert_eq!(
+            generator.member_to_schema("Order", &["line", "sku"]),
+            json!({ "$ref": "#/$defs/Line/properties/sku" })
+        );
+    }
+
+    #[test]
+    fn test_aliased_import_refs_declared_name() {
+        let mut generator = JsonSchemaGenerator::new();
+        generator.module_path = "models/order".to_string();
+        generator
+            .imported_types
+            .insert("api/user".to_string(), vec!["UserRole".to_string()]);
+        generator.imports.insert(
+            "UserRole".to_string(),
+            ImportedType {
+                module_path: "api/user".to_string(),
+                name: "Role".to_string(),
+            },
+        );
+
+        assert_eq!(
+            generator.ref_for("UserRole"),
+            json!({ "$ref": "../api/user.schema.json#/$defs/Role" })
+        );
+    }
+
+    #[test]
+    fn test_reexported_types_point_at_their_declaration() {
+        let mut generator = JsonSchemaGenerator::new();
+        generator.module_path = "index".to_string();
+        generator
+            .imported_types
+            .insert("common/base".to_string(), vec!["Id".to_string()]);
+        generator.imports.insert(
+            "Id".to_string(),
+            ImportedType {
+                module_path: "common/ids".to_string(),
+                name: "Id".to_string(),
+            },
+        );
+
+        let tokens = xenomorph_common::lexer::Lexer::tokenize("export import common/base;").0;
+        let (ast, errors) = xenomorph_common::parser::Parser::parse(&tokens);
+        assert!(errors.is_empty());
+        generator.on_before_ast(&ast, &mut Vec::new());
+
+        assert_eq!(
+            generator.defs["Id"],
+            json!({ "$ref": "./common/ids.schema.json#/$defs/Id" })
+        );
+    }
+
     #[test]
     fn test_regex_source_extraction() {
         assert_eq!(regex_source("/foo.*/i"), "foo.*");
@@ -7,7 +7,7 @@ use xenomorph_common::parser::{
     AnonymType, BinaryExprType, Declaration, Expr, KeyValExpr, Literal, NumberType,
 };
 use xenomorph_common::plugins::XenoPlugin;
-use xenomorph_common::semantic::{AnalyzerListener, ScopeInfo};
+use xenomorph_common::semantic::{AnalyzerListener, ImportedType, ScopeInfo};
 
 // ── Plugin registration ─────────────────────────────────────────────
 
@@ -20,6 +20,7 @@ static PLUGIN: XenoPlugin = XenoPlugin {
     provide_types: None,
     provide_annotations: None,
     provide_config_schema: Some(provide_config_schema),
+    documentation: None,
     register_generator: Some(create_generator),
     register_analyzer: None,
 };
@@ -58,6 +59,10 @@ struct TsGenerator {
     output_dir: Option<PathBuf>,
     /// Imported types keyed by module path, for generating destructured imports.
     imported_types: HashMap<String, Vec<String>>,
+    /// Where each imported name comes from, for renaming aliased imports.
+    imports: HashMap<String, ImportedType>,
+    /// The .ts file written for the module.
+    written: Option<PathBuf>,
 }
 
 impl TsGenerator {
@@ -68,8 +73,27 @@ impl TsGenerator {
             module_path: String::new(),
             output_dir: None,
             imported_types: HashMap::new(),
+            imports: HashMap::new(),
+            written: None,
         }
     }
+
+    /// `{ A, B as C }` for the names imported from `import_path`, renaming aliased imports.
+    fn specifiers(&self, import_path: &str) -> Option<String> {
+        let names = self.imported_types.get(import_path)?;
+        if names.is_empty() {
+            return None;
+        }
+        let specifiers = names
+            .iter()
+            .map(|local| match self.imports.get(local) {
+                Some(import) if &import.name != local => format!("{} as {local}", import.name),
+                _ => local.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        Some(format!("{{ {specifiers} }}"))
+    }
 }
 
 impl<'src> AnalyzerListener<'src> for TsGenerator {
@@ -85,19 +109,19 @@ impl<'src> AnalyzerListener<'src> for TsGenerator {
         self.abs_path = scope.abs_path.clone();
         self.module_path = scope.module_path.clone();
         self.imported_types = scope.imported_types.clone();
+        self.imports = scope.imports.clone();
         self.out.clear();
         self.out
             .push_str("// Auto-generated by xenomorph-typescript — do not edit.\n\n");
 
-        let mut imports: Vec<_> = scope.imported_types.iter().collect();
-        imports.sort_by(|(left, _), (right, _)| left.cmp(right));
+        let mut import_paths: Vec<_> = scope.imported_types.keys().collect();
+        import_paths.sort();
 
-        for (import_path, names) in imports {
-            if !names.is_empty() {
-                let destructured = names.join(", ");
+        for import_path in import_paths {
+            if let Some(specifiers) = self.specifiers(import_path) {
                 let ts_import_path = ts_import_specifier(&scope.module_path, import_path);
                 self.out.push_str(&format!(
-                    "import type {{ {destructured} }} from \"{ts_import_path}\";\n"
+                    "import type {specifiers} from \"{ts_import_path}\";\n"
                 ));
             }
         }
@@ -111,8 +135,27 @@ impl<'src> AnalyzerListener<'src> for TsGenerator {
         // Generate type declarations
         for decl in ast {
             match decl {
-                Declaration::TypeDecl { docs, name, t } => {
-                    generate_type_decl(&mut self.out, docs, name.v, t);
+                Declaration::TypeDecl {
+                    docs,
+                    name,
+                    generics,
+                    t,
+                } => {
+                    let generics: Vec<&str> = generics.iter().map(|g| g.v).collect();
+                    generate_type_decl(&mut self.out, docs, name.v, &generics, t);
+                }
+                Declaration::Import {
+                    path,
+                    exported: true,
+                    ..
+                } => {
+                    let import_path = path.join("/");
+                    if let Some(specifiers) = self.specifiers(&import_path) {
+                        let ts_import_path = ts_import_specifier(&self.module_path, &import_path);
+                        self.out.push_str(&format!(
+                            "export type {specifiers} from \"{ts_import_path}\";\n"
+                        ));
+                    }
                 }
                 _ => {}
             }
@@ -140,12 +183,23 @@ impl<'src> AnalyzerListener<'src> for TsGenerator {
             Ok(_) => println!("✓ {} → {}", scope.module_path, out_path.display()),
             Err(e) => eprintln!("✗ {} — failed to write: {}", scope.module_path, e),
         }
+        self.written = Some(out_path);
+    }
+
+    fn outputs(&self) -> Vec<PathBuf> {
+        self.written.iter().cloned().collect()
     }
 }
 
 // ── Type declaration generation ─────────────────────────────────────
 
-fn generate_type_decl(out: &mut String, docs: &Option<&str>, name: &str, t: &[Expr]) {
+fn generate_type_decl(
+    out: &mut String,
+    docs: &Option<&str>,
+    name: &str,
+    generics: &[&str],
+    t: &[Expr],
+) {
     let annotations: Vec<String> = t
         .iter()
         .filter_map(|e| match e {
@@ -173,10 +227,17 @@ fn generate_type_decl(out: &mut String, docs: &Option<&str>, name: &str, t: &[Ex
         out.push_str(" */\n");
     }
 
+    // `Page<T>` for generic declarations, a const can't be generic so those skip the Set shortcut
+    let declared_name = if generics.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name}<{}>", generics.join(", "))
+    };
+
     // Single struct → interface
     if type_exprs.len() == 1 {
         if let Expr::Struct(fields) = type_exprs[0] {
-            generate_interface(out, name, fields);
+            generate_interface(out, &declared_name, fields);
             return;
         }
     }
@@ -184,13 +245,13 @@ fn generate_type_decl(out: &mut String, docs: &Option<&str>, name: &str, t: &[Ex
     // Single enum → string union or discriminated union
     if type_exprs.len() == 1 {
         if let Expr::Enum(variants) = type_exprs[0] {
-            generate_enum(out, name, variants);
+            generate_enum(out, &declared_name, variants);
             return;
         }
     }
 
     // Single set of literals → emit const + type
-    if type_exprs.len() == 1 {
+    if type_exprs.len() == 1 && generics.is_empty() {
         if let Expr::Set(inner) = type_exprs[0] {
             if is_all_literals(inner) {
                 let elems: Vec<String> = inner.iter().map(|a| anonym_type_to_ts(a)).collect();
@@ -216,7 +277,7 @@ fn generate_type_decl(out: &mut String, docs: &Option<&str>, name: &str, t: &[Ex
             .collect::<Vec<_>>()
             .join(" | ")
     };
-    out.push_str(&format!("export type {name} = {ts};\n\n"));
+    out.push_str(&format!("export type {declared_name} = {ts};\n\n"));
 }
 
 // ── Interface (struct) generation ───────────────────────────────────
@@ -345,6 +406,20 @@ fn expr_to_ts(expr: &Expr) -> Option<String> {
             let elem_type = builtin_to_ts(type_ident.v);
             format!("{}[]", elem_type)
         }
+        Expr::MemberAccess(base, members) => {
+            let mut ts = base.v.to_string();
+            for member in members {
+                ts.push_str(&format!("[\"{}\"]", member.v));
+            }
+            ts
+        }
+        Expr::TypeApplication(type_ident, args) => {
+            let args: Vec<String> = args.iter().map(anonym_type_to_ts).collect();
+            match type_ident.v {
+                "dict" => format!("Record<{}>", args.join(", ")),
+                other => format!("{}<{}>", builtin_to_ts(other), args.join(", ")),
+            }
+        }
         Expr::List(inner) => {
             if inner.len() == 1 {
                 let elem = anonym_type_to_ts(&inner[0]);
@@ -556,6 +631,79 @@ mod tests {
         assert_eq!(builtin_to_ts("MyCustomType"), "MyCustomType");
     }
 
+    #[test]
+    fn test_generic_declaration_and_application() {
+        let tokens = xenomorph_common::lexer::Lexer::tokenize(
+            "type Page<T> = { items: [T], next: dict<string, T> }; type Users = Page<User>;",
+        )
+        .0;
+        let (ast, errors) = xenomorph_common::parser::Parser::parse(&tokens);
+        assert!(errors.is_empty());
+
+        let mut out = String::new();
+        for decl in &ast {
+            if let Declaration::TypeDecl {
+                docs,
+                name,
+                generics,
+                t,
+            } = decl
+            {
+                let generics: Vec<&str> = generics.iter().map(|g| g.v).collect();
+                generate_type_decl(&mut out, docs, name.v, &generics, t);
+            }
+        }
+
+        assert!(out.contains("export interface Page<T> {"));
+        assert!(out.contains("  next: Record<string, T>;"));
+        assert!(out.contains("export type Users = Page<User>;"));
+    }
+
+    #[test]
+    fn test_member_access_is_indexed_access_type() {
+        let tokens =
+            xenomorph_common::lexer::Lexer::tokenize("type Id = User._id | Org.owner._id;").0;
+        let (ast, errors) = xenomorph_common::parser::Parser::parse(&tokens);
+        assert!(errors.is_empty());
+
+        let mut out = String::new();
+        if let Declaration::TypeDecl { docs, name, t, .. } = &ast[0] {
+            generate_type_decl(&mut out, docs, name.v, &[], t);
+        }
+        assert_eq!(
+            out,
+            "export type Id = User[\"_id\"] | Org[\"owner\"][\"_id\"];\n\n"
+        );
+    }
+
+    #[test]
+    fn test_reexports_are_exported_from_the_facade() {
+        let tokens =
+            xenomorph_common::lexer::Lexer::tokenize("export import common/base { Id as BaseId };")
+                .0;
+        let (ast, errors) = xenomorph_common::parser::Parser::parse(&tokens);
+        assert!(errors.is_empty());
+
+        let mut generator = TsGenerator::new();
+        generator.module_path = "index".to_string();
+        generator
+            .imported_types
+            .insert("common/base".to_string(), vec!["BaseId".to_string()]);
+        generator.imports.insert(
+            "BaseId".to_string(),
+            ImportedType {
+                module_path: "common/base".to_string(),
+                name: "Id".to_string(),
+            },
+        );
+        generator.on_before_ast(&ast, &mut Vec::new());
+
+        assert_eq!(
+            generator.out,
+            "export type { Id as BaseId } from \"./common/base\";\n"
+        );
+    }
+
     #[test]
     fn test_ts_import_specifier_for_sibling_module() {
         assert_eq!(